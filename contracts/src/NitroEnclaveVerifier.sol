@@ -83,6 +83,23 @@ contract NitroEnclaveVerifier is Ownable, INitroEnclaveVerifier {
         delete trustedIntermediateCerts[_certHash];
     }
 
+    /**
+     * @dev Adds intermediate certificates to the trusted set
+     * @param _certHashes Path digests of the certificates to trust
+     * 
+     * Requirements:
+     * - Only callable by contract owner
+     * 
+     * Certificates are otherwise only cached as a side effect of a successful verification.
+     * Registering them up front lets the first proofs for a new chain start from a longer
+     * trusted prefix.
+     */
+    function addTrustedCerts(bytes32[] calldata _certHashes) external onlyOwner {
+        for (uint256 i = 0; i < _certHashes.length; i++) {
+            trustedIntermediateCerts[_certHashes[i]] = true;
+        }
+    }
+
     /**
      * @dev Checks the prefix length of trusted certificates in each provided certificate chain for reports
      * @param _report_certs Array of certificate chains, each containing certificate hashes
@@ -181,6 +198,10 @@ contract NitroEnclaveVerifier is Ownable, INitroEnclaveVerifier {
      * @param journal Verification journal to validate
      * @return Updated journal with final verification result
      * 
+     * A journal proven with certificate validity checks skipped is rejected with
+     * CertValiditySkipped, since neither the chain's validity nor its intermediates could
+     * then be trusted or cached.
+     *
      * This function performs comprehensive validation:
      * 1. Checks if the initial ZK verification was successful
      * 2. Validates the root certificate matches the trusted root
@@ -191,11 +212,17 @@ contract NitroEnclaveVerifier is Ownable, INitroEnclaveVerifier {
      * The timestamp validation converts milliseconds to seconds and checks:
      * - Attestation is not too old (timestamp + maxTimeDiff >= block.timestamp)
      * - Attestation is not from the future (timestamp <= block.timestamp)
+     * A nonzero verifyAtTimestamp, the time the certificates were checked at, must pass the
+     * same window.
      */
     function _verifyJournal(VerifierJournal memory journal) internal returns (VerifierJournal memory) {
         if (journal.result != VerificationResult.Success) {
             return journal;
         }
+        if (journal.skipCertValidity) {
+            journal.result = VerificationResult.CertValiditySkipped;
+            return journal;
+        }
         if (journal.trustedCertsPrefixLen == 0) {
             journal.result = VerificationResult.RootCertNotTrusted;
             return journal;
@@ -220,6 +247,11 @@ contract NitroEnclaveVerifier is Ownable, INitroEnclaveVerifier {
             journal.result = VerificationResult.InvalidTimestamp;
             return journal;
         }
+        uint64 verifyAt = journal.verifyAtTimestamp;
+        if (verifyAt != 0 && (verifyAt + maxTimeDiff < block.timestamp || verifyAt > block.timestamp)) {
+            journal.result = VerificationResult.InvalidTimestamp;
+            return journal;
+        }
         _cacheNewCert(journal);
         return journal;
     }
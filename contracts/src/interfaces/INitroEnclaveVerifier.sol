@@ -37,6 +37,11 @@ struct VerifierInput {
     uint8 trustedCertsPrefixLen;    
     // Raw AWS Nitro Enclave attestation report (COSE_Sign1 format)
     bytes attestationReport;  
+    // Skip checking that every certificate was valid at the attestation timestamp
+    bool skipCertValidity;
+    // If nonzero, check certificate validity at this Unix timestamp (seconds) instead of
+    // the attestation timestamp; it is recorded in the journal and checked on-chain
+    uint64 verifyAtTimestamp;
 }
 
 /**
@@ -50,6 +55,17 @@ struct VerifierJournal {
     uint8 trustedCertsPrefixLen;     
     // Attestation timestamp (Unix timestamp in milliseconds)
     uint64 timestamp;          
+    // Start of the leaf certificate's validity window (Unix timestamp in seconds)
+    uint64 notBefore;
+    // End of the leaf certificate's validity window (Unix timestamp in seconds)
+    uint64 notAfter;
+    // Whether certificate validity was skipped (VerifierInput.skipCertValidity); such
+    // journals are only meaningful off-chain and the verifier contract rejects them with
+    // CertValiditySkipped
+    bool skipCertValidity;
+    // Unix timestamp (seconds) the certificates were checked at, or 0 for the attestation
+    // timestamp (VerifierInput.verifyAtTimestamp)
+    uint64 verifyAtTimestamp;
     // Array of certificate hashes in the chain (root to leaf)
     bytes32[] certs;           
     // User-defined data embedded in the attestation
@@ -118,7 +134,9 @@ enum VerificationResult {
     // One or more intermediate certificates are not trusted
     IntermediateCertsNotTrusted, 
     // Attestation timestamp is outside acceptable range
-    InvalidTimestamp             
+    InvalidTimestamp,
+    // The journal was proven with certificate validity checks skipped
+    CertValiditySkipped
 }
 
 /**
@@ -165,6 +183,15 @@ interface INitroEnclaveVerifier {
      */
     function revokeCert(bytes32 _certHash) external;
 
+    /**
+     * @dev Adds intermediate certificates to the trusted set
+     * @param _certHashes Path digests of the certificates to trust
+     * 
+     * Requirements:
+     * - Only callable by contract owner
+     */
+    function addTrustedCerts(bytes32[] calldata _certHashes) external;
+
     /**
      * @dev Checks how many certificates in each report are trusted
      * @param _report_certs Array of certificate chains, each containing certificate hashes
@@ -3,18 +3,32 @@
 //! This module provides tools for examining the contents of attestation reports,
 //! including the attestation document, certificate chain, and other metadata.
 
-use std::path::PathBuf;
+use std::{collections::BTreeSet, path::PathBuf};
 
 use alloy_primitives::Bytes;
+use anyhow::anyhow;
 use aws_nitro_enclave_attestation_verifier::{stub::Bytes48, AttestationReport};
-use clap::{Args, Subcommand};
-use x509_verifier_rust_crypto::x509_parser::time::ASN1Time;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{Args, Subcommand, ValueEnum};
+use serde_json::json;
+use x509_verifier_rust_crypto::{x509_parser::time::ASN1Time, CertChain, CertError};
+
+use crate::{
+    error::{ErrorCode, WithErrorCode},
+    utils::{read_report, ReportEncoding},
+};
 
 /// Debug subcommands for attestation report analysis.
 #[derive(Subcommand)]
 pub enum DebugCli {
     /// Inspect and display attestation document contents
     Doc(DebugDocCli),
+    /// Export the certificate chain as PEM or DER
+    Cert(DebugCertCli),
+    /// Check whether the report's chain verifies at a given time and trusted length
+    Verify(DebugVerifyCli),
+    /// Compare the PCRs and identity of two reports
+    Diff(DebugDiffCli),
 }
 
 impl DebugCli {
@@ -22,6 +36,9 @@ impl DebugCli {
     pub fn run(&self) -> anyhow::Result<()> {
         match self {
             DebugCli::Doc(cli) => cli.run(),
+            DebugCli::Cert(cli) => cli.run(),
+            DebugCli::Verify(cli) => cli.run(),
+            DebugCli::Diff(cli) => cli.run(),
         }
     }
 }
@@ -32,6 +49,14 @@ pub struct DebugDocCli {
     /// Path to the Nitro Enclave attestation report file
     #[clap(long)]
     report: PathBuf,
+
+    /// How the report file is encoded; `auto` detects raw, base64, PEM or hex
+    #[clap(long, value_enum, default_value_t = ReportEncoding::Auto)]
+    report_encoding: ReportEncoding,
+
+    /// Print the document as a single JSON object to stdout instead of logging it
+    #[clap(long)]
+    json: bool,
 }
 
 impl DebugDocCli {
@@ -45,8 +70,15 @@ impl DebugDocCli {
     /// - Certificate chain information and validity periods
     pub fn run(&self) -> anyhow::Result<()> {
         // Parse the attestation report from file
-        let report = AttestationReport::parse(&std::fs::read(&self.report)?)?;
-        let cert_chain = report.cert_chain()?;
+        let report = read_report(&self.report, self.report_encoding)
+            .and_then(|data| AttestationReport::parse(&data))
+            .code(ErrorCode::Parse)?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&doc_json(&report)?)?);
+            return Ok(());
+        }
+        let bundle_order = report.bundle_order()?;
+        let cert_chain = report.cert_chain_ordered(bundle_order)?;
         let doc = report.doc();
         
         // Display attestation document information
@@ -61,6 +93,9 @@ impl DebugDocCli {
         // Display optional fields if present
         if let Some(data) = &doc.public_key {
             tracing::info!("\tPublicKey: {}", Bytes::copy_from_slice(data));
+            if let Ok(Some(pubkey)) = report.enclave_public_key() {
+                tracing::info!("\tPublicKey algorithm: {:?}", pubkey.algo);
+            }
         }
         if let Some(data) = &doc.user_data {
             tracing::info!("\tUserData: {}", Bytes::copy_from_slice(data));
@@ -80,16 +115,414 @@ impl DebugDocCli {
         
         // Display certificate chain information
         tracing::info!("Cert Chain:");
+        tracing::info!("\tCA bundle order: {:?}", bundle_order);
         let digest = cert_chain.digest();
-        for (idx, cert) in cert_chain.certs.iter().enumerate() {
+        for (idx, (cert, issuer)) in cert_chain.links().enumerate() {
             tracing::info!("\t[{idx}] Digest: {:?}", digest[idx]);
+            tracing::info!("\t    Subject: {}", cert.raw.subject());
+            match issuer {
+                Some(issuer) => tracing::info!("\t    Signed by: {}", issuer.raw.subject()),
+                None => tracing::info!("\t    Signed by: itself"),
+            }
             let (start, end) = cert.validity();
             tracing::info!(
                 "\t    Valid: {start}({}) - {end}({})",
                 start.timestamp(),
                 end.timestamp()
             );
+            let sans = cert.subject_alt_names()?;
+            if !sans.is_empty() {
+                tracing::info!("\t    SAN: {}", sans.join(", "));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Encoding of the certificates written by `debug cert`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CertEncoding {
+    Pem,
+    Der,
+}
+
+impl CertEncoding {
+    fn extension(&self) -> &'static str {
+        match self {
+            CertEncoding::Pem => "pem",
+            CertEncoding::Der => "der",
+        }
+    }
+}
+
+/// Arguments for exporting the certificate chain of an attestation report.
+#[derive(Args)]
+pub struct DebugCertCli {
+    /// Path to the Nitro Enclave attestation report file
+    #[clap(long)]
+    report: PathBuf,
+
+    /// How the report file is encoded; `auto` detects raw, base64, PEM or hex
+    #[clap(long, value_enum, default_value_t = ReportEncoding::Auto)]
+    report_encoding: ReportEncoding,
+
+    /// Encoding of the exported certificates
+    #[clap(long, value_enum, default_value_t = CertEncoding::Pem)]
+    encoding: CertEncoding,
+
+    /// Directory to write one file per certificate into (`cert_0` is the root)
+    ///
+    /// If not specified, the PEM chain is printed to stdout.
+    #[clap(long)]
+    out: Option<PathBuf>,
+}
+
+impl DebugCertCli {
+    /// Writes every certificate in the report's chain, root first, to `--out` or stdout.
+    pub fn run(&self) -> anyhow::Result<()> {
+        let report = read_report(&self.report, self.report_encoding)
+            .and_then(|data| AttestationReport::parse(&data))
+            .code(ErrorCode::Parse)?;
+        let cert_chain = report
+            .bundle_order()
+            .and_then(|order| report.cert_chain_ordered(order))
+            .code(ErrorCode::Parse)?;
+
+        let Some(out) = &self.out else {
+            if self.encoding == CertEncoding::Der {
+                return Err(ErrorCode::Config.wrap(anyhow!("DER output requires --out")));
+            }
+            for cert in &cert_chain.certs {
+                print!("{}", pem_encode(cert.bytes()));
+            }
+            return Ok(());
+        };
+
+        std::fs::create_dir_all(out)?;
+        for (name, data) in export_chain(&cert_chain, self.encoding) {
+            let path = out.join(name);
+            std::fs::write(&path, data)?;
+            tracing::info!("wrote {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// Returns a numbered file name and the encoded bytes for every cert in the chain,
+/// root first.
+fn export_chain(cert_chain: &CertChain, encoding: CertEncoding) -> Vec<(String, Vec<u8>)> {
+    cert_chain
+        .certs
+        .iter()
+        .enumerate()
+        .map(|(idx, cert)| {
+            let data = match encoding {
+                CertEncoding::Pem => pem_encode(cert.bytes()).into_bytes(),
+                CertEncoding::Der => cert.bytes().to_vec(),
+            };
+            (format!("cert_{}.{}", idx, encoding.extension()), data)
+        })
+        .collect()
+}
+
+/// Wraps a DER certificate in a PEM `CERTIFICATE` block with 64-column base64 lines.
+fn pem_encode(der: &[u8]) -> String {
+    let encoded = STANDARD.encode(der);
+    let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str("-----END CERTIFICATE-----\n");
+    pem
+}
+
+/// Arguments for checking a report's chain at a given time and trusted length.
+#[derive(Args)]
+pub struct DebugVerifyCli {
+    /// Path to the Nitro Enclave attestation report file
+    #[clap(long)]
+    report: PathBuf,
+
+    /// How the report file is encoded; `auto` detects raw, base64, PEM or hex
+    #[clap(long, value_enum, default_value_t = ReportEncoding::Auto)]
+    report_encoding: ReportEncoding,
+
+    /// Number of certs from the root that are trusted without checking their signature
+    #[clap(long, default_value_t = 1)]
+    trusted_len: usize,
+
+    /// Unix timestamp (seconds) to check the chain at; defaults to the document's own
+    #[clap(long)]
+    at: Option<u64>,
+}
+
+impl DebugVerifyCli {
+    /// Runs [`AttestationReport::authenticate`] and prints whether it passed, along with
+    /// the index of the failing cert when it can be told.
+    pub fn run(&self) -> anyhow::Result<()> {
+        let report = read_report(&self.report, self.report_encoding)
+            .and_then(|data| AttestationReport::parse(&data))
+            .code(ErrorCode::Parse)?;
+        let timestamp = self.at.unwrap_or(report.doc().timestamp / 1000);
+
+        match report.authenticate(self.trusted_len, timestamp) {
+            Ok(_) => {
+                println!("pass: trusted_len={} at={}", self.trusted_len, timestamp);
+                Ok(())
+            }
+            Err(err) => {
+                println!("fail: trusted_len={} at={}", self.trusted_len, timestamp);
+                let cert_chain = report.cert_chain().code(ErrorCode::Parse)?;
+                if let Some(idx) = failing_cert_index(&cert_chain, self.trusted_len, timestamp) {
+                    println!("failing cert: [{}] {}", idx, cert_chain.digest()[idx]);
+                }
+                Err(ErrorCode::Verify.wrap(err))
+            }
+        }
+    }
+}
+
+/// Returns the chain position of the first cert that fails its signature, constraint or
+/// validity check, or `None` if no single cert is to blame (e.g. a bad COSE signature).
+fn failing_cert_index(cert_chain: &CertChain, trusted_len: usize, timestamp: u64) -> Option<usize> {
+    match cert_chain.verify_chain(trusted_len) {
+        Err(CertError::SignatureInvalid {
+            index: Some(index), ..
+        })
+        | Err(CertError::NotCa { index })
+        | Err(CertError::PathLenExceeded { index, .. })
+        | Err(CertError::MissingKeyUsage { index, .. })
+        | Err(CertError::AlgorithmNotAllowed { index, .. })
+        | Err(CertError::KeyIdMismatch { index })
+        | Err(CertError::DuplicateCert { index, .. })
+        | Err(CertError::Revoked { index }) => return Some(index),
+        _ => {}
+    }
+    let time = ASN1Time::from_timestamp(timestamp as i64).ok()?;
+    cert_chain
+        .certs
+        .iter()
+        .position(|cert| cert.check_valid(time).is_err())
+}
+
+/// Arguments for comparing two attestation reports.
+#[derive(Args)]
+pub struct DebugDiffCli {
+    /// Path to the first attestation report
+    #[clap(long)]
+    a: PathBuf,
+
+    /// Path to the second attestation report
+    #[clap(long)]
+    b: PathBuf,
+
+    /// How both report files are encoded; `auto` detects raw, base64, PEM or hex
+    #[clap(long, value_enum, default_value_t = ReportEncoding::Auto)]
+    report_encoding: ReportEncoding,
+}
+
+impl DebugDiffCli {
+    /// Prints the PCRs that differ between `--a` and `--b`, whether their identity fields
+    /// differ and how far apart they were generated.
+    pub fn run(&self) -> anyhow::Result<()> {
+        let parse = |path: &PathBuf| {
+            read_report(path, self.report_encoding)
+                .and_then(|data| AttestationReport::parse(&data))
+                .code(ErrorCode::Parse)
+        };
+        let diff = ReportDiff::new(&parse(&self.a)?, &parse(&self.b)?);
+
+        for (index, a, b) in &diff.pcrs {
+            println!("PCR[{}]:", index);
+            println!("\ta: {}", a.as_deref().unwrap_or("-"));
+            println!("\tb: {}", b.as_deref().unwrap_or("-"));
+        }
+        if diff.pcrs.is_empty() {
+            println!("PCRs: same");
         }
+        let same = |differs: bool| if differs { "differs" } else { "same" };
+        println!("module_id: {}", same(diff.module_id));
+        println!("user_data: {}", same(diff.user_data));
+        println!("public_key: {}", same(diff.public_key));
+        println!("timestamp delta (b - a): {}ms", diff.timestamp_delta);
         Ok(())
     }
 }
+
+/// Differences between two attestation documents, as printed by `debug diff`.
+#[derive(Debug)]
+struct ReportDiff {
+    /// Index and 0x-prefixed values in `a` and `b` of every PCR that differs; `None` when
+    /// a document does not report that PCR
+    pcrs: Vec<(u64, Option<String>, Option<String>)>,
+    module_id: bool,
+    user_data: bool,
+    public_key: bool,
+    /// `b`'s timestamp minus `a`'s, in milliseconds
+    timestamp_delta: i128,
+}
+
+impl ReportDiff {
+    fn new(a: &AttestationReport, b: &AttestationReport) -> Self {
+        let (a, b) = (a.doc(), b.doc());
+        let indices: BTreeSet<u64> = a.pcrs.keys().chain(b.pcrs.keys()).copied().collect();
+        let pcrs = indices
+            .into_iter()
+            .filter(|&index| a.pcr(index) != b.pcr(index))
+            .map(|index| (index, a.pcr_hex(index), b.pcr_hex(index)))
+            .collect();
+        Self {
+            pcrs,
+            module_id: a.module_id != b.module_id,
+            user_data: a.user_data != b.user_data,
+            public_key: a.public_key != b.public_key,
+            timestamp_delta: b.timestamp as i128 - a.timestamp as i128,
+        }
+    }
+}
+
+/// Builds the JSON representation printed by `debug doc --json`.
+///
+/// Binary fields are 0x-prefixed hex, PCRs are keyed by their index and every cert
+/// in the chain (root first) carries its digest and validity window.
+fn doc_json(report: &AttestationReport) -> anyhow::Result<serde_json::Value> {
+    let cert_chain = report.cert_chain_ordered(report.bundle_order()?)?;
+    let doc = report.doc();
+
+    let pcrs = doc
+        .pcrs
+        .iter()
+        .map(|(k, v)| (k.to_string(), json!(Bytes48::from(v).to_string())))
+        .collect::<serde_json::Map<_, _>>();
+    let digest = cert_chain.digest();
+    let certs = cert_chain
+        .certs
+        .iter()
+        .enumerate()
+        .map(|(idx, cert)| {
+            let (start, end) = cert.validity();
+            json!({
+                "digest": digest[idx].to_string(),
+                "not_before": start.timestamp(),
+                "not_after": end.timestamp(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(json!({
+        "module_id": doc.module_id,
+        "timestamp": doc.timestamp,
+        "digest": doc.digest,
+        "pcrs": pcrs,
+        "public_key": hex_opt(&doc.public_key),
+        "user_data": hex_opt(&doc.user_data),
+        "nonce": hex_opt(&doc.nonce),
+        "certs": certs,
+    }))
+}
+
+fn hex_opt(data: &Option<impl AsRef<[u8]>>) -> Option<String> {
+    data.as_ref()
+        .map(|n| Bytes::copy_from_slice(n.as_ref()).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_verifier_rust_crypto::{x509_parser::pem::parse_x509_pem, Cert};
+
+    use super::*;
+
+    #[test]
+    fn test_doc_json() {
+        let report =
+            AttestationReport::parse(&std::fs::read("../../samples/attestation_1.report").unwrap())
+                .unwrap();
+        let value = doc_json(&report).unwrap();
+
+        let pcrs = value["pcrs"].as_object().unwrap();
+        assert_eq!(pcrs.len(), report.doc().pcrs.len());
+        for (index, pcr) in pcrs {
+            let pcr = pcr.as_str().unwrap();
+            assert_eq!(pcr.len(), 2 + 2 * 48, "PCR[{}]", index);
+            assert!(pcr.starts_with("0x"), "PCR[{}]", index);
+            assert!(pcr[2..].bytes().all(|c| c.is_ascii_hexdigit()), "PCR[{}]", index);
+        }
+        assert_eq!(pcrs["3"].as_str(), report.doc().pcr_hex(3).as_deref());
+        assert_eq!(value["nonce"], "0x31323334");
+
+        let certs = value["certs"].as_array().unwrap();
+        assert_eq!(certs.len(), 5);
+        for cert in certs {
+            assert!(cert["not_before"].as_i64().unwrap() < cert["not_after"].as_i64().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_failing_cert_index() {
+        let report =
+            AttestationReport::parse(&std::fs::read("../../samples/attestation_1.report").unwrap())
+                .unwrap();
+        let cert_chain = report.cert_chain().unwrap();
+
+        // at the document's own time the chain verifies
+        let timestamp = report.doc().timestamp / 1000;
+        report.authenticate(1, timestamp).unwrap();
+        assert_eq!(failing_cert_index(&cert_chain, 1, timestamp), None);
+
+        // a year later the root is still valid but the first intermediate has expired
+        let expired = timestamp + 365 * 24 * 3600;
+        assert!(report.authenticate(1, expired).is_err());
+        assert_eq!(failing_cert_index(&cert_chain, 1, expired), Some(1));
+    }
+
+    #[test]
+    fn test_export_chain() {
+        let report =
+            AttestationReport::parse(&std::fs::read("../../samples/attestation_1.report").unwrap())
+                .unwrap();
+        let cert_chain = report.cert_chain().unwrap();
+
+        let der = export_chain(&cert_chain, CertEncoding::Der);
+        assert_eq!(der.len(), 5);
+        assert_eq!(der[0].0, "cert_0.der");
+        for ((_, data), cert) in der.iter().zip(&cert_chain.certs) {
+            let parsed = Cert::parse_der(data).unwrap();
+            assert_eq!(parsed.digest(), cert.digest());
+        }
+
+        let pem = export_chain(&cert_chain, CertEncoding::Pem);
+        assert_eq!(pem[4].0, "cert_4.pem");
+        for ((_, data), cert) in pem.iter().zip(&cert_chain.certs) {
+            let (_, pem) = parse_x509_pem(data).unwrap();
+            assert_eq!(pem.label, "CERTIFICATE");
+            assert_eq!(pem.contents, cert.bytes());
+            Cert::parse_der(&pem.contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_report_diff() {
+        let read = |name: &str| {
+            let path = format!("../verifier/samples/{}.report", name);
+            AttestationReport::parse(&std::fs::read(path).unwrap()).unwrap()
+        };
+        // signed by the same test leaf, 90s apart and with a different PCR3
+        let a = read("pcr_diff_a");
+        let b = read("pcr_diff_b");
+
+        let diff = ReportDiff::new(&a, &b);
+        assert_eq!(diff.pcrs.len(), 1);
+        let (index, pcr_a, pcr_b) = &diff.pcrs[0];
+        assert_eq!(*index, 3);
+        assert_eq!(pcr_a.as_deref(), Some(&*format!("0x{}", "00".repeat(48))));
+        assert_eq!(pcr_b.as_deref(), Some(&*format!("0x{}", "11".repeat(48))));
+        assert!(!diff.module_id && !diff.user_data && !diff.public_key);
+        assert_eq!(diff.timestamp_delta, 90_000);
+        assert_eq!(ReportDiff::new(&b, &a).timestamp_delta, -90_000);
+
+        let same = ReportDiff::new(&a, &a);
+        assert!(same.pcrs.is_empty());
+        assert_eq!(same.timestamp_delta, 0);
+    }
+}
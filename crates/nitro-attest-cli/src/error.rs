@@ -0,0 +1,137 @@
+//! Machine-readable error reporting.
+//!
+//! Commands attach an [`ErrorCode`] to their failures with `.code(ErrorCode::..)`, and
+//! `--error-format json` prints the code together with the error chain so that wrapping
+//! tools can branch on the kind of failure. The code does not change the error message.
+
+use std::fmt;
+
+use clap::ValueEnum;
+use serde_json::json;
+
+/// How a failing command reports its error on stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// Human readable error chain
+    #[default]
+    Text,
+    /// A single JSON object with `code`, `message` and `context`
+    Json,
+}
+
+/// Coarse category of a CLI failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// An input file (report, proof) could not be read or decoded
+    Parse,
+    /// A report or proof was rejected by verification
+    Verify,
+    /// An RPC endpoint or remote prover could not be reached
+    Network,
+    /// Missing or conflicting arguments
+    Config,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Parse => "parse",
+            ErrorCode::Verify => "verify",
+            ErrorCode::Network => "network",
+            ErrorCode::Config => "config",
+        }
+    }
+
+    /// Tags `err` with this code.
+    pub fn wrap(self, err: impl Into<anyhow::Error>) -> anyhow::Error {
+        CodedError {
+            code: self,
+            error: err.into(),
+        }
+        .into()
+    }
+}
+
+/// An error tagged with an [`ErrorCode`]. It displays as the wrapped error and continues
+/// with its causes, so the tag is invisible in the error chain.
+#[derive(Debug)]
+struct CodedError {
+    code: ErrorCode,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for CodedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Attaches an [`ErrorCode`] to the error of a `Result`.
+pub trait WithErrorCode<T> {
+    fn code(self, code: ErrorCode) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> WithErrorCode<T> for Result<T, E> {
+    fn code(self, code: ErrorCode) -> anyhow::Result<T> {
+        self.map_err(|err| code.wrap(err))
+    }
+}
+
+/// Builds the object printed by `--error-format json`.
+///
+/// `code` is the outermost [`ErrorCode`] attached anywhere in the chain of `err`, or
+/// `"other"` if there is none. `message` is the top-level error and `context` lists its
+/// causes, outermost first.
+pub fn error_json(err: &anyhow::Error) -> serde_json::Value {
+    let code = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<CodedError>())
+        .map_or("other", |coded| coded.code.as_str());
+    json!({
+        "code": code,
+        "message": err.to_string(),
+        "context": err.chain().skip(1).map(|cause| cause.to_string()).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+    use aws_nitro_enclave_attestation_verifier::AttestationReport;
+
+    use super::*;
+
+    #[test]
+    fn test_error_json() {
+        let data = std::fs::read("../../samples/attestation_1.report").unwrap();
+        let report = AttestationReport::parse(&data).unwrap();
+        // long after the leaf cert expired
+        let untagged = report.authenticate(1, 4102444800).unwrap_err();
+        let err = report
+            .authenticate(1, 4102444800)
+            .code(ErrorCode::Verify)
+            .unwrap_err();
+        let value = error_json(&err);
+        assert_eq!(value["code"], "verify");
+        assert_eq!(value["message"], untagged.to_string());
+        let chain: Vec<String> = untagged.chain().skip(1).map(|c| c.to_string()).collect();
+        assert_eq!(value["context"], json!(chain));
+        assert_eq!(format!("{:#}", err), format!("{:#}", untagged));
+
+        let err = AttestationReport::parse(b"not a report")
+            .code(ErrorCode::Parse)
+            .context("read report.bin")
+            .unwrap_err();
+        let value = error_json(&err);
+        assert_eq!(value["code"], "parse");
+        assert_eq!(value["message"], "read report.bin");
+
+        assert_eq!(error_json(&anyhow!("boom"))["code"], "other");
+    }
+}
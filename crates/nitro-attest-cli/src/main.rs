@@ -8,13 +8,15 @@
 //! - Verify proofs on-chain using smart contracts
 //! - Aggregate multiple proofs together
 //! - Upload ZK programs for remote execution
+//! - Export the program ids for the verifier contract
 //! - Debug and inspect attestation reports
+//! - Verify attestation reports offline
 //!
 //! ## Examples
 //!
 //! Generate a proof from an attestation report:
 //! ```bash
-//! nitro-attest-cli prove --report attestation.report --sp1 --out proof.json
+//! nitro-attest-cli prove --report attestation.report --backend sp1 --out proof.json
 //! ```
 //!
 //! Verify a proof on-chain:
@@ -23,13 +25,18 @@
 //! ```
 
 use clap::{Parser, Subcommand};
-use tracing_subscriber::{filter::LevelFilter, EnvFilter};
+use error::ErrorFormat;
+use tracing_subscriber::{filter::LevelFilter, fmt::writer::BoxMakeWriter, EnvFilter};
+use utils::OutputFormat;
 
 mod debug;
+mod error;
+mod program_id;
 mod proof;
 mod prove;
 mod upload;
 mod utils;
+mod verify;
 
 /// Main CLI application structure for Nitro Attestation CLI
 #[derive(Parser)]
@@ -39,6 +46,14 @@ mod utils;
 struct NitroAttestCli {
     #[command(subcommand)]
     command: Commands,
+
+    /// How to report a failure on stderr; `json` prints an object with an error code
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    error_format: ErrorFormat,
+
+    /// How to print generated proofs on stdout; `json` prints only the proof JSON
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
 }
 
 /// Available subcommands for the CLI
@@ -53,27 +68,46 @@ enum Commands {
     
     /// Upload ZK programs for remote execution
     Upload(upload::UploadCli),
+
+    /// Print the program ids to configure on the verifier contract
+    ProgramId(program_id::ProgramIdCli),
     
     /// Debug utilities for inspecting attestation reports
     #[command(subcommand)]
     Debug(debug::DebugCli),
+
+    /// Verify an attestation report locally, without a proof or contract
+    VerifyOffline(verify::VerifyOfflineCli),
 }
 
 fn main() -> anyhow::Result<()> {
+    let cli = NitroAttestCli::parse();
+
+    // keep stdout parseable in json mode
+    let log_writer = match cli.format {
+        OutputFormat::Text => BoxMakeWriter::new(std::io::stdout),
+        OutputFormat::Json => BoxMakeWriter::new(std::io::stderr),
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
             EnvFilter::builder()
                 .with_default_directive(LevelFilter::INFO.into())
                 .from_env_lossy(),
         )
+        .with_writer(log_writer)
         .init();
 
-    let cli = NitroAttestCli::parse();
-    match &cli.command {
-        Commands::Prove(cli) => cli.run()?,
-        Commands::Debug(cli) => cli.run()?,
-        Commands::Upload(cli) => cli.run()?,
-        Commands::Proof(cli) => cli.run()?,
+    let result = match &cli.command {
+        Commands::Prove(prove) => prove.run(cli.format),
+        Commands::Debug(cli) => cli.run(),
+        Commands::Upload(cli) => cli.run(),
+        Commands::ProgramId(cli) => cli.run(),
+        Commands::Proof(proof) => proof.run(cli.format),
+        Commands::VerifyOffline(cli) => cli.run(),
+    };
+    if let (Err(err), ErrorFormat::Json) = (&result, cli.error_format) {
+        eprintln!("{}", error::error_json(err));
+        std::process::exit(1);
     }
-    Ok(())
+    result
 }
@@ -0,0 +1,41 @@
+//! Program identifier export.
+//!
+//! Prints the ids the verifier contract's `ZkCoProcessorConfig` must be set to for the
+//! selected zkVM, without generating a proof or uploading the programs.
+
+use std::path::PathBuf;
+
+use aws_nitro_enclave_attestation_prover::program_ids;
+use clap::Args;
+
+use crate::{
+    error::{ErrorCode, WithErrorCode},
+    utils::ProverArgs,
+};
+
+/// Command-line arguments for printing the program identifiers.
+#[derive(Args)]
+pub struct ProgramIdCli {
+    #[clap(flatten)]
+    prover: ProverArgs,
+
+    /// Output file path for storing the program identifier
+    #[clap(long)]
+    out: Option<PathBuf>,
+}
+
+impl ProgramIdCli {
+    /// Prints the verifier, verifier proof and aggregator ids as JSON, in the same format
+    /// `upload --out` writes. The ids are computed without constructing a prover.
+    pub fn run(&self) -> anyhow::Result<()> {
+        let config = self.prover.prover_config().code(ErrorCode::Config)?;
+        let zk = config.system.zktype();
+        let program_id = program_ids(zk)?.encode_json(zk)?;
+
+        if let Some(out) = &self.out {
+            std::fs::write(out, &program_id)?;
+        }
+        println!("{}", String::from_utf8(program_id)?);
+        Ok(())
+    }
+}
@@ -3,36 +3,51 @@
 //! This module provides functionality for working with generated proofs including
 //! on-chain verification, proof aggregation, and composite proof generation.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use aws_nitro_enclave_attestation_prover::{
-    set_prover_dev_mode, utils::block_on, OnchainProof, ProofType,
+    set_prover_dev_mode, utils::block_on, NitroEnclaveProver, OnchainProof, ProofType,
 };
 use clap::{Args, Subcommand};
 
-use crate::utils::{ContractArgs, ProverArgs};
+use crate::{
+    error::{ErrorCode, WithErrorCode},
+    utils::{read_report, ContractArgs, OutputFormat, ProverArgs, ReportEncoding},
+};
 
 /// Subcommands for proof-related operations.
 #[derive(Subcommand)]
 pub enum ProofCli {
     /// Verify a proof on-chain using smart contract
     VerifyOnChain(ProofVerifyOnChainCli),
+
+    /// Verify a proof locally, without a contract or RPC endpoint
+    VerifyLocal(ProofVerifyLocalCli),
     
     /// Generate composite proofs for single attestation reports  
     GenComposite(ProofGenCompositeCli),
     
     /// Aggregate multiple proofs into a single proof
     Aggregate(ProofAggregateCli),
+
+    /// Print the ABI-encoded verify/batchVerify calldata of a proof
+    Calldata(ProofCalldataCli),
+
+    /// Print the program ids configured on the contract for every zk coprocessor
+    ShowConfig(ProofShowConfigCli),
 }
 
 impl ProofCli {
     /// Executes the appropriate proof subcommand.
-    pub fn run(&self) -> anyhow::Result<()> {
+    pub fn run(&self, format: OutputFormat) -> anyhow::Result<()> {
         match self {
             ProofCli::VerifyOnChain(cli) => cli.run(),
-            ProofCli::Aggregate(cli) => cli.run(),
-            ProofCli::GenComposite(cli) => cli.run(),
+            ProofCli::VerifyLocal(cli) => cli.run(),
+            ProofCli::Aggregate(cli) => cli.run(format),
+            ProofCli::GenComposite(cli) => cli.run(format),
+            ProofCli::Calldata(cli) => cli.run(),
+            ProofCli::ShowConfig(cli) => cli.run(),
         }
     }
 }
@@ -44,6 +59,11 @@ pub struct ProofVerifyOnChainCli {
     #[clap(long)]
     proof: PathBuf,
 
+    /// Send the verification as a transaction instead of an `eth_call`, so the contract
+    /// records its result. Requires --private-key.
+    #[clap(long)]
+    submit: bool,
+
     /// Smart contract configuration for verification
     #[clap(flatten)]
     contract: ContractArgs,
@@ -56,29 +76,122 @@ impl ProofVerifyOnChainCli {
     /// ensuring the proof was generated correctly and corresponds to valid
     /// Nitro Enclave attestation data.
     pub fn run(&self) -> anyhow::Result<()> {
+        if self.submit && self.contract.private_key.is_none() {
+            return Err(ErrorCode::Config.wrap(anyhow!(
+                "--submit requires --private-key to sign the transaction."
+            )));
+        }
+
         // Ensure contract configuration is provided
         let contract = self.contract.stub()?.ok_or_else(|| {
-            anyhow!("No contract specified. Use --contract, --rpc-url to specify the contract.")
+            ErrorCode::Config.wrap(anyhow!(
+                "No contract specified. Use --contract, --rpc-url to specify the contract."
+            ))
         })?;
 
         // Load and parse the proof file
-        let result = OnchainProof::decode_json(&std::fs::read(&self.proof)?)?;
+        let result = read_proof(&self.proof)?;
         
         // Validate that the proof contains on-chain verification data
         if result.onchain_proof.len() == 0 {
-            return Err(anyhow::anyhow!(
+            return Err(ErrorCode::Parse.wrap(anyhow::anyhow!(
                 "Proof does not contain an on-chain proof, unable to submit."
-            ));
+            )));
+        }
+
+        if self.submit {
+            let tx_hash = block_on(contract.submit_proof(&result)).code(ErrorCode::Verify)?;
+            println!("submitted: {}", tx_hash);
+            return Ok(());
         }
 
         // Verify proof to contract for verification
-        let result = block_on(contract.verify_proof(&result))?;
+        let result = block_on(contract.verify_proof(&result)).code(ErrorCode::Verify)?;
         dbg!(result);
 
         Ok(())
     }
 }
 
+/// Arguments for verifying proofs locally.
+#[derive(Args)]
+pub struct ProofVerifyLocalCli {
+    /// Path to the proof file to verify
+    #[clap(long)]
+    proof: PathBuf,
+
+    /// Verify a proof generated in development mode
+    #[arg(long, default_value = "false", env = "DEV_MODE")]
+    dev: bool,
+}
+
+impl ProofVerifyLocalCli {
+    /// Checks the proof with the program of the zkVM it was generated by, selected from
+    /// its `zktype`.
+    pub fn run(&self) -> anyhow::Result<()> {
+        set_prover_dev_mode(self.dev);
+        let proof = read_proof(&self.proof)?;
+
+        let prover = NitroEnclaveProver::for_proof(&proof, None).code(ErrorCode::Config)?;
+        prover.verify_local(&proof).code(ErrorCode::Verify)?;
+        println!("proof is valid");
+
+        Ok(())
+    }
+}
+
+/// Arguments for exporting the contract calldata of a proof.
+#[derive(Args)]
+pub struct ProofCalldataCli {
+    /// Path to the proof file
+    #[clap(long)]
+    proof: PathBuf,
+}
+
+impl ProofCalldataCli {
+    /// Prints the hex-encoded `verify`/`batchVerify` calldata to stdout, so the proof can
+    /// be submitted with external tooling.
+    pub fn run(&self) -> anyhow::Result<()> {
+        let proof = read_proof(&self.proof)?;
+        if proof.onchain_proof.len() == 0 {
+            return Err(ErrorCode::Parse.wrap(anyhow!(
+                "Proof does not contain an on-chain proof, unable to build calldata."
+            )));
+        }
+        println!("{}", proof.verify_calldata());
+        Ok(())
+    }
+}
+
+/// Arguments for showing the contract's zk coprocessor configuration.
+#[derive(Args)]
+pub struct ProofShowConfigCli {
+    /// Smart contract configuration
+    #[clap(flatten)]
+    contract: ContractArgs,
+}
+
+impl ProofShowConfigCli {
+    /// Prints the on-chain program ids, to compare against the local ones when diagnosing
+    /// program ID mismatches.
+    pub fn run(&self) -> anyhow::Result<()> {
+        let contract = self.contract.stub()?.ok_or_else(|| {
+            ErrorCode::Config.wrap(anyhow!(
+                "No contract specified. Use --contract, --rpc-url to specify the contract."
+            ))
+        })?;
+        let configs = block_on(contract.all_zk_configs()).code(ErrorCode::Network)?;
+        for (zk, config) in configs {
+            println!("{:?}:", zk);
+            println!("\tverifierId: {}", config.verifierId);
+            println!("\tverifierProofId: {}", config.verifierProofId);
+            println!("\taggregatorId: {}", config.aggregatorId);
+            println!("\tzkVerifier: {}", config.zkVerifier);
+        }
+        Ok(())
+    }
+}
+
 /// Arguments for aggregating multiple proofs into a single proof.
 #[derive(Args)]
 pub struct ProofAggregateCli {
@@ -104,20 +217,20 @@ impl ProofAggregateCli {
     /// 
     /// Combines multiple individual proofs into a single aggregated proof,
     /// enabling efficient batch verification of multiple attestation reports.
-    pub fn run(&self) -> anyhow::Result<()> {
+    pub fn run(&self, format: OutputFormat) -> anyhow::Result<()> {
         set_prover_dev_mode(self.prover.dev);
         
         // Validate that proof files are provided
         if self.proof.is_empty() {
-            return Err(anyhow!(
+            return Err(ErrorCode::Config.wrap(anyhow!(
                 "No proof files provided. Use --proof to specify the proof files."
-            ));
+            )));
         }
 
         // Load and extract raw proofs from all proof files
         let mut proofs = Vec::with_capacity(self.proof.len());
         for proof_file in &self.proof {
-            let proof = OnchainProof::decode_json(&std::fs::read(proof_file)?)?;
+            let proof = read_proof(proof_file)?;
             proofs.push(proof.raw_proof);
         }
 
@@ -134,7 +247,7 @@ impl ProofAggregateCli {
         if let Some(out) = &self.out {
             std::fs::write(out, aggregated_proof.encode_json()?)?;
         }
-        println!("proof: {:?}", aggregated_proof);
+        format.print_proof(&aggregated_proof)?;
 
         Ok(())
     }
@@ -147,6 +260,10 @@ pub struct ProofGenCompositeCli {
     #[arg(long)]
     report: PathBuf,
 
+    /// How the report file is encoded; `auto` detects raw, base64, PEM or hex
+    #[arg(long, value_enum, default_value_t = ReportEncoding::Auto)]
+    report_encoding: ReportEncoding,
+
     /// Output file path for the composite proof
     #[arg(long)]
     out: Option<PathBuf>,
@@ -165,11 +282,11 @@ impl ProofGenCompositeCli {
     /// 
     /// Creates a composite proof structure that can be used for more
     /// complex verification scenarios or as input to aggregation.
-    pub fn run(&self) -> anyhow::Result<()> {
+    pub fn run(&self, format: OutputFormat) -> anyhow::Result<()> {
         set_prover_dev_mode(self.prover.dev);
         
         // Read the attestation report file
-        let raw_report = std::fs::read(&self.report)?;
+        let raw_report = read_report(&self.report, self.report_encoding).code(ErrorCode::Parse)?;
 
         // Initialize prover and contract interface
         let contract = self.contract.stub()?;
@@ -184,8 +301,17 @@ impl ProofGenCompositeCli {
         if let Some(out) = &self.out {
             std::fs::write(out, composite_proof.encode_json()?)?;
         }
-        println!("proof: {:?}", composite_proof);
+        format.print_proof(&composite_proof)?;
 
         Ok(())
     }
 }
+
+/// Reads and decodes a proof file, tagging failures as [`ErrorCode::Parse`].
+fn read_proof(path: &Path) -> anyhow::Result<OnchainProof> {
+    std::fs::read(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|data| OnchainProof::decode_json(&data))
+        .with_context(|| format!("read proof {}", path.display()))
+        .code(ErrorCode::Parse)
+}
@@ -5,11 +5,15 @@
 
 use std::path::PathBuf;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use aws_nitro_enclave_attestation_prover::set_prover_dev_mode;
 use clap::Args;
+use serde_json::json;
 
-use crate::utils::{ContractArgs, ProverArgs};
+use crate::{
+    error::{ErrorCode, WithErrorCode},
+    utils::{read_report, ContractArgs, OutputFormat, ProverArgs, ReportEncoding},
+};
 
 /// Command-line arguments for the prove subcommand.
 /// 
@@ -24,6 +28,21 @@ pub struct ProveCli {
     #[arg(long)]
     report: Vec<PathBuf>,
 
+    /// Directory of attestation reports to prove together with any --report files
+    ///
+    /// Files are taken in file name order.
+    #[arg(long)]
+    report_dir: Option<PathBuf>,
+
+    /// Only take files from --report-dir whose name matches this pattern (`*` and `?`
+    /// wildcards), e.g. "*.report"
+    #[arg(long, requires = "report_dir")]
+    glob: Option<String>,
+
+    /// How the report file is encoded; `auto` detects raw, base64, PEM or hex
+    #[arg(long, value_enum, default_value_t = ReportEncoding::Auto)]
+    report_encoding: ReportEncoding,
+
     /// Output file path for the generated proof
     /// 
     /// If not specified, the proof will only be printed to stdout.
@@ -31,6 +50,12 @@ pub struct ProveCli {
     #[arg(long)]
     out: Option<PathBuf>,
 
+    /// Only execute the verifier program and print the estimated cycles per report,
+    /// without generating a proof. With `--format json` the estimates are printed as a
+    /// JSON array.
+    #[arg(long)]
+    estimate_only: bool,
+
     /// Zero-knowledge proof system configuration
     #[clap(flatten)]
     prover: ProverArgs,
@@ -50,17 +75,19 @@ impl ProveCli {
     /// 4. Creates the appropriate prover instance
     /// 5. Generates proofs (single or aggregated)
     /// 6. Outputs results to file and/or stdout
-    pub fn run(&self) -> anyhow::Result<()> {
+    pub fn run(&self, format: OutputFormat) -> anyhow::Result<()> {
         set_prover_dev_mode(self.prover.dev);
-        if self.report.len() == 0 {
-            return Err(anyhow!(
-                "No report files provided. Use --report to specify the report files."
-            ));
+        let reports = self.report_paths().code(ErrorCode::Parse)?;
+        if reports.len() == 0 {
+            return Err(ErrorCode::Config.wrap(anyhow!(
+                "No report files provided. Use --report or --report-dir to specify the report files."
+            )));
         }
 
-        let mut raw_reports = Vec::with_capacity(self.report.len());
-        for report in &self.report {
-            raw_reports.push(std::fs::read(report)?);
+        let mut raw_reports = Vec::with_capacity(reports.len());
+        for report in &reports {
+            let data = read_report(report, self.report_encoding).code(ErrorCode::Parse)?;
+            raw_reports.push(data);
         }
 
         // Initialize smart contract interface (if configured)
@@ -68,7 +95,31 @@ impl ProveCli {
         
         // Create the prover instance with the specified configuration
         let prover = self.prover.new_prover(contract)?;
-        
+
+        if self.estimate_only {
+            let mut estimates = Vec::with_capacity(reports.len());
+            for (path, report) in reports.iter().zip(raw_reports) {
+                let estimate = prover.estimate_report(report)?;
+                match format {
+                    OutputFormat::Text => println!(
+                        "{}: {} cycles, journal {} bytes",
+                        path.display(),
+                        estimate.cycles,
+                        estimate.journal_size
+                    ),
+                    OutputFormat::Json => estimates.push(json!({
+                        "report": path.display().to_string(),
+                        "cycles": estimate.cycles,
+                        "journal_size": estimate.journal_size,
+                    })),
+                }
+            }
+            if format == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&estimates)?);
+            }
+            return Ok(());
+        }
+
         // Generate proof based on the number of input reports
         let result = if raw_reports.len() == 1 {
             prover.prove_attestation_report(raw_reports.remove(0))?
@@ -82,8 +133,119 @@ impl ProveCli {
         }
         
         // Display proof information to stdout
-        println!("proof: {:?}", result);
+        format.print_proof(&result)?;
 
         Ok(())
     }
+
+    /// Returns the --report files followed by the matching files in --report-dir.
+    fn report_paths(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut paths = self.report.clone();
+        let Some(dir) = &self.report_dir else {
+            return Ok(paths);
+        };
+
+        let mut found = Vec::new();
+        let entries =
+            std::fs::read_dir(dir).with_context(|| format!("read dir {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if self
+                .glob
+                .as_deref()
+                .map_or(true, |pattern| wildcard_match(pattern, &name))
+            {
+                found.push(path);
+            }
+        }
+        found.sort();
+        paths.extend(found);
+        Ok(paths)
+    }
+}
+
+/// Matches `name` against `pattern`, where `*` matches any run of characters and `?` any
+/// single character.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and the name position it currently absorbs up to
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, absorbed)) => {
+                    p = star + 1;
+                    n = absorbed + 1;
+                    backtrack = Some((star, absorbed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        prove: ProveCli,
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.report", "attestation_1.report"));
+        assert!(wildcard_match(
+            "attestation_?.report",
+            "attestation_2.report"
+        ));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("*.report", "attestation_1.report.bak"));
+        assert!(!wildcard_match(
+            "attestation_?.report",
+            "attestation_10.report"
+        ));
+    }
+
+    #[test]
+    fn test_report_dir() {
+        let dir = std::env::temp_dir().join(format!("prove_report_dir_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sample = std::fs::read("../../samples/attestation_1.report").unwrap();
+        for name in ["c.report", "a.report", "b.report"] {
+            std::fs::write(dir.join(name), &sample).unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "not a report").unwrap();
+
+        let dir_arg = dir.to_str().unwrap();
+        let cli = Cli::parse_from(["prove", "--report-dir", dir_arg, "--glob", "*.report"]);
+        let paths = cli.prove.report_paths().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<_> = paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["a.report", "b.report", "c.report"]);
+    }
 }
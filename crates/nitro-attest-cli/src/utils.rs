@@ -3,29 +3,158 @@
 //! This module contains shared argument structures and helper functions
 //! used across different CLI commands for configuring provers and smart contracts.
 
-use alloy_primitives::Address;
-use anyhow::{anyhow, bail};
+use std::{path::Path, time::Duration};
+
+use alloy_primitives::{hex, Address};
+use anyhow::{bail, Context};
 use aws_nitro_enclave_attestation_prover::{
-    NitroEnclaveProver, NitroEnclaveVerifierContract, ProverConfig,
+    NitroEnclaveProver, NitroEnclaveVerifierContract, OnchainProof, ProverConfig,
 };
-use clap::Args;
+use aws_nitro_enclave_attestation_verifier::{stub::ZkCoProcessorType, AttestationReport};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{Args, ValueEnum};
+
+use crate::error::{ErrorCode, WithErrorCode};
+
+/// How commands that generate a proof print it on stdout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable summary; proof bytes are shown by length only
+    #[default]
+    Text,
+    /// Only the proof JSON, as written by `--out`; logs go to stderr
+    Json,
+}
+
+impl OutputFormat {
+    /// Prints a generated proof in this format.
+    pub fn print_proof(&self, proof: &OnchainProof) -> anyhow::Result<()> {
+        match self {
+            OutputFormat::Text => println!("{}", proof),
+            OutputFormat::Json => println!("{}", String::from_utf8(proof.encode_json()?)?),
+        }
+        Ok(())
+    }
+}
+
+/// How an attestation report file is encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportEncoding {
+    /// Try raw CBOR, then base64 (optionally PEM-wrapped), then hex
+    #[default]
+    Auto,
+    /// Binary COSE_Sign1 bytes as returned by the NSM
+    Raw,
+    /// Base64 text, optionally wrapped in `-----BEGIN ...-----` lines
+    Base64,
+    /// Hex text, optionally 0x-prefixed
+    Hex,
+}
+
+impl ReportEncoding {
+    /// Decodes the contents of a report file into the raw report bytes.
+    ///
+    /// `Auto` returns the first decoding that parses as an [`AttestationReport`], since
+    /// hex text is also valid base64.
+    pub fn decode(&self, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        match self {
+            ReportEncoding::Auto => {
+                if AttestationReport::parse(&data).is_ok() {
+                    return Ok(data);
+                }
+                for encoding in [ReportEncoding::Base64, ReportEncoding::Hex] {
+                    if let Ok(decoded) = encoding.decode(data.clone()) {
+                        if AttestationReport::parse(&decoded).is_ok() {
+                            return Ok(decoded);
+                        }
+                    }
+                }
+                bail!("report is neither raw CBOR nor base64 or hex encoded")
+            }
+            ReportEncoding::Raw => Ok(data),
+            ReportEncoding::Base64 => {
+                let text = std::str::from_utf8(&data).context("base64 report is not text")?;
+                let body = text
+                    .lines()
+                    .filter(|line| !line.starts_with("-----"))
+                    .flat_map(|line| line.split_whitespace())
+                    .collect::<String>();
+                Ok(STANDARD.decode(body).context("invalid base64 report")?)
+            }
+            ReportEncoding::Hex => {
+                let text = std::str::from_utf8(&data).context("hex report is not text")?;
+                Ok(hex::decode(text.trim()).context("invalid hex report")?)
+            }
+        }
+    }
+}
+
+/// Reads a report file and decodes it with `encoding` into the raw report bytes.
+pub fn read_report(path: &Path, encoding: ReportEncoding) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|data| encoding.decode(data))
+        .with_context(|| format!("read report {}", path.display()))
+}
+
+/// The zkVM used to generate proofs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// Succinct SP1
+    Sp1,
+    /// RISC Zero
+    Risc0,
+}
+
+impl From<Backend> for ZkCoProcessorType {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::Sp1 => ZkCoProcessorType::Succinct,
+            Backend::Risc0 => ZkCoProcessorType::RiscZero,
+        }
+    }
+}
 
 /// Command-line arguments for configuring zero-knowledge proof system settings.
 /// 
 /// Supports both RISC0 and SP1 proof systems with their respective configuration options.
-/// Only one prover type should be specified at a time.
+/// The proof system is selected with `--backend`; `--sp1` and `--risc0` are kept as
+/// deprecated aliases, and at most one of the three may be given.
 #[derive(Args, Clone)]
 pub struct ProverArgs {
-    #[cfg(feature = "risc0")]
-    /// Use the RISC0 zkVM for proof generation
-    #[arg(long)]
+    /// The zkVM used for proof generation
+    #[arg(long, value_enum, group = "zk_backend")]
+    pub backend: Option<Backend>,
+
+    /// Deprecated alias for `--backend risc0`
+    #[arg(long, group = "zk_backend")]
     pub risc0: bool,
 
-    #[cfg(feature = "sp1")]
-    /// Use the SP1 zkVM for proof generation
-    #[arg(long)]
+    /// Deprecated alias for `--backend sp1`
+    #[arg(long, group = "zk_backend")]
     pub sp1: bool,
 
+    /// Wrap the final proof in PLONK instead of Groth16 (SP1 only)
+    #[arg(long)]
+    pub plonk: bool,
+
+    /// Give up on a single remote proof request after this many seconds
+    #[arg(long, env = "PROVE_TIMEOUT_SECS")]
+    pub prove_timeout: Option<u64>,
+
+    /// Retry a remote proof request this many times after a transient network error
+    #[arg(long, env = "PROVE_MAX_RETRIES", default_value = "0")]
+    pub max_retries: u32,
+
+    /// Seconds to wait before the first retry, doubled after each further attempt
+    #[arg(long, env = "PROVE_RETRY_BACKOFF_SECS", default_value = "10")]
+    pub retry_backoff_secs: u64,
+
+    /// Maximum number of composite proofs generated in parallel (default: the
+    /// PROVE_MAX_CONCURRENCY environment variable, then 8)
+    #[arg(long)]
+    pub max_concurrency: Option<usize>,
+
     /// Enable development mode for mock proof generation
     #[arg(long, default_value = "false", env = "DEV_MODE")]
     pub dev: bool,
@@ -48,34 +177,64 @@ pub struct ProverArgs {
 }
 
 impl ProverArgs {
+    /// Returns the selected proof system, from `--backend` or one of its deprecated aliases.
+    pub fn backend(&self) -> anyhow::Result<Backend> {
+        let mut selected = Vec::new();
+        selected.extend(self.backend);
+        if self.sp1 {
+            selected.push(Backend::Sp1);
+        }
+        if self.risc0 {
+            selected.push(Backend::Risc0);
+        }
+        match selected[..] {
+            [backend] => Ok(backend),
+            [] => bail!("No prover specified. Use --backend <sp1|risc0> to select a proof system."),
+            _ => bail!("Only one of --backend, --sp1 and --risc0 may be given."),
+        }
+    }
+
     /// Creates a prover configuration based on the specified arguments.
     pub fn prover_config(&self) -> anyhow::Result<ProverConfig> {
-        #[cfg(all(feature = "sp1", feature = "risc0"))]
-        if self.sp1 && self.risc0 {
-            return Err(anyhow!(
-                "Cannot use both --sp1 and --risc0 at the same time."
-            ));
-        }
+        let backend = self.backend()?;
+        let timeout = self.prove_timeout.map(Duration::from_secs);
+        let retry_backoff = Duration::from_secs(self.retry_backoff_secs);
 
         #[cfg(feature = "sp1")]
-        if self.sp1 {
-            use aws_nitro_enclave_attestation_prover::SP1ProverConfig;
-            return Ok(ProverConfig::sp1_with(SP1ProverConfig {
+        if backend == Backend::Sp1 {
+            use aws_nitro_enclave_attestation_prover::{RawProofType, SP1ProverConfig};
+            let mut config = ProverConfig::sp1_with(SP1ProverConfig {
                 private_key: self.sp1_private_key.clone(),
                 rpc_url: self.sp1_rpc_url.clone(),
-            }));
+                max_retries: self.max_retries,
+                retry_backoff,
+                timeout,
+            });
+            if self.plonk {
+                config.onchain_proof_type = RawProofType::Plonk;
+            }
+            config.max_concurrency = self.max_concurrency;
+            return Ok(config);
         }
 
         #[cfg(feature = "risc0")]
-        if self.risc0 {
+        if backend == Backend::Risc0 {
             use aws_nitro_enclave_attestation_prover::RiscZeroProverConfig;
-            return Ok(ProverConfig::risc0_with(RiscZeroProverConfig {
+            if self.plonk {
+                bail!("--plonk is only supported with --backend sp1");
+            }
+            let mut config = ProverConfig::risc0_with(RiscZeroProverConfig {
                 api_url: self.risc0_api_url.clone(),
                 api_key: self.risc0_api_key.clone(),
-            }));
+                max_retries: self.max_retries,
+                retry_backoff,
+                timeout,
+            });
+            config.max_concurrency = self.max_concurrency;
+            return Ok(config);
         }
 
-        bail!("No prover specified. Use --risc0 or --sp1 to select a proof system.");
+        bail!("The {:?} backend is not enabled in this build.", backend);
     }
 
     /// Creates a new `NitroEnclaveProver` instance with the configured settings.
@@ -83,7 +242,8 @@ impl ProverArgs {
         &self,
         contract: Option<NitroEnclaveVerifierContract>,
     ) -> anyhow::Result<NitroEnclaveProver> {
-        Ok(NitroEnclaveProver::new(self.prover_config()?, contract))
+        let config = self.prover_config().code(ErrorCode::Config)?;
+        Ok(NitroEnclaveProver::new(config, contract))
     }
 }
 
@@ -99,6 +259,10 @@ pub struct ContractArgs {
     /// The RPC URL to connect to the Ethereum network
     #[arg(long, env = "RPC_URL", default_value = "http://localhost:8545")]
     pub rpc_url: Option<String>,
+
+    /// Private key used to sign transactions sent to the contract
+    #[arg(long, env = "PRIVATE_KEY")]
+    pub private_key: Option<String>,
 }
 
 impl ContractArgs {
@@ -114,7 +278,94 @@ impl ContractArgs {
         }
         let contract = *self.contract.as_ref().unwrap();
         let rpc_url = self.rpc_url.as_ref().unwrap();
-        let verifier = NitroEnclaveVerifierContract::dial(&rpc_url, contract, None)?;
+        let verifier =
+            NitroEnclaveVerifierContract::dial(&rpc_url, contract, self.private_key.as_deref())
+                .code(ErrorCode::Network)?;
         Ok(Some(verifier))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        prover: ProverArgs,
+    }
+
+    fn sample() -> Vec<u8> {
+        std::fs::read("../../samples/attestation_1.report").unwrap()
+    }
+
+    fn read_encoded(name: &str, data: &[u8], encoding: ReportEncoding) -> anyhow::Result<Vec<u8>> {
+        let path = std::env::temp_dir().join(format!("{}_{}", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        let result = read_report(&path, encoding);
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_read_report_encodings() {
+        let raw = sample();
+        let base64 = STANDARD.encode(&raw);
+        let pem = format!(
+            "-----BEGIN ATTESTATION REPORT-----\n{}\n-----END ATTESTATION REPORT-----\n",
+            base64
+                .as_bytes()
+                .chunks(64)
+                .map(|line| std::str::from_utf8(line).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        let hex = format!("0x{}\n", hex::encode(&raw));
+
+        let cases: [(&str, &[u8], ReportEncoding); 4] = [
+            ("raw.report", &raw, ReportEncoding::Raw),
+            ("report.b64", base64.as_bytes(), ReportEncoding::Base64),
+            ("report.pem", pem.as_bytes(), ReportEncoding::Base64),
+            ("report.hex", hex.as_bytes(), ReportEncoding::Hex),
+        ];
+        for (name, data, encoding) in cases {
+            assert_eq!(read_encoded(name, data, encoding).unwrap(), raw, "{}", name);
+            let auto = read_encoded(name, data, ReportEncoding::Auto).unwrap();
+            assert_eq!(auto, raw, "{} (auto)", name);
+        }
+
+        // an explicit encoding is not second-guessed
+        assert!(read_encoded("wrong.hex", base64.as_bytes(), ReportEncoding::Hex).is_err());
+        let garbage = read_encoded("garbage.report", b"not a report", ReportEncoding::Auto);
+        assert!(garbage.is_err());
+    }
+
+    #[test]
+    fn test_backend_args() {
+        let cli = Cli::try_parse_from(["prove", "--backend", "sp1"]).unwrap();
+        assert_eq!(cli.prover.backend().unwrap(), Backend::Sp1);
+        let cli = Cli::try_parse_from(["prove", "--backend", "risc0"]).unwrap();
+        assert_eq!(cli.prover.backend().unwrap(), Backend::Risc0);
+        assert_eq!(
+            ZkCoProcessorType::from(Backend::Sp1),
+            ZkCoProcessorType::Succinct
+        );
+        assert_eq!(
+            ZkCoProcessorType::from(Backend::Risc0),
+            ZkCoProcessorType::RiscZero
+        );
+
+        assert!(Cli::try_parse_from(["prove", "--backend", "plonky2"]).is_err());
+        let cli = Cli::try_parse_from(["prove"]).unwrap();
+        assert!(cli.prover.backend().is_err());
+
+        let cli = Cli::try_parse_from(["prove", "--sp1"]).unwrap();
+        assert_eq!(cli.prover.backend().unwrap(), Backend::Sp1);
+        let cli = Cli::try_parse_from(["prove", "--risc0"]).unwrap();
+        assert_eq!(cli.prover.backend().unwrap(), Backend::Risc0);
+        assert!(Cli::try_parse_from(["prove", "--backend", "risc0", "--sp1"]).is_err());
+        assert!(Cli::try_parse_from(["prove", "--sp1", "--risc0"]).is_err());
+    }
+}
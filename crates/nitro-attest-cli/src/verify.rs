@@ -0,0 +1,84 @@
+//! Offline verification of attestation reports.
+//!
+//! Checks a report's certificate chain and COSE signature locally, without generating
+//! a proof or talking to a contract.
+
+use std::path::PathBuf;
+
+use alloy_primitives::B256;
+use aws_nitro_enclave_attestation_verifier::{
+    AttestationPolicy, AttestationReport, AuthenticateOptions, PcrPolicy, AWS_NITRO_ROOT_DIGEST,
+};
+use clap::Args;
+
+use crate::{
+    error::{ErrorCode, WithErrorCode},
+    utils::{read_report, ReportEncoding},
+};
+
+/// Arguments for verifying an attestation report offline.
+#[derive(Args)]
+pub struct VerifyOfflineCli {
+    /// Path to the Nitro Enclave attestation report file
+    #[arg(long)]
+    report: PathBuf,
+
+    /// How the report file is encoded; `auto` detects raw, base64, PEM or hex
+    #[arg(long, value_enum, default_value_t = ReportEncoding::Auto)]
+    report_encoding: ReportEncoding,
+
+    /// Unix timestamp (seconds) to check the cert chain at; defaults to the document's own
+    #[arg(long)]
+    at: Option<u64>,
+
+    /// Digest of the root cert the chain must end in; defaults to the AWS Nitro Enclaves root
+    #[arg(long, default_value_t = AWS_NITRO_ROOT_DIGEST)]
+    root: B256,
+
+    /// EIF measurements JSON (as printed by `nitro-cli describe-eif`) the PCRs must match
+    #[arg(long)]
+    eif: Option<PathBuf>,
+
+    /// Print a step-by-step explanation of why the report passed or failed
+    #[arg(long)]
+    explain: bool,
+}
+
+impl VerifyOfflineCli {
+    /// Verifies the report with only the root trusted and pinned to `--root`, then checks
+    /// the PCRs if `--eif` is given.
+    pub fn run(&self) -> anyhow::Result<()> {
+        let report = read_report(&self.report, self.report_encoding)
+            .and_then(|data| AttestationReport::parse(&data))
+            .code(ErrorCode::Parse)?;
+        let timestamp = self.at.unwrap_or(report.doc().timestamp / 1000);
+        let policy = match &self.eif {
+            Some(path) => Some(AttestationPolicy {
+                pcrs: PcrPolicy::from_eif(path).code(ErrorCode::Config)?.pcrs,
+                ..Default::default()
+            }),
+            None => None,
+        };
+
+        let opts = AuthenticateOptions {
+            expected_root: Some(self.root),
+            ..Default::default()
+        };
+
+        if self.explain {
+            println!(
+                "{}",
+                report.explain_verification(policy.as_ref(), timestamp, &opts)
+            );
+        }
+
+        report
+            .authenticate_with(1, timestamp, &opts)
+            .code(ErrorCode::Verify)?;
+        if let Some(policy) = &policy {
+            policy.evaluate(report.doc()).code(ErrorCode::Verify)?;
+        }
+        tracing::info!("Report verified at {}", timestamp);
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! Runs the CLI binary on a failing command and checks what it reports on stderr.
+
+use std::process::{Command, Output};
+
+/// Verifies attestation_1 long after its leaf cert expired.
+fn verify_expired(error_format: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_nitro-attest-cli"))
+        .args([
+            "verify-offline",
+            "--report",
+            "../../samples/attestation_1.report",
+        ])
+        .args(["--at", "4102444800", "--error-format", error_format])
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_error_format_json() {
+    let output = verify_expired("json");
+    assert!(!output.status.success());
+
+    let value: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(value["code"], "verify");
+    // the code is attached without replacing the cause
+    let message = value["message"].as_str().unwrap();
+    assert!(message.contains("expired"), "{}", message);
+}
+
+#[test]
+fn test_error_format_text() {
+    let output = verify_expired("text");
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first_line = stderr.lines().next().unwrap();
+    assert!(first_line.starts_with("Error: "), "{}", stderr);
+    assert!(first_line.contains("expired"), "{}", stderr);
+}
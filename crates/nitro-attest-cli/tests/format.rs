@@ -0,0 +1,26 @@
+//! Runs the CLI binary with `--format json` and parses its stdout.
+#![cfg(feature = "sp1")]
+
+use std::process::Command;
+
+use aws_nitro_enclave_attestation_prover::{OnchainProof, ProofType};
+
+#[test]
+fn test_prove_format_json() {
+    let output = Command::new(env!("CARGO_BIN_EXE_nitro-attest-cli"))
+        .env("SKIP_TIME_VALIDITY_CHECK", "true")
+        .args(["prove", "--sp1", "--dev", "--format", "json"])
+        .args(["--report", "../../samples/attestation_1.report"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // stdout holds nothing but the proof
+    let proof = OnchainProof::decode_json(&output.stdout).unwrap();
+    assert!(matches!(proof.proof_type, ProofType::Verifier));
+    assert!(!output.stderr.is_empty(), "logs should go to stderr");
+}
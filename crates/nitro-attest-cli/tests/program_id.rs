@@ -0,0 +1,24 @@
+//! Runs the CLI binary to print the program ids.
+#![cfg(feature = "sp1")]
+
+use std::process::Command;
+
+#[test]
+fn test_program_id_sp1() {
+    let output = Command::new(env!("CARGO_BIN_EXE_nitro-attest-cli"))
+        .args(["program-id", "--sp1"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["zktype"], "Succinct");
+    for field in ["verifier_id", "verifier_proof_id", "aggregator_id"] {
+        let id = json["program_id"][field].as_str().unwrap();
+        assert!(id.starts_with("0x") && id.len() == 66, "{}: {}", field, id);
+    }
+}
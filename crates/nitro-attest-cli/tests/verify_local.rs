@@ -0,0 +1,45 @@
+//! Runs the CLI binary to prove in dev mode and verify the written proof locally.
+#![cfg(feature = "sp1")]
+
+use std::process::Command;
+
+fn cli() -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_nitro-attest-cli"));
+    // the sample report is long past any time window
+    cmd.env("SKIP_TIME_VALIDITY_CHECK", "true");
+    cmd
+}
+
+#[test]
+fn test_proof_verify_local() {
+    let out = std::env::temp_dir().join(format!("verify_local_{}.json", std::process::id()));
+    let status = cli()
+        .args([
+            "prove",
+            "--sp1",
+            "--dev",
+            "--report",
+            "../../samples/attestation_1.report",
+        ])
+        .arg("--out")
+        .arg(&out)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = cli()
+        .args(["proof", "verify-local", "--dev", "--proof"])
+        .arg(&out)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "proof is valid"
+    );
+}
@@ -1,15 +1,23 @@
-use std::sync::Arc;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use alloy_network::{Ethereum, EthereumWallet, TransactionBuilder};
-use alloy_primitives::{Address, Bytes, B256};
+use alloy_primitives::{Address, Bytes, TxHash, B256};
 use alloy_provider::{PendingTransactionBuilder, Provider, ProviderBuilder};
-use alloy_rpc_types::TransactionRequest;
+use alloy_rpc_types::{TransactionReceipt, TransactionRequest};
 use alloy_signer_local::PrivateKeySigner;
 use alloy_sol_types::SolCall;
-use anyhow::{anyhow, Context};
+use alloy_transport::TransportResult;
+use anyhow::{anyhow, bail, Context};
 use aws_nitro_enclave_attestation_verifier::stub::{
     INitroEnclaveVerifier::*, VerifierJournal, ZkCoProcessorConfig, ZkCoProcessorType,
 };
+use x509_verifier_rust_crypto::CertChain;
 
 use crate::{OnchainProof, ProofType};
 
@@ -19,10 +27,23 @@ pub enum OnchainProofVerifyResult {
     Batch(Vec<VerifierJournal>),
 }
 
+/// Contract state needed to prepare verifier inputs, see
+/// [`NitroEnclaveVerifierContract::prepare_context`].
+#[derive(Debug, Clone)]
+pub struct ContractContext {
+    pub zk_config: ZkCoProcessorConfig,
+    pub max_time_diff: u64,
+    /// Trusted prefix length of each queried cert chain, in query order.
+    pub trusted_certs_prefix_lengths: Vec<u8>,
+}
+
 #[derive(Clone)]
 pub struct NitroEnclaveVerifierContract {
     contract: Address,
-    client: Arc<Box<dyn Provider>>,
+    clients: Arc<Vec<Box<dyn Provider>>>,
+    /// Index of the endpoint tried first, moved past endpoints that fail.
+    next_client: Arc<AtomicUsize>,
+    has_signer: bool,
 }
 
 impl NitroEnclaveVerifierContract {
@@ -31,25 +52,94 @@ impl NitroEnclaveVerifierContract {
         contract: Address,
         private_key: Option<&str>,
     ) -> anyhow::Result<Self> {
-        let url = endpoint.try_into()?;
-
-        let provider: Box<dyn Provider> = match private_key {
-            Some(pk) => {
-                let signer = pk.parse::<PrivateKeySigner>()?;
-                let wallet = EthereumWallet::new(signer);
-                let provider = ProviderBuilder::new().wallet(wallet).connect_http(url);
-                Box::new(provider)
-            }
-            None => {
-                let provider = ProviderBuilder::new().connect_http(url);
-                Box::new(provider)
-            }
+        Self::dial_multi(&[endpoint], contract, private_key)
+    }
+
+    /// Like [`Self::dial`], with fallback endpoints.
+    ///
+    /// A read-only call failing with a transport error (connection failure, HTTP 5xx, rate
+    /// limiting) is retried on the next endpoint in round-robin order, and later requests
+    /// start there. Errors returned by the node itself, such as reverts, are not retried.
+    /// Transactions are never retried, see [`Self::transact`].
+    pub fn dial_multi(
+        endpoints: &[&str],
+        contract: Address,
+        private_key: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        if endpoints.is_empty() {
+            bail!("no RPC endpoint provided");
+        }
+        let wallet = match private_key {
+            Some(pk) => Some(EthereumWallet::new(pk.parse::<PrivateKeySigner>()?)),
+            None => None,
         };
 
-        Ok(Self {
+        let mut providers: Vec<Box<dyn Provider>> = Vec::with_capacity(endpoints.len());
+        for endpoint in endpoints {
+            let url = endpoint
+                .parse()
+                .with_context(|| format!("invalid RPC endpoint: {}", endpoint))?;
+            match &wallet {
+                Some(wallet) => {
+                    let provider = ProviderBuilder::new()
+                        .wallet(wallet.clone())
+                        .connect_http(url);
+                    providers.push(Box::new(provider));
+                }
+                None => {
+                    let provider = ProviderBuilder::new().connect_http(url);
+                    providers.push(Box::new(provider));
+                }
+            }
+        }
+
+        Ok(Self::from_providers(
             contract,
-            client: Arc::new(provider),
-        })
+            providers,
+            private_key.is_some(),
+        ))
+    }
+
+    pub(crate) fn from_providers(
+        contract: Address,
+        providers: Vec<Box<dyn Provider>>,
+        has_signer: bool,
+    ) -> Self {
+        Self {
+            contract,
+            clients: Arc::new(providers),
+            next_client: Arc::new(AtomicUsize::new(0)),
+            has_signer,
+        }
+    }
+
+    /// Runs `request` against the endpoints in round-robin order until one of them
+    /// does not fail with a transport error. Only for read-only requests, which are safe
+    /// to repeat.
+    async fn with_failover<'a, T, Fut>(
+        &'a self,
+        request: impl Fn(&'a dyn Provider) -> Fut,
+    ) -> TransportResult<T>
+    where
+        Fut: Future<Output = TransportResult<T>> + 'a,
+    {
+        let start = self.next_client.load(Ordering::Relaxed);
+        let mut last_err = None;
+        for offset in 0..self.clients.len() {
+            let idx = (start + offset) % self.clients.len();
+            match request(self.clients[idx].as_ref()).await {
+                Err(err) if err.is_transport_error() => {
+                    let next = (idx + 1) % self.clients.len();
+                    if self.clients.len() > 1 {
+                        tracing::warn!("RPC endpoint #{} failed, trying #{}: {}", idx, next, err);
+                    }
+                    self.next_client.store(next, Ordering::Relaxed);
+                    last_err = Some(err);
+                }
+                result => return result,
+            }
+        }
+        Err(last_err.expect("at least one endpoint"))
     }
 
     pub async fn call<T: SolCall>(&self, call: &T) -> anyhow::Result<T::Return> {
@@ -57,14 +147,20 @@ impl NitroEnclaveVerifierContract {
             .with_call(call)
             .to(self.contract);
         let result = self
-            .client
-            .call(tx)
+            .with_failover(|client| {
+                let tx = tx.clone();
+                async move { client.call(tx).await }
+            })
             .await
             .with_context(|| format!("contract={:?}", self.contract))?;
         let result = T::abi_decode_returns(&result)?;
         Ok(result)
     }
 
+    /// Sends `call` as a transaction through the currently preferred endpoint only.
+    ///
+    /// A transport error does not tell whether the node already received the transaction,
+    /// so resending it elsewhere could broadcast it twice; the error is returned instead.
     pub async fn transact<T: SolCall>(
         &self,
         call: &T,
@@ -72,7 +168,8 @@ impl NitroEnclaveVerifierContract {
         let tx = TransactionRequest::default()
             .with_call(call)
             .to(self.contract);
-        let result = self.client.send_transaction(tx).await?;
+        let idx = self.next_client.load(Ordering::Relaxed) % self.clients.len();
+        let result = self.clients[idx].send_transaction(tx).await?;
         Ok(result)
     }
 
@@ -101,6 +198,88 @@ impl NitroEnclaveVerifierContract {
         })
     }
 
+    /// Submits the proof verification as a transaction instead of an `eth_call`, so the
+    /// contract records the newly verified intermediate certs in its cache.
+    ///
+    /// Requires the contract to be dialed with a private key. Returns the receipt of the
+    /// mined transaction, or an error if it reverted.
+    pub async fn submit_verify_proof(
+        &self,
+        proof: &OnchainProof,
+    ) -> anyhow::Result<TransactionReceipt> {
+        if !self.has_signer {
+            bail!("Submitting a transaction requires a contract dialed with a private key.");
+        }
+        if proof.onchain_proof.len() == 0 {
+            return Err(anyhow!(
+                "Proof does not contain an on-chain proof, unable to submit."
+            ));
+        }
+        let journal = proof.raw_proof.journal.clone();
+        let proof_bytes = proof.onchain_proof.clone();
+        let zk = proof.zktype;
+        let zk_config = self.zk_config(proof.zktype).await?;
+        proof.program_id.verify(&zk_config)?;
+
+        let pending = match proof.proof_type {
+            ProofType::Verifier => {
+                let call = verifyCall {
+                    output: journal,
+                    zkCoprocessor: zk,
+                    proofBytes: proof_bytes,
+                };
+                self.transact(&call).await?
+            }
+            ProofType::Aggregator => {
+                let call = batchVerifyCall {
+                    output: journal,
+                    zkCoprocessor: zk,
+                    proofBytes: proof_bytes,
+                };
+                self.transact(&call).await?
+            }
+        };
+        let receipt = pending.get_receipt().await?;
+        if !receipt.status() {
+            bail!(
+                "Verification transaction reverted: {}",
+                receipt.transaction_hash
+            );
+        }
+        Ok(receipt)
+    }
+
+    /// Like [`Self::submit_verify_proof`], but returns only the hash of the mined
+    /// transaction.
+    pub async fn submit_proof(&self, proof: &OnchainProof) -> anyhow::Result<TxHash> {
+        let receipt = self.submit_verify_proof(proof).await?;
+        Ok(receipt.transaction_hash)
+    }
+
+    /// Registers the intermediate certs among the first `up_to_len` certs of `chain` as
+    /// trusted, so later proofs for this chain start from a prefix of that length.
+    ///
+    /// The root (index 0) is configured separately and is not registered. Calls the
+    /// owner-only `addTrustedCerts`, so the contract must be dialed with the owner key.
+    pub async fn register_trusted_certs(
+        &self,
+        chain: &CertChain<'_>,
+        up_to_len: u8,
+    ) -> anyhow::Result<TxHash> {
+        if !self.has_signer {
+            bail!("Registering trusted certs requires a contract dialed with a private key.");
+        }
+        let call = register_trusted_certs_call(chain, up_to_len)?;
+        let receipt = self.transact(&call).await?.get_receipt().await?;
+        if !receipt.status() {
+            bail!(
+                "Trusted cert registration reverted: {}",
+                receipt.transaction_hash
+            );
+        }
+        Ok(receipt.transaction_hash)
+    }
+
     pub async fn verify(
         &self,
         zk: ZkCoProcessorType,
@@ -141,10 +320,41 @@ impl NitroEnclaveVerifierContract {
         self.call(&call).await
     }
 
+    /// Returns the configuration of every supported zk coprocessor. Unconfigured ones are
+    /// returned with zero ids.
+    pub async fn all_zk_configs(
+        &self,
+    ) -> anyhow::Result<Vec<(ZkCoProcessorType, ZkCoProcessorConfig)>> {
+        let mut configs = Vec::new();
+        for zk in [ZkCoProcessorType::RiscZero, ZkCoProcessorType::Succinct] {
+            configs.push((zk, self.zk_config(zk).await?));
+        }
+        Ok(configs)
+    }
+
     pub async fn max_time_diff(&self) -> anyhow::Result<u64> {
         Ok(self.call(&maxTimeDiffCall {}).await?)
     }
 
+    /// Fetches the zk config, `maxTimeDiff` and the cert cache status of `cert_digests`
+    /// with concurrent requests, saving two round-trips over querying them one by one.
+    pub async fn prepare_context(
+        &self,
+        zk: ZkCoProcessorType,
+        cert_digests: Vec<Vec<B256>>,
+    ) -> anyhow::Result<ContractContext> {
+        let (zk_config, max_time_diff, trusted_certs_prefix_lengths) = tokio::try_join!(
+            self.zk_config(zk),
+            self.max_time_diff(),
+            self.batch_query_cert_cache(cert_digests),
+        )?;
+        Ok(ContractContext {
+            zk_config,
+            max_time_diff,
+            trusted_certs_prefix_lengths,
+        })
+    }
+
     pub async fn batch_query_cert_cache(
         &self,
         certs_digests: Vec<Vec<B256>>,
@@ -170,3 +380,221 @@ impl NitroEnclaveVerifierContract {
         Ok(result)
     }
 }
+
+fn register_trusted_certs_call(
+    chain: &CertChain<'_>,
+    up_to_len: u8,
+) -> anyhow::Result<addTrustedCertsCall> {
+    let digests = chain.digest();
+    let up_to_len = up_to_len as usize;
+    if up_to_len < 2 || up_to_len > digests.len() {
+        bail!(
+            "trusted prefix length must be between 2 and the chain length {}, got {}",
+            digests.len(),
+            up_to_len
+        );
+    }
+    Ok(addTrustedCertsCall {
+        _certHashes: digests[1..up_to_len].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy_sol_types::SolValue;
+    use alloy_transport::mock::Asserter;
+    use aws_nitro_enclave_attestation_verifier::AttestationReport;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::*;
+
+    /// Answers one JSON-RPC `eth_call` over HTTP after a delay, tracking how many
+    /// requests are in flight at once.
+    async fn serve_delayed_call(mut stream: TcpStream, in_flight: Arc<AtomicUsize>) -> usize {
+        let mut buf = Vec::new();
+        let body = loop {
+            let mut chunk = [0u8; 4096];
+            let n = stream.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf);
+            let Some(header_end) = text.find("\r\n\r\n") else {
+                continue;
+            };
+            let content_length: usize = text[..header_end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse().unwrap())
+                })
+                .unwrap();
+            if buf.len() >= header_end + 4 + content_length {
+                break buf[header_end + 4..header_end + 4 + content_length].to_vec();
+            }
+        };
+
+        let concurrent = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let params = &request["params"][0];
+        let input = params["input"]
+            .as_str()
+            .or(params["data"].as_str())
+            .unwrap();
+        let input = alloy_primitives::hex::decode(input).unwrap();
+        let selector: [u8; 4] = input[..4].try_into().unwrap();
+        let output = if selector == getZkConfigCall::SELECTOR {
+            getZkConfigCall::abi_encode_returns(&ZkCoProcessorConfig {
+                verifierId: B256::ZERO,
+                verifierProofId: B256::ZERO,
+                aggregatorId: B256::ZERO,
+                zkVerifier: Address::ZERO,
+            })
+        } else if selector == maxTimeDiffCall::SELECTOR {
+            maxTimeDiffCall::abi_encode_returns(&3600)
+        } else {
+            checkTrustedIntermediateCertsCall::abi_encode_returns(&vec![1u8])
+        };
+
+        let response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": Bytes::from(output),
+        })
+        .to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            response.len(),
+            response
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        concurrent
+    }
+
+    #[tokio::test]
+    async fn test_prepare_context_concurrent() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let in_flight = Arc::new(AtomicUsize::new(0));
+            let mut handlers = Vec::new();
+            for _ in 0..3 {
+                let (stream, _) = listener.accept().await.unwrap();
+                handlers.push(tokio::spawn(serve_delayed_call(stream, in_flight.clone())));
+            }
+            let mut max_in_flight = 0;
+            for handler in handlers {
+                max_in_flight = max_in_flight.max(handler.await.unwrap());
+            }
+            max_in_flight
+        });
+
+        let contract = NitroEnclaveVerifierContract::dial(&endpoint, Address::ZERO, None).unwrap();
+        let context = contract
+            .prepare_context(ZkCoProcessorType::Succinct, vec![vec![B256::ZERO]])
+            .await
+            .unwrap();
+        assert_eq!(context.max_time_diff, 3600);
+        assert_eq!(context.trusted_certs_prefix_lengths, vec![1]);
+        assert_eq!(context.zk_config.zkVerifier, Address::ZERO);
+
+        // all three requests were waiting on the server at the same time
+        assert_eq!(server.await.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_register_trusted_certs_call() {
+        let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+        let report = AttestationReport::parse(&report).unwrap();
+        let chain = report.cert_chain().unwrap();
+
+        let calldata = register_trusted_certs_call(&chain, 4).unwrap().abi_encode();
+        assert_eq!(
+            calldata[..4],
+            alloy_primitives::keccak256("addTrustedCerts(bytes32[])")[..4]
+        );
+        let call = addTrustedCertsCall::abi_decode(&calldata).unwrap();
+        assert_eq!(call._certHashes, chain.digest()[1..4]);
+
+        assert!(register_trusted_certs_call(&chain, 1).is_err());
+        assert!(register_trusted_certs_call(&chain, 6).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_all_zk_configs() {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        let contract = NitroEnclaveVerifierContract::from_providers(
+            Address::ZERO,
+            vec![Box::new(provider)],
+            false,
+        );
+
+        let config = |id: u8| ZkCoProcessorConfig {
+            verifierId: B256::repeat_byte(id),
+            verifierProofId: B256::repeat_byte(id + 1),
+            aggregatorId: B256::repeat_byte(id + 2),
+            zkVerifier: Address::repeat_byte(id + 3),
+        };
+        for id in [0x10, 0x20] {
+            asserter.push_success(&Bytes::from(getZkConfigCall::abi_encode_returns(&config(
+                id,
+            ))));
+        }
+
+        let configs = contract.all_zk_configs().await.unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].0, ZkCoProcessorType::RiscZero);
+        assert_eq!(configs[0].1.verifierId, config(0x10).verifierId);
+        assert_eq!(configs[1].0, ZkCoProcessorType::Succinct);
+        assert_eq!(configs[1].1.aggregatorId, config(0x20).aggregatorId);
+        assert_eq!(configs[1].1.zkVerifier, config(0x20).zkVerifier);
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        // an asserter with no queued response fails every request with a transport error
+        let down = Asserter::new();
+        let up = Asserter::new();
+        let providers: Vec<Box<dyn Provider>> = vec![
+            Box::new(ProviderBuilder::new().connect_mocked_client(down.clone())),
+            Box::new(ProviderBuilder::new().connect_mocked_client(up.clone())),
+        ];
+        let contract =
+            NitroEnclaveVerifierContract::from_providers(Address::ZERO, providers, false);
+
+        up.push_success(&Bytes::from(3600u64.abi_encode()));
+        assert_eq!(contract.max_time_diff().await.unwrap(), 3600);
+        assert_eq!(contract.next_client.load(Ordering::Relaxed), 1);
+
+        // the healthy endpoint is tried first from now on
+        up.push_success(&Bytes::from(60u64.abi_encode()));
+        assert_eq!(contract.max_time_diff().await.unwrap(), 60);
+
+        // a request failing everywhere returns the last transport error
+        assert!(contract.max_time_diff().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transact_without_failover() {
+        let down = Asserter::new();
+        let up = Asserter::new();
+        let providers: Vec<Box<dyn Provider>> = vec![
+            Box::new(ProviderBuilder::new().connect_mocked_client(down.clone())),
+            Box::new(ProviderBuilder::new().connect_mocked_client(up.clone())),
+        ];
+        let contract =
+            NitroEnclaveVerifierContract::from_providers(Address::ZERO, providers, false);
+
+        // the failed endpoint is not swapped for the healthy one
+        assert!(contract.transact(&maxTimeDiffCall {}).await.is_err());
+        assert_eq!(contract.next_client.load(Ordering::Relaxed), 0);
+    }
+}
@@ -1,6 +1,7 @@
 mod prover;
 pub use prover::*;
 mod program;
+pub use program::{ProofTimeout, DEFAULT_RETRY_BACKOFF};
 mod types;
 pub use types::*;
 mod contract;
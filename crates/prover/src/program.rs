@@ -4,11 +4,13 @@
 //! zero-knowledge proof programs that can verify AWS Nitro Enclave attestations.
 //! It provides a unified interface for different ZK proof systems like RISC0 and SP1.
 
+use std::{fmt, time::Duration};
+
 use alloy_primitives::{Bytes, B256};
 use alloy_sol_types::SolValue;
 use aws_nitro_enclave_attestation_verifier::stub::ZkCoProcessorType;
 
-use crate::{RawProof, RawProofType};
+use crate::{ProofEstimate, RawProof, RawProofType};
 
 /// Core trait defining the interface for zero-knowledge proof programs.
 ///
@@ -44,6 +46,14 @@ pub trait Program: Send + Sync {
     /// It might be empty if the proof is not verifiable on-chain (e.g. FakeProof, CompositeProof).
     fn onchain_proof(&self, proof: &RawProof) -> anyhow::Result<Bytes>;
 
+    /// Verifies a proof generated by this program against its own program ID, without
+    /// going through a chain. Proofs generated in dev mode verify only in dev mode.
+    fn verify_proof(&self, proof: &RawProof) -> anyhow::Result<()>;
+
+    /// Executes the guest on `input` without proving, to estimate the cost of a proof
+    /// before requesting one from a metered prover.
+    fn estimate(&self, input: &Self::Input) -> anyhow::Result<ProofEstimate>;
+
     /// Uploads the program image to a remote proving service.
     fn upload_image(&self, cfg: &RemoteProverConfig) -> anyhow::Result<()>;
 
@@ -91,4 +101,37 @@ pub struct RemoteProverConfig {
     pub api_url: Option<String>,
     /// The authentication key for accessing the remote proving service
     pub api_key: String,
+    /// How many times a proof request is retried after a transient network error, see
+    /// [`crate::utils::is_transient_error`]
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled after each further attempt
+    pub retry_backoff: Duration,
+    /// Upper bound for a single proof request; `None` waits indefinitely
+    pub timeout: Option<Duration>,
+}
+
+/// Default for [`RemoteProverConfig::retry_backoff`]. Retries themselves are off by default.
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Retry and timeout settings a program applies to its proof requests. Retries only apply
+/// to remote provers (SP1 network, Bonsai); the timeout bounds every proof.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct RetryConfig {
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+    pub timeout: Option<Duration>,
 }
+
+/// A proof request did not finish within [`RemoteProverConfig::timeout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofTimeout {
+    pub timeout: Duration,
+}
+
+impl fmt::Display for ProofTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "proof generation timed out after {:?}", self.timeout)
+    }
+}
+
+impl std::error::Error for ProofTimeout {}
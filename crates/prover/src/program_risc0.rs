@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
 use alloy_primitives::{Bytes, B256};
 use alloy_sol_types::SolValue;
@@ -6,17 +6,21 @@ use anyhow::anyhow;
 use aws_nitro_enclave_attestation_verifier::stub::{
     BatchVerifierInput, BatchVerifierJournal, VerifierInput, VerifierJournal, ZkCoProcessorType,
 };
-use bonsai_sdk::{blocking::Client, API_URL_ENVVAR};
+use bonsai_sdk::{blocking::Client, API_KEY_ENVVAR, API_URL_ENVVAR};
 use lazy_static::lazy_static;
 use risc0_ethereum_contracts::groth16;
 use risc0_methods::{
     RISC0_AGGREGATOR_ELF, RISC0_AGGREGATOR_ID, RISC0_VERIFIER_ELF, RISC0_VERIFIER_ID,
 };
-use risc0_zkvm::{default_prover, Digest, ExecutorEnv, InnerReceipt, ProverOpts, VERSION};
+use risc0_zkvm::{
+    default_executor, default_prover, Digest, ExecutorEnv, InnerReceipt, ProverOpts, Receipt,
+    VERSION,
+};
 
 use crate::{
-    program::{Program, RemoteProverConfig},
-    RawProof, RawProofType,
+    program::{Program, RemoteProverConfig, RetryConfig, DEFAULT_RETRY_BACKOFF},
+    utils::{retry_with_backoff, run_with_timeout},
+    ProgramId, ProofEstimate, RawProof, RawProofType,
 };
 
 lazy_static! {
@@ -30,6 +34,12 @@ lazy_static! {
 pub struct RiscZeroProverConfig {
     pub api_url: Option<String>,
     pub api_key: Option<String>,
+    /// See [`RemoteProverConfig::max_retries`]
+    pub max_retries: u32,
+    /// See [`RemoteProverConfig::retry_backoff`]
+    pub retry_backoff: Duration,
+    /// See [`RemoteProverConfig::timeout`]
+    pub timeout: Option<Duration>,
 }
 
 impl Default for RiscZeroProverConfig {
@@ -37,6 +47,9 @@ impl Default for RiscZeroProverConfig {
         RiscZeroProverConfig {
             api_url: std::env::var("BONSAI_API_URL").ok(),
             api_key: std::env::var("BONSAI_API_KEY").ok(),
+            max_retries: 0,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            timeout: None,
         }
     }
 }
@@ -47,14 +60,32 @@ impl TryFrom<RiscZeroProverConfig> for RemoteProverConfig {
         Ok(RemoteProverConfig {
             api_url: value.api_url,
             api_key: value.api_key.ok_or_else(|| anyhow!("missing BONSAI_API_KEY"))?,
+            max_retries: value.max_retries,
+            retry_backoff: value.retry_backoff,
+            timeout: value.timeout,
         })
     }
 }
 
+/// Computes the RISC0 program ids from the image ids compiled into `risc0-methods`.
+pub(crate) fn program_ids() -> ProgramId {
+    // a RISC0 proof is verified against the image id itself
+    ProgramId {
+        verifier_id: image_program_id(RISC0_VERIFIER_ID),
+        verifier_proof_id: image_program_id(RISC0_VERIFIER_ID),
+        aggregator_id: image_program_id(RISC0_AGGREGATOR_ID),
+    }
+}
+
+fn image_program_id(image_id: [u32; 8]) -> B256 {
+    B256::from_slice(Digest::new(image_id).as_bytes())
+}
+
 #[derive(Clone)]
 pub struct ProgramRisc0<Input, Output> {
     elf: &'static [u8],
     image_id: [u32; 8],
+    retry: RetryConfig,
     _marker: PhantomData<(Input, Output)>,
 }
 
@@ -63,20 +94,41 @@ impl<Input, Output> ProgramRisc0<Input, Output> {
         ProgramRisc0 {
             elf,
             image_id,
+            retry: RetryConfig::default(),
             _marker: PhantomData,
         }
     }
 
+    /// Bounds every proof with `retry.timeout`, and retries proofs requested from Bonsai
+    /// after transient errors.
+    pub(crate) fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
     pub fn gen_raw_proof(&self, env: ExecutorEnv, opts: &ProverOpts) -> anyhow::Result<RawProof> {
-        let prover = default_prover();
-        let prove_info = prover.prove_with_opts(env, self.elf, opts)?;
-        let journal: Bytes = prove_info.receipt.journal.bytes.clone().into();
-        let claim = prove_info.receipt.inner;
-        let proof = RawProof::from_proof(&claim, journal)?;
-        Ok(proof)
+        prove_raw(self.elf, env, opts)
+    }
+}
+
+/// Whether `default_prover()` sends proof requests to Bonsai: either `RISC0_PROVER` asks
+/// for it, or it is unset and Bonsai credentials are configured.
+fn is_bonsai_prover() -> bool {
+    match std::env::var("RISC0_PROVER") {
+        Ok(prover) if !prover.is_empty() => prover == "bonsai",
+        _ => std::env::var(API_URL_ENVVAR).is_ok() && std::env::var(API_KEY_ENVVAR).is_ok(),
     }
 }
 
+fn prove_raw(elf: &[u8], env: ExecutorEnv, opts: &ProverOpts) -> anyhow::Result<RawProof> {
+    let prover = default_prover();
+    let prove_info = prover.prove_with_opts(env, elf, opts)?;
+    let journal: Bytes = prove_info.receipt.journal.bytes.clone().into();
+    let claim = prove_info.receipt.inner;
+    let proof = RawProof::from_proof(&claim, journal)?;
+    Ok(proof)
+}
+
 impl<Input, Output> Program for ProgramRisc0<Input, Output>
 where
     Input: SolValue + Send + Sync,
@@ -100,6 +152,25 @@ where
         Ok(encoded_proof.into())
     }
 
+    fn verify_proof(&self, proof: &RawProof) -> anyhow::Result<()> {
+        let inner = proof.decode_proof::<InnerReceipt>()?;
+        let receipt = Receipt::new(inner, proof.journal.to_vec());
+        receipt
+            .verify(Digest::new(self.image_id))
+            .map_err(|err| anyhow!("RISC0 receipt verification failed: {}", err))
+    }
+
+    fn estimate(&self, input: &Self::Input) -> anyhow::Result<ProofEstimate> {
+        let env = ExecutorEnv::builder()
+            .write_slice(&input.abi_encode())
+            .build()?;
+        let session = default_executor().execute(env, self.elf)?;
+        Ok(ProofEstimate {
+            cycles: session.cycles(),
+            journal_size: session.journal.bytes.len(),
+        })
+    }
+
     fn upload_image(&self, cfg: &RemoteProverConfig) -> anyhow::Result<()> {
         let api_url = match cfg.api_url.clone() {
             Some(api_url) => Ok(api_url),
@@ -115,7 +186,7 @@ where
     }
 
     fn program_id(&self) -> B256 {
-        B256::from_slice(Digest::new(self.image_id).as_bytes())
+        image_program_id(self.image_id)
     }
 
     fn verify_proof_id(&self) -> B256 {
@@ -128,18 +199,38 @@ where
         raw_proof_type: RawProofType,
         encoded_composite_proofs: Option<&[&Bytes]>,
     ) -> anyhow::Result<RawProof> {
-        let mut env = ExecutorEnv::builder();
+        let mut assumptions = Vec::new();
         if let Some(encoded_composite_proofs) = encoded_composite_proofs {
             for proof in encoded_composite_proofs {
-                let item = bincode::deserialize::<InnerReceipt>(proof)?;
-                env.add_assumption(item);
+                assumptions.push(bincode::deserialize::<InnerReceipt>(proof)?);
             }
         }
-        let env = env.write_slice(&input.abi_encode()).build()?;
+        let input = input.abi_encode();
         let opts = match raw_proof_type {
             RawProofType::Groth16 => ProverOpts::groth16(),
             RawProofType::Composite => ProverOpts::composite(),
+            RawProofType::Plonk => {
+                return Err(anyhow!("PLONK proofs are not supported by RISC0, use Groth16"))
+            }
         };
-        Ok(self.gen_raw_proof(env, &opts)?)
+        // the executor env is consumed by proving, so every attempt builds its own
+        let elf = self.elf;
+        // a local prover fails deterministically, only Bonsai requests are worth retrying
+        let max_retries = if is_bonsai_prover() {
+            self.retry.max_retries
+        } else {
+            0
+        };
+        retry_with_backoff(max_retries, self.retry.retry_backoff, || {
+            let (assumptions, input, opts) = (assumptions.clone(), input.clone(), opts.clone());
+            run_with_timeout(self.retry.timeout, move || {
+                let mut env = ExecutorEnv::builder();
+                for item in assumptions {
+                    env.add_assumption(item);
+                }
+                let env = env.write_slice(&input).build()?;
+                prove_raw(elf, env, &opts)
+            })
+        })
     }
 }
@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
 use alloy_primitives::{hex::FromHex, Bytes, B256};
 use alloy_sol_types::SolValue;
@@ -12,20 +12,26 @@ use sp1_methods::{
     SP1_VERIFIER_PK, SP1_VERIFIER_VK,
 };
 use sp1_sdk::{
-    network::builder::NetworkProverBuilder, HashableKey, SP1Proof, SP1ProvingKey, SP1Stdin,
-    SP1VerifyingKey, SP1_CIRCUIT_VERSION,
+    network::builder::NetworkProverBuilder, HashableKey, SP1Proof, SP1ProofWithPublicValues,
+    SP1ProvingKey, SP1PublicValues, SP1Stdin, SP1VerifyingKey, SP1_CIRCUIT_VERSION,
 };
 
 use crate::{
-    program::{Program, RemoteProverConfig},
-    utils::block_on,
-    RawProof, RawProofType,
+    program::{Program, RemoteProverConfig, RetryConfig, DEFAULT_RETRY_BACKOFF},
+    utils::{block_on_send, retry_with_backoff, run_with_timeout},
+    ProgramId, ProofEstimate, RawProof, RawProofType,
 };
 
 #[derive(Debug, Clone)]
 pub struct SP1ProverConfig {
     pub private_key: Option<String>,
     pub rpc_url: Option<String>,
+    /// See [`RemoteProverConfig::max_retries`]
+    pub max_retries: u32,
+    /// See [`RemoteProverConfig::retry_backoff`]
+    pub retry_backoff: Duration,
+    /// See [`RemoteProverConfig::timeout`]
+    pub timeout: Option<Duration>,
 }
 
 impl Default for SP1ProverConfig {
@@ -33,6 +39,9 @@ impl Default for SP1ProverConfig {
         SP1ProverConfig {
             private_key: std::env::var("NETWORK_PRIVATE_KEY").ok(),
             rpc_url: std::env::var("NETWORK_RPC_URL").ok(),
+            max_retries: 0,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            timeout: None,
         }
     }
 }
@@ -45,6 +54,9 @@ impl TryFrom<SP1ProverConfig> for RemoteProverConfig {
             api_key: value
                 .private_key
                 .ok_or_else(|| anyhow!("missing private key"))?,
+            max_retries: value.max_retries,
+            retry_backoff: value.retry_backoff,
+            timeout: value.timeout,
         })
     }
 }
@@ -56,11 +68,35 @@ lazy_static! {
         ProgramSP1::new(SP1_AGGREGATOR_ELF, &SP1_AGGREGATOR_VK, &SP1_AGGREGATOR_PK);
 }
 
+/// Computes the SP1 program ids from the verifying keys alone, without the proving keys
+/// [`ProgramSP1::new`] needs.
+pub(crate) fn program_ids() -> ProgramId {
+    ProgramId {
+        verifier_id: vk_program_id(&SP1_VERIFIER_VK),
+        verifier_proof_id: vk_verify_proof_id(&SP1_VERIFIER_VK),
+        aggregator_id: vk_program_id(&SP1_AGGREGATOR_VK),
+    }
+}
+
+/// Whether [`ENV_PROVER`] sends proof requests to the SP1 network.
+fn is_network_prover() -> bool {
+    std::env::var("SP1_PROVER").is_ok_and(|prover| prover == "network")
+}
+
+fn vk_program_id(vk: &SP1VerifyingKey) -> B256 {
+    vk.bytes32_raw().into()
+}
+
+fn vk_verify_proof_id(vk: &SP1VerifyingKey) -> B256 {
+    B256::new(unsafe { std::mem::transmute(vk.hash_u32()) })
+}
+
 #[derive(Clone)]
 pub struct ProgramSP1<Input, Output> {
     vk: &'static SP1VerifyingKey,
     pk: &'static SP1ProvingKey,
     elf: &'static [u8],
+    retry: RetryConfig,
     _marker: PhantomData<(Input, Output)>,
 }
 
@@ -74,21 +110,42 @@ impl<Input, Output> ProgramSP1<Input, Output> {
             vk,
             pk,
             elf,
+            retry: RetryConfig::default(),
             _marker: PhantomData,
         }
     }
 
+    /// Bounds every proof with `retry.timeout`, and retries proofs requested from the SP1
+    /// network after transient errors.
+    pub(crate) fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
     fn gen_raw_proof(
         &self,
         stdin: SP1Stdin,
         raw_proof_type: RawProofType,
     ) -> anyhow::Result<RawProof> {
-        let prover = ENV_PROVER.prove(&self.pk, &stdin);
-        let prover = match raw_proof_type {
-            RawProofType::Composite => prover.compressed(),
-            RawProofType::Groth16 => prover.groth16(),
+        let pk = self.pk;
+        // a local prover fails deterministically, only network requests are worth retrying
+        let max_retries = if is_network_prover() {
+            self.retry.max_retries
+        } else {
+            0
         };
-        let proof = prover.run()?;
+        let proof = retry_with_backoff(max_retries, self.retry.retry_backoff, || {
+            let stdin = stdin.clone();
+            run_with_timeout(self.retry.timeout, move || {
+                let prover = ENV_PROVER.prove(pk, &stdin);
+                let prover = match raw_proof_type {
+                    RawProofType::Composite => prover.compressed(),
+                    RawProofType::Groth16 => prover.groth16(),
+                    RawProofType::Plonk => prover.plonk(),
+                };
+                prover.run()
+            })
+        })?;
 
         Ok(RawProof::from_proof(
             &(proof.proof, self.vk),
@@ -143,8 +200,31 @@ where
         })
     }
 
+    fn verify_proof(&self, proof: &RawProof) -> anyhow::Result<()> {
+        let (sp1_proof, _) = proof.decode_proof::<(SP1Proof, SP1VerifyingKey)>()?;
+        let proof = SP1ProofWithPublicValues {
+            proof: sp1_proof,
+            public_values: SP1PublicValues::from(&proof.journal),
+            sp1_version: SP1_CIRCUIT_VERSION.into(),
+            tee_proof: None,
+        };
+        ENV_PROVER
+            .verify(&proof, self.vk)
+            .map_err(|err| anyhow!("SP1 proof verification failed: {}", err))
+    }
+
+    fn estimate(&self, input: &Self::Input) -> anyhow::Result<ProofEstimate> {
+        let mut stdin = SP1Stdin::new();
+        stdin.write_vec(input.abi_encode());
+        let (public_values, report) = ENV_PROVER.execute(self.elf, &stdin).run()?;
+        Ok(ProofEstimate {
+            cycles: report.total_instruction_count(),
+            journal_size: public_values.as_slice().len(),
+        })
+    }
+
     fn upload_image(&self, cfg: &RemoteProverConfig) -> anyhow::Result<()> {
-        block_on(async {
+        block_on_send(async {
             let mut builder = NetworkProverBuilder::default().private_key(&cfg.api_key);
             if let Some(api_url) = &cfg.api_url {
                 builder = builder.rpc_url(&api_url);
@@ -156,11 +236,11 @@ where
     }
 
     fn program_id(&self) -> B256 {
-        self.vk.bytes32_raw().into()
+        vk_program_id(self.vk)
     }
 
     fn verify_proof_id(&self) -> B256 {
-        B256::new(unsafe { std::mem::transmute(self.vk.hash_u32()) })
+        vk_verify_proof_id(self.vk)
     }
 
     fn gen_proof(
@@ -1,20 +1,30 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
-    program::{Program, RemoteProverConfig},
-    utils::{block_on, parallels_blocking},
-    NitroEnclaveVerifierContract, OnchainProof, OnchainProofVerifyResult, ProgramId, ProofType,
-    RawProof, RawProofType,
+    program::{Program, RemoteProverConfig, RetryConfig},
+    utils::{block_on_send, parallels_blocking},
+    NitroEnclaveVerifierContract, OnchainProof, OnchainProofVerifyResult, ProgramId, ProofEstimate,
+    ProofMetadata, ProofType, RawProof, RawProofType,
 };
 use alloy_primitives::Bytes;
-use anyhow::{anyhow, bail, Context};
+use alloy_rpc_types::TransactionReceipt;
+use anyhow::{anyhow, bail, ensure, Context};
 use aws_nitro_enclave_attestation_verifier::{
     stub::{
         BatchVerifierInput, BatchVerifierJournal, VerifierInput, VerifierJournal, ZkCoProcessorType,
     },
-    AttestationReport,
+    AttestationReport, BundleOrder, Verifier,
 };
 
+/// Maximum report age in seconds assumed when no contract provides `maxTimeDiff`.
+const DEFAULT_MAX_TIME_DIFF: u64 = 3600 * 3;
+
+/// Default for [`ProverConfig::max_future_skew_secs`].
+const DEFAULT_MAX_FUTURE_SKEW: u64 = 300;
+
 /// Configuration enumeration for different zero-knowledge proof systems.
 ///
 /// This enum allows users to select and configure which ZK proof system
@@ -34,9 +44,31 @@ use aws_nitro_enclave_attestation_verifier::{
 /// - `risc0` feature enables the RiscZero variant
 #[derive(Debug, Clone)]
 pub struct ProverConfig {
+    /// How many leading certs of each report's chain are trusted when no contract is configured
+    pub trusted_prefix_strategy: TrustedPrefixStrategy,
+    /// Trusted prefix length used before [`ProverConfig::trusted_prefix_strategy`] existed.
+    /// While the strategy is `RootOnly`, a value other than 1 is applied as
+    /// `TrustedPrefixStrategy::Fixed`.
+    #[deprecated(note = "use `trusted_prefix_strategy` instead")]
     pub default_trusted_certs_prefix_length: u8,
+    /// Only log reports that are stale or from the future instead of failing.
     pub skip_time_validity_check: bool,
+    /// Have the verifier program skip cert validity at the report's timestamp
+    /// (`skipCertValidity`). Off by default: the journal records the flag, and the verifier
+    /// contract rejects such proofs with `CertValiditySkipped`.
+    pub skip_cert_validity: bool,
     pub skip_contract_program_id_check: bool,
+    /// Proof type of the final, on-chain verifiable proof. PLONK is only supported by SP1.
+    pub onchain_proof_type: RawProofType,
+    /// Verify every composite proof before aggregating, so a bad input is reported by index
+    /// instead of failing inside the aggregator guest.
+    pub verify_before_aggregate: bool,
+    /// Maximum number of composite proofs generated in parallel. When unset, the
+    /// `PROVE_MAX_CONCURRENCY` environment variable is used, then 8.
+    pub max_concurrency: Option<usize>,
+    /// How many seconds a report's timestamp may be ahead of the local clock before it is
+    /// rejected as future-dated.
+    pub max_future_skew_secs: u64,
     pub system: ProverSystemConfig,
 }
 
@@ -47,11 +79,18 @@ impl ProverConfig {
     }
 
     #[cfg(feature = "risc0")]
+    #[allow(deprecated)]
     pub fn risc0_with(cfg: crate::program_risc0::RiscZeroProverConfig) -> Self {
         Self {
-            default_trusted_certs_prefix_length: Self::default_trusted_certs_prefix_length(),
+            trusted_prefix_strategy: Self::trusted_prefix_strategy(),
+            default_trusted_certs_prefix_length: 1,
             skip_time_validity_check: Self::skip_time_validity_check(),
+            skip_cert_validity: false,
             skip_contract_program_id_check: Self::skip_contract_program_id_check(),
+            onchain_proof_type: RawProofType::Groth16,
+            verify_before_aggregate: true,
+            max_concurrency: None,
+            max_future_skew_secs: DEFAULT_MAX_FUTURE_SKEW,
             system: ProverSystemConfig::RiscZero(cfg),
         }
     }
@@ -62,20 +101,74 @@ impl ProverConfig {
     }
 
     #[cfg(feature = "sp1")]
+    #[allow(deprecated)]
     pub fn sp1_with(cfg: crate::program_sp1::SP1ProverConfig) -> Self {
         Self {
-            default_trusted_certs_prefix_length: Self::default_trusted_certs_prefix_length(),
+            trusted_prefix_strategy: Self::trusted_prefix_strategy(),
+            default_trusted_certs_prefix_length: 1,
             skip_time_validity_check: Self::skip_time_validity_check(),
+            skip_cert_validity: false,
             skip_contract_program_id_check: Self::skip_contract_program_id_check(),
+            onchain_proof_type: RawProofType::Groth16,
+            verify_before_aggregate: true,
+            max_concurrency: None,
+            max_future_skew_secs: DEFAULT_MAX_FUTURE_SKEW,
             system: ProverSystemConfig::Succinct(cfg),
         }
     }
 
-    fn default_trusted_certs_prefix_length() -> u8 {
-        std::env::var("DEFAULT_TRUSTED_CERTS_PREFIX_LENGTH")
-            .ok()
+    /// Trusts the first `len` certs of each chain, overriding the
+    /// `DEFAULT_TRUSTED_CERTS_PREFIX_LENGTH` environment variable.
+    #[allow(deprecated)]
+    pub fn with_trusted_prefix_length(mut self, len: u8) -> Self {
+        self.trusted_prefix_strategy = TrustedPrefixStrategy::Fixed(len);
+        self.default_trusted_certs_prefix_length = len;
+        self
+    }
+
+    /// Returns `trusted_prefix_strategy`, with a legacy
+    /// `default_trusted_certs_prefix_length` applied over the `RootOnly` default.
+    #[allow(deprecated)]
+    fn resolved_trusted_prefix_strategy(&self) -> TrustedPrefixStrategy {
+        match (
+            self.trusted_prefix_strategy,
+            self.default_trusted_certs_prefix_length,
+        ) {
+            (TrustedPrefixStrategy::RootOnly, len) if len != 1 => TrustedPrefixStrategy::Fixed(len),
+            (strategy, _) => strategy,
+        }
+    }
+
+    /// Overrides the `SKIP_TIME_VALIDITY_CHECK` environment variable.
+    pub fn with_skip_time_check(mut self, skip: bool) -> Self {
+        self.skip_time_validity_check = skip;
+        self
+    }
+
+    /// Has the verifier program skip cert validity checks, see
+    /// [`ProverConfig::skip_cert_validity`].
+    pub fn with_skip_cert_validity(mut self, skip: bool) -> Self {
+        self.skip_cert_validity = skip;
+        self
+    }
+
+    /// Overrides the `SKIP_CONTRACT_PROGRAM_ID_CHECK` environment variable.
+    pub fn with_skip_program_id_check(mut self, skip: bool) -> Self {
+        self.skip_contract_program_id_check = skip;
+        self
+    }
+
+    fn trusted_prefix_strategy() -> TrustedPrefixStrategy {
+        Self::parse_trusted_prefix_strategy(
+            std::env::var("DEFAULT_TRUSTED_CERTS_PREFIX_LENGTH").ok(),
+        )
+    }
+
+    fn parse_trusted_prefix_strategy(value: Option<String>) -> TrustedPrefixStrategy {
+        value
             .and_then(|s| s.parse::<u8>().ok())
-            .unwrap_or(1_u8)
+            .map(TrustedPrefixStrategy::Fixed)
+            .unwrap_or(TrustedPrefixStrategy::RootOnly)
     }
 
     fn skip_time_validity_check() -> bool {
@@ -85,6 +178,60 @@ impl ProverConfig {
             .unwrap_or(false)
     }
 
+    /// Returns the effective composite proof concurrency, see [`ProverConfig::max_concurrency`].
+    pub fn resolved_max_concurrency(&self) -> usize {
+        Self::resolve_max_concurrency(
+            self.max_concurrency,
+            std::env::var("PROVE_MAX_CONCURRENCY").ok(),
+        )
+    }
+
+    fn resolve_max_concurrency(configured: Option<usize>, env: Option<String>) -> usize {
+        configured
+            .or_else(|| env.and_then(|s| s.parse::<usize>().ok()))
+            .unwrap_or(8)
+    }
+
+    /// Checks that report `idx`, signed at `report_timestamp` (seconds), is neither older
+    /// than `max_time_diff` nor further ahead of `current_time` than `max_future_skew_secs`.
+    /// With `skip_time_validity_check` set, violations are only logged.
+    fn check_report_time(
+        &self,
+        idx: usize,
+        report_timestamp: u64,
+        current_time: u64,
+        max_time_diff: u64,
+    ) -> anyhow::Result<()> {
+        if report_timestamp + max_time_diff < current_time {
+            if self.skip_time_validity_check {
+                tracing::warn!(
+                    "Report[{idx}] signed {} seconds ago, ignoring time validity check.",
+                    current_time - report_timestamp
+                );
+            } else {
+                bail!(
+                    "Report[{idx}] signed {} seconds ago, may indicate verification failure. set `SKIP_TIME_VALIDITY_CHECK=true` to ignore this check.",
+                    current_time - report_timestamp
+                );
+            }
+        }
+        if report_timestamp > current_time + self.max_future_skew_secs {
+            if self.skip_time_validity_check {
+                tracing::warn!(
+                    "Report[{idx}] signed {} seconds in the future, ignoring time validity check.",
+                    report_timestamp - current_time
+                );
+            } else {
+                bail!(
+                    "Report[{idx}] signed {} seconds in the future, more than the allowed {} seconds of clock skew. set `SKIP_TIME_VALIDITY_CHECK=true` to ignore this check.",
+                    report_timestamp - current_time,
+                    self.max_future_skew_secs
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn skip_contract_program_id_check() -> bool {
         std::env::var("SKIP_CONTRACT_PROGRAM_ID_CHECK")
             .ok()
@@ -93,6 +240,31 @@ impl ProverConfig {
     }
 }
 
+/// Strategy for choosing `trustedCertsPrefixLen` when no verifier contract is configured.
+///
+/// The resulting length is always clamped to the report's cert chain length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustedPrefixStrategy {
+    /// Trust only the root certificate
+    RootOnly,
+    /// Trust a fixed number of certificates starting from the root
+    Fixed(u8),
+    /// Trust every certificate except the leaf
+    FullChainMinusLeaf,
+}
+
+impl TrustedPrefixStrategy {
+    /// Returns the trusted prefix length for a cert chain of `chain_len` certificates.
+    pub fn prefix_len(&self, chain_len: usize) -> u8 {
+        let len = match self {
+            TrustedPrefixStrategy::RootOnly => 1,
+            TrustedPrefixStrategy::Fixed(len) => *len as usize,
+            TrustedPrefixStrategy::FullChainMinusLeaf => chain_len.saturating_sub(1),
+        };
+        len.min(chain_len).min(u8::MAX as usize) as u8
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ProverSystemConfig {
     #[cfg(feature = "sp1")]
@@ -101,6 +273,18 @@ pub enum ProverSystemConfig {
     RiscZero(crate::program_risc0::RiscZeroProverConfig),
 }
 
+impl ProverSystemConfig {
+    /// Returns the zkVM this configuration selects.
+    pub fn zktype(&self) -> ZkCoProcessorType {
+        match self {
+            #[cfg(feature = "sp1")]
+            ProverSystemConfig::Succinct(_) => ZkCoProcessorType::Succinct,
+            #[cfg(feature = "risc0")]
+            ProverSystemConfig::RiscZero(_) => ZkCoProcessorType::RiscZero,
+        }
+    }
+}
+
 /// AWS Nitro Enclave attestation prover using zero-knowledge proofs.
 ///
 /// `NitroEnclaveProver` is the main entry point for generating cryptographic proofs
@@ -264,6 +448,11 @@ impl NitroEnclaveProver {
                 if let Some(api_key) = &system_cfg.private_key {
                     std::env::set_var("NETWORK_API_KEY", api_key);
                 }
+                let retry = RetryConfig {
+                    max_retries: system_cfg.max_retries,
+                    retry_backoff: system_cfg.retry_backoff,
+                    timeout: system_cfg.timeout,
+                };
                 NitroEnclaveProver {
                     contract,
                     remote_prover_config: system_cfg
@@ -271,8 +460,8 @@ impl NitroEnclaveProver {
                         .try_into()
                         .map_err(|err| format!("{:?}", err)),
                     cfg,
-                    verifier: Box::new(SP1_PROGRAM_VERIFIER.clone()),
-                    aggregator: Box::new(SP1_PROGRAM_AGGREGATOR.clone()),
+                    verifier: Box::new(SP1_PROGRAM_VERIFIER.clone().with_retry(retry)),
+                    aggregator: Box::new(SP1_PROGRAM_AGGREGATOR.clone().with_retry(retry)),
                 }
             }
             #[cfg(feature = "risc0")]
@@ -284,6 +473,11 @@ impl NitroEnclaveProver {
                 if let Some(api_key) = &system_cfg.api_key {
                     std::env::set_var("BONSAI_API_KEY", api_key);
                 }
+                let retry = RetryConfig {
+                    max_retries: system_cfg.max_retries,
+                    retry_backoff: system_cfg.retry_backoff,
+                    timeout: system_cfg.timeout,
+                };
                 NitroEnclaveProver {
                     contract,
                     remote_prover_config: system_cfg
@@ -291,13 +485,47 @@ impl NitroEnclaveProver {
                         .try_into()
                         .map_err(|err| format!("{:?}", err)),
                     cfg,
-                    verifier: Box::new(RISC0_PROGRAM_VERIFIER.clone()),
-                    aggregator: Box::new(RISC0_PROGRAM_AGGREGATOR.clone()),
+                    verifier: Box::new(RISC0_PROGRAM_VERIFIER.clone().with_retry(retry)),
+                    aggregator: Box::new(RISC0_PROGRAM_AGGREGATOR.clone().with_retry(retry)),
                 }
             }
         }
     }
 
+    /// Creates a prover for the zkVM that generated `proof`, selected from its `zktype`,
+    /// with the default configuration of that backend.
+    ///
+    /// Fails if the backend's feature is not enabled in this build.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use aws_nitro_enclave_attestation_prover::{NitroEnclaveProver, OnchainProof};
+    ///
+    /// fn main() -> anyhow::Result<()> {
+    ///     let proof = OnchainProof::decode_json(&std::fs::read("proof.json")?)?;
+    ///     let prover = NitroEnclaveProver::for_proof(&proof, None)?;
+    ///     prover.verify_local(&proof)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn for_proof(
+        proof: &OnchainProof,
+        contract: Option<NitroEnclaveVerifierContract>,
+    ) -> anyhow::Result<Self> {
+        let config = match proof.zktype {
+            #[cfg(feature = "sp1")]
+            ZkCoProcessorType::Succinct => ProverConfig::sp1(),
+            #[cfg(feature = "risc0")]
+            ZkCoProcessorType::RiscZero => ProverConfig::risc0(),
+            zktype => bail!(
+                "Proof was generated with {:?}, which is not enabled in this build.",
+                zktype
+            ),
+        };
+        Ok(Self::new(config, contract))
+    }
+
     /// Returns the zero-knowledge coprocessor type used by this prover.
     ///
     /// This method identifies which ZK proof system (RISC0 or SP1) the prover
@@ -374,7 +602,7 @@ impl NitroEnclaveProver {
     ///
     /// This method processes multiple attestation reports in parallel to generate
     /// individual proofs that can later be aggregated. It respects the concurrency
-    /// limit set by the `PROVE_MAX_CONCURRENCY` environment variable (default: 8).
+    /// limit set by [`ProverConfig::max_concurrency`].
     ///
     /// # Arguments
     ///
@@ -387,8 +615,9 @@ impl NitroEnclaveProver {
     ///
     /// # Performance
     ///
-    /// The concurrency level can be controlled via the `PROVE_MAX_CONCURRENCY`
-    /// environment variable to balance performance with system resources.
+    /// The concurrency level can be controlled via `ProverConfig::max_concurrency`, or the
+    /// `PROVE_MAX_CONCURRENCY` environment variable (default: 8) when that is unset, to
+    /// balance performance with system resources.
     ///
     /// # Examples
     ///
@@ -407,16 +636,37 @@ impl NitroEnclaveProver {
         &self,
         inputs: &[VerifierInput],
     ) -> anyhow::Result<Vec<RawProof>> {
-        let max_concurrency = std::env::var("PROVE_MAX_CONCURRENCY")
-            .ok()
-            .and_then(|s| s.parse::<usize>().ok())
-            .unwrap_or_else(|| 8);
+        let max_concurrency = self.cfg.resolved_max_concurrency();
+        self.gen_multi_composite_proofs_with(inputs, max_concurrency, |_, _| {})
+    }
+
+    /// Same as [`NitroEnclaveProver::gen_multi_composite_proofs`], with an explicit
+    /// concurrency limit and a progress callback.
+    ///
+    /// `on_progress(completed, total)` is called from the worker threads each time a proof
+    /// is generated successfully, so `completed` counts up to `inputs.len()` in completion
+    /// order rather than input order. A `max_concurrency` of 0 is treated as 1.
+    pub fn gen_multi_composite_proofs_with(
+        &self,
+        inputs: &[VerifierInput],
+        max_concurrency: usize,
+        on_progress: impl Fn(usize, usize) + Send + Sync,
+    ) -> anyhow::Result<Vec<RawProof>> {
+        let total = inputs.len();
+        let completed = AtomicUsize::new(0);
 
         // Generate partial proofs in parallel
-        Ok(parallels_blocking(max_concurrency, inputs, |input| {
-            self.verifier
-                .gen_proof(input, RawProofType::Composite, None)
-        })?)
+        Ok(parallels_blocking(
+            max_concurrency.max(1),
+            inputs,
+            |input| {
+                let proof = self
+                    .verifier
+                    .gen_proof(input, RawProofType::Composite, None)?;
+                on_progress(completed.fetch_add(1, Ordering::SeqCst) + 1, total);
+                Ok(proof)
+            },
+        )?)
     }
 
     /// Aggregates multiple individual proofs into a single compact proof.
@@ -451,8 +701,15 @@ impl NitroEnclaveProver {
     pub fn aggregate_proofs(&self, proofs: Vec<RawProof>) -> anyhow::Result<RawProof> {
         let mut journals = Vec::with_capacity(proofs.len());
         let mut encoded_proofs = Vec::with_capacity(proofs.len());
-        for item in &proofs {
-            let decoded = item.decode_journal::<VerifierJournal>()?;
+        for (idx, item) in proofs.iter().enumerate() {
+            if self.cfg.verify_before_aggregate {
+                self.verifier
+                    .verify_proof(item)
+                    .with_context(|| format!("composite proof {} is invalid", idx))?;
+            }
+            let decoded = item
+                .decode_journal::<VerifierJournal>()
+                .with_context(|| format!("composite proof {} has an invalid journal", idx))?;
             journals.push(decoded);
             encoded_proofs.push(&item.encoded_proof);
         }
@@ -463,7 +720,7 @@ impl NitroEnclaveProver {
         };
         Ok(self.aggregator.gen_proof(
             &batch_input,
-            RawProofType::Groth16,
+            self.cfg.onchain_proof_type,
             Some(encoded_proofs.as_slice()),
         )?)
     }
@@ -496,10 +753,21 @@ impl NitroEnclaveProver {
         let inputs = self.prepare_verifier_inputs(vec![report_bytes])?;
         let proof = self
             .verifier
-            .gen_proof(&inputs[0], RawProofType::Groth16, None)?;
+            .gen_proof(&inputs[0], self.cfg.onchain_proof_type, None)?;
         Ok(self.create_onchain_proof(proof, ProofType::Verifier)?)
     }
 
+    /// Estimates the cost of proving a single attestation report by executing the verifier
+    /// program without generating a proof.
+    ///
+    /// The report goes through the same preparation as in `prove_attestation_report`,
+    /// including the contract queries, so the estimate reflects the trusted certificate
+    /// prefix the real proof would use.
+    pub fn estimate_report(&self, report_bytes: Vec<u8>) -> anyhow::Result<ProofEstimate> {
+        let inputs = self.prepare_verifier_inputs(vec![report_bytes])?;
+        self.verifier.estimate(&inputs[0])
+    }
+
     /// Generates an aggregated zero-knowledge proof for multiple attestation reports.
     ///
     /// This method is optimized for batch processing multiple attestation reports
@@ -541,6 +809,8 @@ impl NitroEnclaveProver {
         let inputs = self.prepare_verifier_inputs(raw_reports)?;
         let proofs = self.gen_multi_composite_proofs(&inputs)?;
         let result = self.aggregate_proofs(proofs)?;
+        let journal = result.decode_journal::<BatchVerifierJournal>()?;
+        check_output_order(&inputs, &journal.outputs)?;
         Ok(self.create_onchain_proof(result, ProofType::Aggregator)?)
     }
 
@@ -571,11 +841,14 @@ impl NitroEnclaveProver {
     /// When no contract is provided:
     /// - Issues warnings about potential verification failures and increased costs
     /// - Validates report timestamps against current time
-    /// - Defaults to trusting only the root certificate (length = 1)
+    /// - Uses `ProverConfig::trusted_prefix_strategy` to pick the trusted prefix length
+    ///   (only the root certificate by default)
     ///
     /// # Security Considerations
     ///
     /// - Reports older than 3 hour trigger warnings as they may indicate stale attestations
+    /// - Reports dated more than `ProverConfig::max_future_skew_secs` ahead of the local clock
+    ///   are rejected, as they may indicate a spoofed or clock-skewed enclave
     /// - Certificate chain validation is critical for ensuring attestation authenticity
     /// - Smart contract integration is recommended for production environments
     ///
@@ -602,24 +875,37 @@ impl NitroEnclaveProver {
     ) -> anyhow::Result<Vec<VerifierInput>> {
         let mut parsed_reports = Vec::with_capacity(raw_reports.len());
         let mut cert_digests = Vec::with_capacity(raw_reports.len());
+        let mut chain_lens = Vec::with_capacity(raw_reports.len());
 
         // Parse attestation reports and extract certificate chain digests
-        for raw_report in &raw_reports {
-            parsed_reports.push(AttestationReport::parse(&raw_report)?);
+        for (idx, raw_report) in raw_reports.iter().enumerate() {
+            let report = AttestationReport::parse(raw_report)?;
+            let bundle_order = report.bundle_order()?;
+            tracing::debug!("Report[{idx}] cabundle order: {:?}", bundle_order);
+            if bundle_order == BundleOrder::LeafFirst {
+                // the verifier program only accepts the root first order AWS emits
+                tracing::warn!(
+                    "Report[{idx}] cabundle is leaf first; the verifier program will reject it."
+                );
+            }
+            parsed_reports.push(report);
             let cert_chain = parsed_reports.last().unwrap().cert_chain()?;
             cert_digests.push(cert_chain.digest().to_vec());
+            chain_lens.push(cert_chain.certs.len());
         }
 
         let trusted_certs_prefix_lengths;
         let max_time_diff;
         match &self.contract {
             Some(verifier_contract) => {
-                // make sure the zk config aligned
-                let zk_config = block_on(verifier_contract.zk_config(self.verifier.zktype()))?;
-                max_time_diff = block_on(verifier_contract.max_time_diff())?;
+                let context = block_on_send(
+                    verifier_contract.prepare_context(self.verifier.zktype(), cert_digests),
+                )?;
+                max_time_diff = context.max_time_diff;
 
+                // make sure the zk config aligned
                 let program_id = self.get_program_id();
-                let verify_result = program_id.verify(&zk_config).with_context(|| {
+                let verify_result = program_id.verify(&context.zk_config).with_context(|| {
                     format!("Failed to verify zkconfig for {:?}", self.verifier.zktype())
                 });
                 if let Err(verify_err) = verify_result {
@@ -633,15 +919,16 @@ impl NitroEnclaveProver {
                     }
                 }
 
-                // Query smart contract for certificate cache information
-                trusted_certs_prefix_lengths =
-                    block_on(verifier_contract.batch_query_cert_cache(cert_digests))?;
+                trusted_certs_prefix_lengths = context.trusted_certs_prefix_lengths;
             }
             None => {
                 tracing::warn!("Contract not provided, may lead to attestation failures and increased costs. Not recommended for production.");
-                max_time_diff = 3600 * 3;
-                trusted_certs_prefix_lengths =
-                    vec![self.cfg.default_trusted_certs_prefix_length; parsed_reports.len()];
+                max_time_diff = DEFAULT_MAX_TIME_DIFF;
+                let strategy = self.cfg.resolved_trusted_prefix_strategy();
+                trusted_certs_prefix_lengths = cert_digests
+                    .iter()
+                    .map(|digests| strategy.prefix_len(digests.len()))
+                    .collect();
             }
         }
 
@@ -652,25 +939,28 @@ impl NitroEnclaveProver {
             .as_secs();
         for (idx, report) in parsed_reports.iter().enumerate() {
             let report_timestamp = report.doc().timestamp / 1000;
-            if report_timestamp + max_time_diff < current_time {
-                if self.cfg.skip_time_validity_check {
-                    tracing::warn!(
-                        "Report[{idx}] signed {} seconds ago, ignoring time validity check.",
-                        current_time - report_timestamp
-                    );
-                } else {
-                    bail!(
-                        "Report[{idx}] signed {} seconds ago, may indicate verification failure. set `SKIP_TIME_VALIDITY_CHECK=true` to ignore this check.",
-                        current_time - report_timestamp
-                    );
-                }
-            }
+            self.cfg
+                .check_report_time(idx, report_timestamp, current_time, max_time_diff)?;
         }
 
-        assert!(
+        ensure!(
             trusted_certs_prefix_lengths.len() == raw_reports.len(),
-            "Trusted certificate lengths count mismatch"
+            "Trusted certificate lengths count mismatch: got {} for {} reports",
+            trusted_certs_prefix_lengths.len(),
+            raw_reports.len()
         );
+        for (idx, (&prefix_len, &chain_len)) in trusted_certs_prefix_lengths
+            .iter()
+            .zip(&chain_lens)
+            .enumerate()
+        {
+            ensure!(
+                prefix_len as usize <= chain_len,
+                "Report[{idx}] trusted certificate prefix length {} exceeds its cert chain length {}",
+                prefix_len,
+                chain_len
+            );
+        }
 
         // Build verifier inputs with trusted certificate information
         let verifier_inputs = raw_reports
@@ -679,6 +969,8 @@ impl NitroEnclaveProver {
             .map(|(report_bytes, trusted_cert_prefix_len)| VerifierInput {
                 trustedCertsPrefixLen: trusted_cert_prefix_len,
                 attestationReport: report_bytes.into(),
+                skipCertValidity: self.cfg.skip_cert_validity,
+                verifyAtTimestamp: 0,
             })
             .collect();
         Ok(verifier_inputs)
@@ -702,6 +994,7 @@ impl NitroEnclaveProver {
     /// - Program identifiers for verification logic validation
     /// - Proof type metadata for correct contract method selection
     /// - ZK system information (RISC0 or SP1)
+    /// - Metadata identifying the proven reports and the creation time
     /// - Serialization helpers for JSON export
     ///
     /// # Usage
@@ -715,12 +1008,15 @@ impl NitroEnclaveProver {
         raw_proof: RawProof,
         proof_type: ProofType,
     ) -> anyhow::Result<OnchainProof> {
-        Ok(OnchainProof::new_from_program(
+        let mut proof = OnchainProof::new_from_program(
             &*self.verifier,
             self.get_program_id(),
             raw_proof,
             proof_type,
-        )?)
+        )?;
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        proof.metadata = Some(ProofMetadata::from_journals(&proof.journals()?, created_at));
+        Ok(proof)
     }
 
     /// Verifies a zero-knowledge proof on the Ethereum blockchain via smart contract.
@@ -747,8 +1043,381 @@ impl NitroEnclaveProver {
             .contract
             .as_ref()
             .ok_or_else(|| anyhow!("verify on chain requires contract info"))?;
-        let result = block_on(contract.verify_proof(proof))
+        let result = block_on_send(contract.verify_proof(proof))
             .map_err(|err| anyhow!("Failed to verify proof on chain: {}", err))?;
         Ok(result)
     }
+
+    /// Verifies a proof locally, without a contract or RPC endpoint.
+    ///
+    /// The proof is checked by the verifier or aggregator program matching its
+    /// `proof_type`, so it must come from the same zkVM and program build as this prover.
+    /// Unlike `verify_on_chain`, this does not check the journal against the contract's
+    /// cert cache or time window.
+    pub fn verify_local(&self, proof: &OnchainProof) -> anyhow::Result<()> {
+        if proof.zktype != self.get_zk_type() {
+            bail!(
+                "proof was generated with {:?}, this prover uses {:?}",
+                proof.zktype,
+                self.get_zk_type()
+            );
+        }
+        match proof.proof_type {
+            ProofType::Verifier => self.verifier.verify_proof(&proof.raw_proof),
+            ProofType::Aggregator => self.aggregator.verify_proof(&proof.raw_proof),
+        }
+    }
+
+    /// Verifies a report natively with the verifier crate, without the zkVM.
+    ///
+    /// Returns the journal the verifier program would commit for the same report and
+    /// `trusted_len`, e.g. to check `prepare_verifier_inputs` output without proving.
+    /// `timestamp` is the current unix time in seconds; reports older than the default
+    /// `maxTimeDiff`, or dated more than `max_future_skew_secs` after `timestamp`, are
+    /// rejected unless `skip_time_validity_check` is set. Cert validity is skipped, and
+    /// recorded in the journal, when `skip_cert_validity` is set.
+    pub fn verify_native(
+        &self,
+        report_bytes: &[u8],
+        trusted_len: u8,
+        timestamp: u64,
+    ) -> anyhow::Result<VerifierJournal> {
+        let mut verifier = Verifier::new(trusted_len)
+            .with_clock(move || timestamp)
+            .with_skip_cert_validity(self.cfg.skip_cert_validity);
+        if self.cfg.skip_time_validity_check {
+            verifier = verifier.with_max_future_skew(u64::MAX);
+        } else {
+            verifier = verifier
+                .with_max_age(DEFAULT_MAX_TIME_DIFF)
+                .with_max_future_skew(self.cfg.max_future_skew_secs);
+        }
+        verifier.verify(report_bytes)
+    }
+
+    /// Proves a single attestation report, submits its verification on-chain as a
+    /// transaction, and thereby caches the report's verified intermediate certificates.
+    ///
+    /// Subsequent reports sharing those intermediates get a longer trusted prefix from
+    /// `batch_query_cert_cache`, so fewer certificate signatures are verified inside the
+    /// zkVM and proving becomes cheaper.
+    ///
+    /// # Prerequisites
+    ///
+    /// The prover must be configured with a contract dialed with a private key.
+    ///
+    /// # Gas Implications
+    ///
+    /// Unlike `verify_on_chain` (an `eth_call`), this sends a real transaction. Besides
+    /// the proof verification itself, the contract writes one storage slot per newly
+    /// trusted intermediate certificate (roughly 20k gas each), so the first call for a
+    /// fresh chain costs noticeably more than later ones, which find the certs cached.
+    pub fn prove_and_cache(
+        &self,
+        report_bytes: Vec<u8>,
+    ) -> anyhow::Result<(OnchainProof, TransactionReceipt)> {
+        let contract = self
+            .contract
+            .as_ref()
+            .ok_or_else(|| anyhow!("prove and cache requires contract info"))?;
+        let proof = self.prove_attestation_report(report_bytes)?;
+        let receipt = block_on_send(contract.submit_verify_proof(&proof))
+            .map_err(|err| anyhow!("Failed to submit proof on chain: {}", err))?;
+        Ok((proof, receipt))
+    }
+}
+
+/// Returns the program ids of the `zk` backend without constructing a
+/// [`NitroEnclaveProver`], which would also load the programs' proving keys.
+///
+/// The result equals [`NitroEnclaveProver::get_program_id`] for a prover of that backend.
+pub fn program_ids(zk: ZkCoProcessorType) -> anyhow::Result<ProgramId> {
+    match zk {
+        #[cfg(feature = "sp1")]
+        ZkCoProcessorType::Succinct => Ok(crate::program_sp1::program_ids()),
+        #[cfg(feature = "risc0")]
+        ZkCoProcessorType::RiscZero => Ok(crate::program_risc0::program_ids()),
+        zk => bail!("{:?} is not enabled in this build.", zk),
+    }
+}
+
+/// Checks that `outputs[i]` of an aggregated journal is the result for `inputs[i]`, by
+/// comparing the chain digests each output commits to with those of the input report.
+fn check_output_order(inputs: &[VerifierInput], outputs: &[VerifierJournal]) -> anyhow::Result<()> {
+    if inputs.len() != outputs.len() {
+        bail!(
+            "aggregated journal has {} outputs for {} inputs",
+            outputs.len(),
+            inputs.len()
+        );
+    }
+    for (idx, (input, output)) in inputs.iter().zip(outputs).enumerate() {
+        let cert_chain = AttestationReport::parse(&input.attestationReport)?.cert_chain()?;
+        if output.certs != cert_chain.digest() {
+            bail!("aggregated output {idx} does not match input report {idx}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_nitro_enclave_attestation_verifier::verify_attestation_report;
+
+    use super::*;
+
+    #[test]
+    fn test_trusted_prefix_strategy() {
+        // Nitro chains: root, 3 intermediates, leaf
+        let chain_len = 5;
+        assert_eq!(TrustedPrefixStrategy::RootOnly.prefix_len(chain_len), 1);
+        assert_eq!(TrustedPrefixStrategy::Fixed(3).prefix_len(chain_len), 3);
+        assert_eq!(TrustedPrefixStrategy::Fixed(0).prefix_len(chain_len), 0);
+        assert_eq!(TrustedPrefixStrategy::Fixed(9).prefix_len(chain_len), 5);
+        assert_eq!(
+            TrustedPrefixStrategy::FullChainMinusLeaf.prefix_len(chain_len),
+            4
+        );
+
+        // clamped to short chains
+        assert_eq!(TrustedPrefixStrategy::RootOnly.prefix_len(0), 0);
+        assert_eq!(TrustedPrefixStrategy::FullChainMinusLeaf.prefix_len(1), 0);
+        assert_eq!(TrustedPrefixStrategy::Fixed(2).prefix_len(1), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "sp1")]
+    #[allow(deprecated)]
+    fn test_legacy_trusted_prefix_length() {
+        let mut config = ProverConfig::sp1();
+        config.trusted_prefix_strategy = TrustedPrefixStrategy::RootOnly;
+        config.default_trusted_certs_prefix_length = 3;
+        assert_eq!(
+            config.resolved_trusted_prefix_strategy(),
+            TrustedPrefixStrategy::Fixed(3)
+        );
+
+        // an explicit strategy wins
+        config.trusted_prefix_strategy = TrustedPrefixStrategy::FullChainMinusLeaf;
+        assert_eq!(
+            config.resolved_trusted_prefix_strategy(),
+            TrustedPrefixStrategy::FullChainMinusLeaf
+        );
+        config.default_trusted_certs_prefix_length = 1;
+        config.trusted_prefix_strategy = TrustedPrefixStrategy::RootOnly;
+        assert_eq!(
+            config.resolved_trusted_prefix_strategy(),
+            TrustedPrefixStrategy::RootOnly
+        );
+    }
+
+    #[test]
+    fn test_max_concurrency_overrides_env() {
+        let env = || Some("3".to_string());
+        assert_eq!(ProverConfig::resolve_max_concurrency(Some(5), env()), 5);
+        assert_eq!(ProverConfig::resolve_max_concurrency(None, env()), 3);
+        assert_eq!(ProverConfig::resolve_max_concurrency(None, None), 8);
+        assert_eq!(
+            ProverConfig::resolve_max_concurrency(None, Some("many".into())),
+            8
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sp1")]
+    fn test_config_builder_overrides_env() {
+        assert_eq!(
+            ProverConfig::parse_trusted_prefix_strategy(None),
+            TrustedPrefixStrategy::RootOnly
+        );
+        assert_eq!(
+            ProverConfig::parse_trusted_prefix_strategy(Some("x".into())),
+            TrustedPrefixStrategy::RootOnly
+        );
+
+        // as if DEFAULT_TRUSTED_CERTS_PREFIX_LENGTH=2 was set
+        let mut from_env = ProverConfig::sp1();
+        from_env.trusted_prefix_strategy =
+            ProverConfig::parse_trusted_prefix_strategy(Some("2".into()));
+        assert_eq!(
+            from_env.trusted_prefix_strategy,
+            TrustedPrefixStrategy::Fixed(2)
+        );
+
+        let config = from_env
+            .with_trusted_prefix_length(3)
+            .with_skip_time_check(true)
+            .with_skip_program_id_check(true);
+        assert_eq!(
+            config.trusted_prefix_strategy,
+            TrustedPrefixStrategy::Fixed(3)
+        );
+        assert!(config.skip_time_validity_check);
+        assert!(config.skip_contract_program_id_check);
+
+        let config = config
+            .with_skip_time_check(false)
+            .with_skip_program_id_check(false);
+        assert!(!config.skip_time_validity_check);
+        assert!(!config.skip_contract_program_id_check);
+    }
+
+    #[test]
+    fn test_check_output_order() {
+        let inputs = ["attestation_1", "attestation_2"]
+            .iter()
+            .map(|name| VerifierInput {
+                trustedCertsPrefixLen: 1,
+                attestationReport: std::fs::read(format!("../../samples/{}.report", name))
+                    .unwrap()
+                    .into(),
+                skipCertValidity: false,
+                verifyAtTimestamp: 0,
+            })
+            .collect::<Vec<_>>();
+        let mut outputs = inputs
+            .iter()
+            .map(|input| verify_attestation_report(input).unwrap())
+            .collect::<Vec<_>>();
+        check_output_order(&inputs, &outputs).unwrap();
+
+        outputs.swap(0, 1);
+        let err = check_output_order(&inputs, &outputs).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "aggregated output 0 does not match input report 0"
+        );
+
+        outputs.pop();
+        assert!(check_output_order(&inputs, &outputs).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "sp1", feature = "risc0"))]
+    fn test_for_proof() {
+        for (path, zktype) in [
+            (
+                "../../samples/proofs/proof_risc0_verifier_1.json",
+                ZkCoProcessorType::RiscZero,
+            ),
+            (
+                "../../samples/proofs/proof_sp1_verifier_1.json",
+                ZkCoProcessorType::Succinct,
+            ),
+        ] {
+            let proof = OnchainProof::decode_json(&std::fs::read(path).unwrap()).unwrap();
+            let prover = NitroEnclaveProver::for_proof(&proof, None).unwrap();
+            assert_eq!(prover.get_zk_type(), zktype);
+        }
+    }
+
+    #[test]
+    fn test_program_ids_match_prover() {
+        let mut configs = Vec::new();
+        #[cfg(feature = "sp1")]
+        configs.push(ProverConfig::sp1());
+        #[cfg(feature = "risc0")]
+        configs.push(ProverConfig::risc0());
+
+        for config in configs {
+            let prover = NitroEnclaveProver::new(config, None);
+            let zk = prover.get_zk_type();
+            assert_eq!(
+                program_ids(zk).unwrap(),
+                prover.get_program_id(),
+                "{:?}",
+                zk
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sp1")]
+    fn test_check_report_time_future_skew() {
+        let mut cfg = ProverConfig::sp1();
+        cfg.skip_time_validity_check = false;
+        let now = 1723799509;
+
+        // within the default 300s tolerance
+        cfg.check_report_time(0, now + 300, now, 3600).unwrap();
+        // beyond it
+        let err = cfg.check_report_time(1, now + 301, now, 3600).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Report[1] signed 301 seconds in the future"));
+
+        cfg.max_future_skew_secs = 0;
+        assert!(cfg.check_report_time(0, now + 1, now, 3600).is_err());
+        cfg.skip_time_validity_check = true;
+        cfg.check_report_time(0, now + 1, now, 3600).unwrap();
+
+        // the staleness check still applies
+        cfg.skip_time_validity_check = false;
+        assert!(cfg.check_report_time(0, now - 3601, now, 3600).is_err());
+    }
+
+    /// A contract answering the three `prepare_context` calls, in the order they are
+    /// issued, with an all-zero zk config, a `maxTimeDiff` of an hour and `prefix_lengths`.
+    #[cfg(feature = "sp1")]
+    fn mock_contract(prefix_lengths: Vec<u8>) -> NitroEnclaveVerifierContract {
+        use alloy_primitives::{Address, Bytes, B256};
+        use alloy_provider::ProviderBuilder;
+        use alloy_sol_types::SolCall;
+        use alloy_transport::mock::Asserter;
+        use aws_nitro_enclave_attestation_verifier::stub::{
+            INitroEnclaveVerifier::*, ZkCoProcessorConfig,
+        };
+
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        asserter.push_success(&Bytes::from(getZkConfigCall::abi_encode_returns(
+            &ZkCoProcessorConfig {
+                verifierId: B256::ZERO,
+                verifierProofId: B256::ZERO,
+                aggregatorId: B256::ZERO,
+                zkVerifier: Address::ZERO,
+            },
+        )));
+        asserter.push_success(&Bytes::from(maxTimeDiffCall::abi_encode_returns(&3600)));
+        asserter.push_success(&Bytes::from(
+            checkTrustedIntermediateCertsCall::abi_encode_returns(&prefix_lengths),
+        ));
+        NitroEnclaveVerifierContract::from_providers(Address::ZERO, vec![Box::new(provider)], false)
+    }
+
+    #[cfg(feature = "sp1")]
+    fn prepare_with_contract(prefix_lengths: Vec<u8>, reports: &[&str]) -> anyhow::Result<()> {
+        let mut cfg = ProverConfig::sp1();
+        cfg.skip_time_validity_check = true;
+        cfg.skip_contract_program_id_check = true;
+        let prover = NitroEnclaveProver::new(cfg, Some(mock_contract(prefix_lengths)));
+        let reports = reports
+            .iter()
+            .map(|name| std::fs::read(format!("../../samples/{}.report", name)).unwrap())
+            .collect();
+        prover.prepare_verifier_inputs(reports).map(|_| ())
+    }
+
+    #[test]
+    #[cfg(feature = "sp1")]
+    fn test_prefix_lengths_mismatch_is_an_error() {
+        // one prefix length for two reports
+        let err = prepare_with_contract(vec![1], &["attestation_1", "attestation_2"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Trusted certificate lengths count mismatch: got 1 for 2 reports"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sp1")]
+    fn test_prefix_length_exceeding_chain_is_an_error() {
+        prepare_with_contract(vec![5], &["attestation_1"]).unwrap();
+        let err =
+            prepare_with_contract(vec![1, 6], &["attestation_1", "attestation_2"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Report[1] trusted certificate prefix length 6 exceeds its cert chain length 5"
+        );
+    }
 }
@@ -1,11 +1,20 @@
 use std::{
     panic::{self, AssertUnwindSafe},
     sync::{mpsc, Arc},
+    time::Duration,
 };
 
 use anyhow::{anyhow, Context, Result};
 use tokio::runtime::{Handle, Runtime};
 
+use crate::program::ProofTimeout;
+
+/// Drives `fut` to completion from synchronous code.
+///
+/// Inside a multi-threaded Tokio runtime the current worker is handed over with
+/// `block_in_place` and the existing runtime is reused. Outside of any runtime a fresh
+/// one is created. Like `block_in_place`, this panics on a current-thread runtime; use
+/// [`block_on_send`] there.
 pub fn block_on<T>(fut: impl std::future::Future<Output = T>) -> T {
     use tokio::task::block_in_place;
 
@@ -17,6 +26,23 @@ pub fn block_on<T>(fut: impl std::future::Future<Output = T>) -> T {
     }
 }
 
+/// [`block_on`] that also works inside a current-thread runtime, which cannot be blocked
+/// on: there the future runs on a helper thread with its own runtime.
+pub fn block_on_send<T: Send>(fut: impl std::future::Future<Output = T> + Send) -> T {
+    use tokio::runtime::RuntimeFlavor;
+
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::CurrentThread => {
+            std::thread::scope(|s| {
+                s.spawn(|| block_on(fut))
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))
+            })
+        }
+        _ => block_on(fut),
+    }
+}
+
 pub fn parallels_blocking<'a, T, F, E>(
     max_concurrency: usize,
     tasks: &'a [T],
@@ -96,3 +122,208 @@ where
     })
     .unwrap()
 }
+
+/// Runs `f`, retrying up to `max_retries` times while it fails with a transient error
+/// (see [`is_transient_error`]). The delay starts at `backoff` and doubles per attempt.
+/// Deterministic failures such as decode errors or guest panics, and [`ProofTimeout`]s,
+/// are returned immediately.
+pub fn retry_with_backoff<T>(
+    max_retries: u32,
+    backoff: Duration,
+    f: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    retry_if(max_retries, backoff, is_transient_error, f)
+}
+
+fn retry_if<T>(
+    max_retries: u32,
+    backoff: Duration,
+    is_transient: impl Fn(&anyhow::Error) -> bool,
+    mut f: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < max_retries && is_transient(&err) => {
+                let delay = backoff.saturating_mul(1 << attempt.min(16));
+                attempt += 1;
+                tracing::warn!(
+                    "transient proving error, retry {}/{} in {:?}: {:#}",
+                    attempt,
+                    max_retries,
+                    delay,
+                    err
+                );
+                std::thread::sleep(delay);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Runs the blocking `f` on its own thread and waits at most `timeout` for it, failing with
+/// [`ProofTimeout`] otherwise. Without a timeout `f` runs on the current thread.
+///
+/// The proving SDKs offer no cancellation, so on timeout the thread is left to finish in
+/// the background and its result is dropped.
+pub fn run_with_timeout<T>(
+    timeout: Option<Duration>,
+    f: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T>
+where
+    T: Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return f();
+    };
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    block_on(async {
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(anyhow!("proving thread exited without a result")),
+            Err(_) => Err(ProofTimeout { timeout }.into()),
+        }
+    })
+}
+
+/// Whether `err` is a transport failure of a remote prover that may succeed when retried:
+/// a Bonsai HTTP request that could not connect or timed out, or an SP1 network call that
+/// failed with gRPC `UNAVAILABLE`, `DEADLINE_EXCEEDED` or `RESOURCE_EXHAUSTED`. Anything
+/// the prover rejected, and every local failure, is deterministic.
+///
+/// A [`ProofTimeout`] is never transient: the timed out request keeps running in the
+/// background, so retrying would only start another one next to it.
+pub fn is_transient_error(err: &anyhow::Error) -> bool {
+    if err.downcast_ref::<ProofTimeout>().is_some() {
+        return false;
+    }
+    err.chain().any(is_transient_transport_error)
+}
+
+fn is_transient_transport_error(_cause: &(dyn std::error::Error + 'static)) -> bool {
+    #[cfg(feature = "risc0")]
+    if let Some(bonsai_sdk::SdkErr::HttpErr(err)) = _cause.downcast_ref() {
+        return err.is_connect() || err.is_timeout();
+    }
+    #[cfg(feature = "sp1")]
+    if let Some(sp1_sdk::network::Error::RpcError(status)) = _cause.downcast_ref() {
+        // tonic::Code discriminants are the gRPC status codes
+        const DEADLINE_EXCEEDED: i32 = 4;
+        const RESOURCE_EXHAUSTED: i32 = 8;
+        const UNAVAILABLE: i32 = 14;
+        return matches!(
+            status.code() as i32,
+            DEADLINE_EXCEEDED | RESOURCE_EXHAUSTED | UNAVAILABLE
+        );
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn test_retry_with_backoff() {
+        let backoff = Duration::from_millis(1);
+        let is_transient = |err: &anyhow::Error| err.to_string() == "connection reset";
+        let calls = Cell::new(0);
+        let result = retry_if(3, backoff, is_transient, || {
+            calls.set(calls.get() + 1);
+            if calls.get() <= 2 {
+                return Err(anyhow!("connection reset"));
+            }
+            Ok(calls.get())
+        });
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls.get(), 3);
+
+        // deterministic errors are not retried
+        calls.set(0);
+        let result: Result<()> = retry_if(3, backoff, is_transient, || {
+            calls.set(calls.get() + 1);
+            Err(anyhow!("Failed to deserialize proof: invalid length"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+
+        // the last transient error is returned once retries are exhausted
+        calls.set(0);
+        let result: Result<()> = retry_if(2, backoff, is_transient, || {
+            calls.set(calls.get() + 1);
+            Err(anyhow!("connection reset"))
+        });
+        assert_eq!(result.unwrap_err().to_string(), "connection reset");
+        assert_eq!(calls.get(), 3);
+
+        // untyped errors are never transient, whatever their message says
+        calls.set(0);
+        let result: Result<()> = retry_with_backoff(3, backoff, || {
+            calls.set(calls.get() + 1);
+            Err(std::io::Error::other("503 service unavailable").into())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+
+        // a proof that hit its own timeout is never retried
+        calls.set(0);
+        let timeout = Duration::from_millis(1);
+        let result: Result<()> = retry_with_backoff(3, Duration::from_millis(1), || {
+            calls.set(calls.get() + 1);
+            Err(ProofTimeout { timeout }.into())
+        });
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<ProofTimeout>(),
+            Some(&ProofTimeout { timeout })
+        );
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_run_with_timeout() {
+        let slow_prover = || {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(1)
+        };
+        let timeout = Duration::from_millis(20);
+        let err = run_with_timeout(Some(timeout), slow_prover).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ProofTimeout>(),
+            Some(&ProofTimeout { timeout })
+        );
+
+        assert_eq!(
+            run_with_timeout(Some(Duration::from_secs(5)), || Ok(1)).unwrap(),
+            1
+        );
+        assert_eq!(run_with_timeout(None, slow_prover).unwrap(), 1);
+        let err = run_with_timeout::<()>(Some(timeout), || Err(anyhow!("bad input"))).unwrap_err();
+        assert_eq!(err.to_string(), "bad input");
+    }
+
+    #[test]
+    fn test_block_on_standalone() {
+        assert_eq!(block_on(async { 1 }), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_on_nested_multi_thread() {
+        let value = block_on(async { tokio::spawn(async { 1 }).await.unwrap() });
+        assert_eq!(value, 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_on_send_nested_current_thread() {
+        let value = block_on_send(async {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            1
+        });
+        assert_eq!(value, 1);
+    }
+}
@@ -0,0 +1,51 @@
+//! Integration tests against a local anvil node with a deployed `NitroEnclaveVerifier`.
+//!
+//! These tests are ignored by default. Run them with:
+//!
+//! ```bash
+//! RPC_URL=http://localhost:8545 CONTRACT=0x... PRIVATE_KEY=0x... REPORT=fresh.report \
+//!     cargo test -p aws-nitro-enclave-attestation-prover --test anvil -- --ignored
+//! ```
+//!
+//! `REPORT` must be recent enough to pass the contract's `maxTimeDiff` check.
+#![cfg(feature = "sp1")]
+
+use alloy_primitives::Address;
+use aws_nitro_enclave_attestation_prover::{
+    utils::block_on, NitroEnclaveProver, NitroEnclaveVerifierContract, ProverConfig,
+};
+use aws_nitro_enclave_attestation_verifier::AttestationReport;
+
+fn env(name: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| panic!("missing env {}", name))
+}
+
+fn dial() -> NitroEnclaveVerifierContract {
+    let contract: Address = env("CONTRACT").parse().unwrap();
+    let private_key = env("PRIVATE_KEY");
+    NitroEnclaveVerifierContract::dial(&env("RPC_URL"), contract, Some(&private_key)).unwrap()
+}
+
+#[test]
+#[ignore]
+fn test_prove_and_cache_grows_cert_cache() {
+    let report_bytes = std::fs::read(env("REPORT")).unwrap();
+    let report = AttestationReport::parse(&report_bytes).unwrap();
+    let cert_digests = vec![report.cert_chain().unwrap().digest().to_vec()];
+
+    let contract = dial();
+    let before = block_on(contract.batch_query_cert_cache(cert_digests.clone())).unwrap();
+
+    let prover = NitroEnclaveProver::new(ProverConfig::sp1(), Some(dial()));
+    let (_, receipt) = prover.prove_and_cache(report_bytes).unwrap();
+    assert!(receipt.status());
+
+    let after = block_on(contract.batch_query_cert_cache(cert_digests.clone())).unwrap();
+    assert!(
+        after[0] > before[0],
+        "cache did not grow: {:?} -> {:?}",
+        before,
+        after
+    );
+    assert_eq!(after[0] as usize, cert_digests[0].len());
+}
@@ -0,0 +1,155 @@
+//! Proves in dev mode and checks the result locally, without a contract.
+#![cfg(feature = "sp1")]
+
+use std::sync::Mutex;
+
+use alloy_sol_types::SolValue;
+use aws_nitro_enclave_attestation_prover::{
+    set_prover_dev_mode, NitroEnclaveProver, OnchainProof, ProverConfig, RawProofType,
+};
+use aws_nitro_enclave_attestation_verifier::{stub::VerifierInput, verify_attestation_report};
+use sp1_methods::{ENV_PROVER, SP1_VERIFIER_ELF};
+use sp1_sdk::{SP1Proof, SP1Stdin, SP1VerifyingKey};
+
+/// A dev mode SP1 prover for the sample reports.
+///
+/// The samples are long past any time window, so the host-side freshness check only logs.
+/// This does not set `skipCertValidity`: the certs are still checked at the report's own
+/// timestamp, and the journals remain acceptable on-chain.
+fn dev_prover() -> NitroEnclaveProver {
+    dev_prover_with(|_| {})
+}
+
+fn dev_prover_with(customize: impl FnOnce(&mut ProverConfig)) -> NitroEnclaveProver {
+    set_prover_dev_mode(true);
+    let mut config = ProverConfig::sp1();
+    config.skip_time_validity_check = true;
+    customize(&mut config);
+    NitroEnclaveProver::new(config, None)
+}
+
+#[test]
+fn test_verify_local_dev_mode() {
+    let prover = dev_prover();
+
+    let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+    let proof = prover.prove_attestation_report(report).unwrap();
+    prover.verify_local(&proof).unwrap();
+    assert_eq!(proof.metadata.as_ref().unwrap().report_count, 1);
+
+    // a proof read back from JSON verifies the same way
+    let proof = OnchainProof::decode_json(&proof.encode_json().unwrap()).unwrap();
+    prover.verify_local(&proof).unwrap();
+}
+
+#[test]
+fn test_verify_native_matches_proof() {
+    let prover = dev_prover();
+
+    let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+    let inputs = prover
+        .prepare_verifier_inputs(vec![report.clone()])
+        .unwrap();
+    let journal = prover
+        .verify_native(&report, inputs[0].trustedCertsPrefixLen, 1723799509)
+        .unwrap();
+    let proof = prover.prove_attestation_report(report).unwrap();
+    assert_eq!(journal.abi_encode(), proof.raw_proof.journal.to_vec());
+}
+
+/// Executes the SP1 guest, which verifies with the precompile-backed crates, and checks it
+/// agrees with the host's plain crates: the same journal for valid reports, and a failure
+/// for a report with a corrupted COSE signature. This covers ECDSA P-384 with SHA-384, the
+/// only algorithm in Nitro reports.
+#[test]
+fn test_guest_matches_native_verifier() {
+    let execute = |input: &VerifierInput| {
+        let mut stdin = SP1Stdin::new();
+        stdin.write_vec(input.encode());
+        ENV_PROVER
+            .execute(SP1_VERIFIER_ELF, &stdin)
+            .run()
+            .map(|(public_values, _)| public_values.to_vec())
+    };
+
+    for name in ["attestation_1", "attestation_2"] {
+        let report = std::fs::read(format!("../../samples/{}.report", name)).unwrap();
+        let mut input = VerifierInput {
+            trustedCertsPrefixLen: 1,
+            attestationReport: report.into(),
+            skipCertValidity: true,
+            verifyAtTimestamp: 0,
+        };
+        let native = verify_attestation_report(&input).unwrap();
+        assert_eq!(execute(&input).unwrap(), native.encode(), "{}", name);
+
+        // the COSE signature is the last field of the report
+        let mut tampered = input.attestationReport.to_vec();
+        *tampered.last_mut().unwrap() ^= 1;
+        input.attestationReport = tampered.into();
+        assert!(verify_attestation_report(&input).is_err(), "{}", name);
+        assert!(execute(&input).is_err(), "{}", name);
+    }
+}
+
+#[test]
+fn test_estimate_report() {
+    let prover = dev_prover();
+
+    let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+    let estimate = prover.estimate_report(report).unwrap();
+    assert!(estimate.cycles > 0);
+    assert!(estimate.journal_size > 0);
+}
+
+#[test]
+fn test_plonk_dev_mode() {
+    let prover = dev_prover_with(|config| config.onchain_proof_type = RawProofType::Plonk);
+
+    let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+    let proof = prover.prove_attestation_report(report).unwrap();
+    let (sp1_proof, _) = proof
+        .raw_proof
+        .decode_proof::<(SP1Proof, SP1VerifyingKey)>()
+        .unwrap();
+    assert!(matches!(sp1_proof, SP1Proof::Plonk(_)));
+    prover.verify_local(&proof).unwrap();
+}
+
+#[test]
+fn test_composite_proofs_progress() {
+    let prover = dev_prover();
+
+    let reports = vec![
+        std::fs::read("../../samples/attestation_1.report").unwrap(),
+        std::fs::read("../../samples/attestation_2.report").unwrap(),
+    ];
+    let inputs = prover.prepare_verifier_inputs(reports).unwrap();
+    let progress = Mutex::new(Vec::new());
+    let proofs = prover
+        .gen_multi_composite_proofs_with(&inputs, 2, |completed, total| {
+            progress.lock().unwrap().push((completed, total));
+        })
+        .unwrap();
+    assert_eq!(proofs.len(), inputs.len());
+
+    let mut progress = progress.into_inner().unwrap();
+    progress.sort();
+    assert_eq!(progress, vec![(1, 2), (2, 2)]);
+}
+
+#[test]
+fn test_aggregate_rejects_bad_proof() {
+    let prover = dev_prover();
+
+    let reports = vec![
+        std::fs::read("../../samples/attestation_1.report").unwrap(),
+        std::fs::read("../../samples/attestation_2.report").unwrap(),
+    ];
+    let inputs = prover.prepare_verifier_inputs(reports).unwrap();
+    let mut proofs = prover.gen_multi_composite_proofs(&inputs).unwrap();
+    proofs[1].encoded_proof = vec![0u8; 8].into();
+
+    let err = prover.aggregate_proofs(proofs).unwrap_err();
+    assert_eq!(err.to_string(), "composite proof 1 is invalid");
+}
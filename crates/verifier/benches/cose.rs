@@ -0,0 +1,98 @@
+//! COSE signature verification benchmarks over the sample attestation reports.
+//!
+//! Run with `cargo bench -p aws-nitro-enclave-attestation-verifier`.
+
+use alloy_primitives::hex;
+use aws_nitro_enclave_attestation_verifier::{
+    stub::VerifierInput, verify_attestation_report, AttestationReport, AttestationReportBuilder,
+    CoseSign1,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use x509_verifier_rust_crypto::{Cert, SigAlgo};
+
+const REPORTS: [&str; 2] = ["attestation_1", "attestation_2"];
+
+fn read_report(name: &str) -> Vec<u8> {
+    std::fs::read(format!("../../samples/{}.report", name)).unwrap()
+}
+
+/// The document's ES384 signature, checked against the NSM leaf cert's key.
+fn bench_cose_verify_signature(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cose_verify_signature");
+    for name in REPORTS {
+        let data = read_report(name);
+        let report = AttestationReport::parse(&data).unwrap();
+        let cose_sign = CoseSign1::from_bytes(&data).unwrap();
+        let leaf = Cert::parse_der(&report.doc().certificate).unwrap();
+        assert!(cose_sign
+            .verify_signature(SigAlgo::EcdsaSHA384, leaf.pubkey())
+            .unwrap());
+        group.bench_with_input(BenchmarkId::from_parameter(name), &cose_sign, |b, cose| {
+            b.iter(|| {
+                cose.verify_signature(SigAlgo::EcdsaSHA384, leaf.pubkey())
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// The report's cabundle and leaf, including the root's self-signature. Throughput is
+/// reported per signature.
+fn bench_cert_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nitro_verify_chain");
+    for name in REPORTS {
+        let data = read_report(name);
+        let report = AttestationReport::parse(&data).unwrap();
+        let chain = report.cert_chain().unwrap();
+        assert!(chain.verify_chain(0).unwrap());
+        group.throughput(Throughput::Elements(chain.certs.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &chain, |b, chain| {
+            b.iter(|| chain.verify_chain(0).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Full verification of a report signed by the test chain, with and without 64KB of
+/// user_data, which is copied into the journal once. Throughput is reported per report byte.
+fn bench_verify_user_data(c: &mut Criterion) {
+    const LEAF_KEY: &str = "8fbd7d8c2273106722eb8bcc7b62b5fcc0af9189fad77042a9094898581a37b46a83653a673c34270603dd7cb092349d";
+    let builder = AttestationReportBuilder::new()
+        .timestamp(1723799509167)
+        .cert_chain(vec![
+            std::fs::read("samples/test_root.der").unwrap(),
+            std::fs::read("samples/test_leaf.der").unwrap(),
+        ])
+        .signing_key(hex::decode(LEAF_KEY).unwrap());
+
+    let mut group = c.benchmark_group("verify_user_data");
+    for size in [0, 64 * 1024] {
+        let input = VerifierInput {
+            trustedCertsPrefixLen: 1,
+            attestationReport: builder
+                .clone()
+                .user_data(vec![0xab; size])
+                .build()
+                .unwrap()
+                .into(),
+            skipCertValidity: false,
+            verifyAtTimestamp: 0,
+        };
+        let journal = verify_attestation_report(&input).unwrap();
+        assert_eq!(journal.userData.len(), size);
+        group.throughput(Throughput::Bytes(input.attestationReport.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| verify_attestation_report(input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_cose_verify_signature,
+    bench_cert_chain,
+    bench_verify_user_data
+);
+criterion_main!(benches);
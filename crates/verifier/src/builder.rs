@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use p384::ecdsa::{signature::Signer, Signature, SigningKey};
+use serde_bytes::{ByteArray, ByteBuf};
+use serde_cbor::Value as CborValue;
+
+use crate::{AttestationDocument, CoseSign1, SigStructure, SignatureAlgorithm};
+
+/// Builds signed attestation reports for tests.
+///
+/// The certificate chain is given root first with the leaf last, and the signing key must be
+/// the P-384 private key of the leaf. [`build`](Self::build) returns tagged COSE_Sign1 bytes
+/// that [`AttestationReport::parse`](crate::AttestationReport::parse) accepts.
+#[derive(Clone, Debug)]
+pub struct AttestationReportBuilder {
+    module_id: String,
+    timestamp: u64,
+    pcrs: BTreeMap<u64, [u8; 48]>,
+    public_key: Option<Vec<u8>>,
+    user_data: Option<Vec<u8>>,
+    nonce: Option<Vec<u8>>,
+    cert_chain: Vec<Vec<u8>>,
+    signing_key: Vec<u8>,
+}
+
+impl Default for AttestationReportBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AttestationReportBuilder {
+    /// Starts a report with PCR0 to PCR15 zeroed, as a debug enclave reports them.
+    pub fn new() -> Self {
+        Self {
+            module_id: "i-00000000000000000-enc0000000000000000".into(),
+            timestamp: 0,
+            pcrs: (0..16).map(|index| (index, [0u8; 48])).collect(),
+            public_key: None,
+            user_data: None,
+            nonce: None,
+            cert_chain: Vec::new(),
+            signing_key: Vec::new(),
+        }
+    }
+
+    pub fn module_id(mut self, module_id: impl Into<String>) -> Self {
+        self.module_id = module_id.into();
+        self
+    }
+
+    /// Sets the document timestamp, in milliseconds since the UNIX epoch.
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn pcr(mut self, index: u64, value: [u8; 48]) -> Self {
+        self.pcrs.insert(index, value);
+        self
+    }
+
+    pub fn public_key(mut self, public_key: impl Into<Vec<u8>>) -> Self {
+        self.public_key = Some(public_key.into());
+        self
+    }
+
+    pub fn user_data(mut self, user_data: impl Into<Vec<u8>>) -> Self {
+        self.user_data = Some(user_data.into());
+        self
+    }
+
+    pub fn nonce(mut self, nonce: impl Into<Vec<u8>>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Sets the DER certificates from the root down to the leaf that signs the report.
+    pub fn cert_chain(mut self, certs: Vec<Vec<u8>>) -> Self {
+        self.cert_chain = certs;
+        self
+    }
+
+    /// Sets the leaf's P-384 private key as a big-endian scalar.
+    pub fn signing_key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.signing_key = key.into();
+        self
+    }
+
+    pub fn build(&self) -> anyhow::Result<Vec<u8>> {
+        let (certificate, cabundle) = self
+            .cert_chain
+            .split_last()
+            .ok_or_else(|| anyhow!("certificate chain is empty"))?;
+        let signing_key = SigningKey::from_slice(&self.signing_key)
+            .map_err(|err| anyhow!("invalid signing key: {}", err))?;
+
+        let doc = AttestationDocument {
+            module_id: self.module_id.clone(),
+            timestamp: self.timestamp,
+            digest: "SHA384".into(),
+            pcrs: self
+                .pcrs
+                .iter()
+                .map(|(index, value)| (*index, ByteArray::new(*value)))
+                .collect(),
+            certificate: ByteBuf::from(certificate.clone()),
+            cabundle: cabundle.iter().cloned().map(ByteBuf::from).collect(),
+            public_key: self.public_key.clone().map(ByteBuf::from),
+            user_data: self.user_data.clone().map(ByteBuf::from),
+            nonce: self.nonce.clone().map(ByteBuf::from),
+        };
+        let payload =
+            serde_cbor::to_vec(&doc).map_err(|err| anyhow!("serialization failed: {:?}", err))?;
+
+        let header = BTreeMap::from([(
+            CborValue::Integer(1),
+            CborValue::Integer(SignatureAlgorithm::ES384 as i128),
+        )]);
+        let protected = serde_cbor::to_vec(&header)
+            .map_err(|err| anyhow!("serialization failed: {:?}", err))?;
+
+        let tbs = SigStructure::new_sign1(&protected, &payload)?.as_bytes()?;
+        let signature: Signature = signing_key.sign(&tbs);
+
+        CoseSign1::new(protected, payload, signature.to_bytes().to_vec())?.to_bytes(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::hex;
+
+    use super::*;
+    use crate::{stub::VerifierInput, verify_attestation_report, AttestationReport};
+
+    const LEAF_KEY: &str = "8fbd7d8c2273106722eb8bcc7b62b5fcc0af9189fad77042a9094898581a37b46a83653a673c34270603dd7cb092349d";
+
+    #[test]
+    fn test_build_report() {
+        let root = std::fs::read("samples/test_root.der").unwrap();
+        let leaf = std::fs::read("samples/test_leaf.der").unwrap();
+        let data = AttestationReportBuilder::new()
+            .module_id("i-0123456789abcdef0-enc0123456789abcdef")
+            .timestamp(1723799509167)
+            .pcr(0, [1u8; 48])
+            .user_data(b"user data".to_vec())
+            .nonce(b"nonce".to_vec())
+            .cert_chain(vec![root, leaf])
+            .signing_key(hex::decode(LEAF_KEY).unwrap())
+            .build()
+            .unwrap();
+
+        let report = AttestationReport::parse(&data).unwrap();
+        assert_eq!(report.doc().pcr(0), Some(&[1u8; 48]));
+
+        let journal = verify_attestation_report(&VerifierInput {
+            trustedCertsPrefixLen: 1,
+            attestationReport: data.into(),
+            skipCertValidity: false,
+            verifyAtTimestamp: 0,
+        })
+        .unwrap();
+        assert_eq!(journal.timestamp, 1723799509167);
+        assert_eq!(journal.moduleId, "i-0123456789abcdef0-enc0123456789abcdef");
+        assert_eq!(journal.nonce.as_ref(), b"nonce");
+        assert_eq!(journal.certs.len(), 2);
+        assert_eq!(journal.pcrs.len(), 1);
+    }
+}
@@ -21,7 +21,7 @@ use x509_verifier_rust_crypto::verify_signature;
 use x509_verifier_rust_crypto::PubKey;
 use x509_verifier_rust_crypto::SigAlgo;
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
 /// Implementation of header_map, with CborValue keys and CborValue values.
 pub struct HeaderMap(
     #[serde(deserialize_with = "::serde_with::rust::maps_duplicate_key_is_error::deserialize")]
@@ -42,20 +42,62 @@ fn sig_algo_val(alg: SigAlgo) -> anyhow::Result<i8> {
     Ok(match alg {
         SigAlgo::EcdsaSHA256 => -7,
         SigAlgo::EcdsaSHA384 => -35,
+        SigAlgo::EcdsaSHA512 => -36,
         alg => return Err(anyhow!("unsupport sigAlgo: {:?}", alg)),
     })
 }
 
+fn sig_algo_from_val(val: i8) -> anyhow::Result<SigAlgo> {
+    Ok(match val {
+        -7 => SigAlgo::EcdsaSHA256,
+        -35 => SigAlgo::EcdsaSHA384,
+        -36 => SigAlgo::EcdsaSHA512,
+        val => return Err(anyhow!("unsupported COSE algorithm: {}", val)),
+    })
+}
+
+fn parse_header(protected: &[u8]) -> anyhow::Result<HeaderMap> {
+    serde_cbor::from_slice(protected).map_err(|err| anyhow!("deserialization failed: {:?}", err))
+}
+
+/// Reads the algorithm (label 1) from a serialized protected header.
+fn header_algorithm(protected: &[u8]) -> anyhow::Result<i8> {
+    parse_header(protected)?.algorithm()
+}
+
+impl HeaderMap {
+    /// Reads the algorithm (label 1).
+    fn algorithm(&self) -> anyhow::Result<i8> {
+        match self.0.get(&CborValue::Integer(1)) {
+            Some(CborValue::Integer(val)) => i8::try_from(*val).map_err(|_| {
+                anyhow!(
+                    "Protected Header contains unknown Signature Algorithm {}",
+                    val
+                )
+            }),
+            Some(_) => Err(anyhow!(
+                "Protected Header contains invalid Signature Algorithm specification"
+            )),
+            None => Err(anyhow!(
+                "Protected Header does not contain a valid Signature Algorithm specification",
+            )),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CoseSign1 {
     /// protected: empty_or_serialized_map,
     protected: ByteBuf,
+    /// `protected` parsed once on construction, so verifying does not parse it again.
+    protected_header: HeaderMap,
     /// unprotected: HeaderMap
     pub unprotected: HeaderMap,
-    /// payload: bstr
-    /// The spec allows payload to be nil and transported separately, but it's not useful at the
-    /// moment, so this is just a ByteBuf for simplicity.
-    pub payload: ByteBuf,
+    /// payload: bstr / nil
+    /// The spec allows payload to be nil and transported separately, see
+    /// [`CoseSign1::verify_signature_detached`]. A nil payload is `None`, and serializes
+    /// back to nil.
+    pub payload: Option<ByteBuf>,
     /// signature: bstr
     pub signature: ByteBuf,
 }
@@ -69,37 +111,90 @@ impl CoseSign1 {
             None | Some(18) => (),
             Some(tag) => return Err(anyhow!("tag error: {:?}", tag)),
         }
-        let protected = cosesign1.value.protected.as_slice();
-        let _: HeaderMap = serde_cbor::from_slice(protected)
-            .map_err(|err| anyhow!("deserialization failed: {:?}", err))?;
         Ok(cosesign1.value)
     }
 
+    /// Assembles a COSE_Sign1 with an empty unprotected header.
+    #[cfg(feature = "test-utils")]
+    pub(crate) fn new(
+        protected: Vec<u8>,
+        payload: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            protected_header: parse_header(&protected)?,
+            protected: ByteBuf::from(protected),
+            unprotected: HeaderMap::default(),
+            payload: Some(ByteBuf::from(payload)),
+            signature: ByteBuf::from(signature),
+        })
+    }
+
+    /// Serializes back to CBOR, with the COSE_Sign1 tag when `tagged` is set.
+    pub fn to_bytes(&self, tagged: bool) -> anyhow::Result<Vec<u8>> {
+        let tag = if tagged { Some(18) } else { None };
+        serde_cbor::to_vec(&serde_cbor::tags::Tagged::new(tag, self))
+            .map_err(|err| anyhow!("serialization failed: {:?}", err))
+    }
+
+    /// Returns the COSE algorithm identifier (e.g. `-35` for ES384) declared in the
+    /// protected header.
+    pub fn protected_algorithm(&self) -> anyhow::Result<i8> {
+        self.protected_header.algorithm()
+    }
+
     pub fn verify_signature(&self, sig_algo: SigAlgo, issuer_key: PubKey) -> anyhow::Result<bool> {
-        let protected: HeaderMap = serde_cbor::from_slice(&self.protected)
-            .map_err(|err| anyhow!("deserialization failed: {:?}", err))?;
+        self.verify_signature_with_aad(sig_algo, issuer_key, &[])
+    }
 
-        if let Some(protected_signature_alg_val) = protected.0.get(&CborValue::Integer(1)) {
-            let protected_signature_alg = match protected_signature_alg_val {
-                CborValue::Integer(val) => val,
-                _ => {
-                    return Err(anyhow!(
-                        "Protected Header contains invalid Signature Algorithm specification"
-                    ))
-                }
-            };
-            if protected_signature_alg != &(sig_algo_val(sig_algo)? as i128) {
-                // The key doesn't match the one specified in the HeaderMap, so this fails
-                // signature verification immediately.
-                return Ok(false);
-            }
-        } else {
+    /// Same as [`CoseSign1::verify_signature`] for objects signed with application-supplied
+    /// `external_aad`.
+    pub fn verify_signature_with_aad(
+        &self,
+        sig_algo: SigAlgo,
+        issuer_key: PubKey,
+        external_aad: &[u8],
+    ) -> anyhow::Result<bool> {
+        if self.protected_algorithm()? != sig_algo_val(sig_algo)? {
+            // The key doesn't match the one specified in the HeaderMap, so this fails
+            // signature verification immediately.
+            return Ok(false);
+        }
+
+        let payload = self.payload.as_ref().ok_or_else(|| {
+            anyhow!("COSE_Sign1 payload is detached, use verify_signature_detached")
+        })?;
+        let sig_structure =
+            SigStructure::new_sign1_with_aad(&self.protected, external_aad, payload)?;
+
+        let tbs = sig_structure.as_bytes()?;
+
+        Ok(verify_signature(
+            issuer_key,
+            sig_algo,
+            &self.signature,
+            &tbs,
+        )?)
+    }
+
+    /// Same as [`CoseSign1::verify_signature`] for a detached payload: the signature is
+    /// checked over `external_payload` instead of the embedded one, which must be nil.
+    pub fn verify_signature_detached(
+        &self,
+        sig_algo: SigAlgo,
+        issuer_key: PubKey,
+        external_payload: &[u8],
+    ) -> anyhow::Result<bool> {
+        if self.payload.is_some() {
             return Err(anyhow!(
-                "Protected Header does not contain a valid Signature Algorithm specification",
+                "COSE_Sign1 has an embedded payload, cannot verify a detached one"
             ));
         }
+        if self.protected_algorithm()? != sig_algo_val(sig_algo)? {
+            return Ok(false);
+        }
 
-        let sig_structure = SigStructure::new_sign1(&self.protected, &self.payload)?;
+        let sig_structure = SigStructure::new_sign1(&self.protected, external_payload)?;
 
         let tbs = sig_structure.as_bytes()?;
 
@@ -126,6 +221,23 @@ impl Serialize for CoseSign1 {
     }
 }
 
+/// The COSE_Sign1 signature field in the forms seen from producers: a bstr as the spec
+/// requires, or that bstr wrapped in a single-element array. Anything else is rejected.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SignatureField {
+    Flat(ByteBuf),
+    Nested([ByteBuf; 1]),
+}
+
+impl SignatureField {
+    fn into_bytes(self) -> ByteBuf {
+        match self {
+            SignatureField::Flat(signature) | SignatureField::Nested([signature]) => signature,
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for CoseSign1 {
     fn deserialize<D>(deserializer: D) -> Result<CoseSign1, D::Error>
     where
@@ -148,26 +260,31 @@ impl<'de> Deserialize<'de> for CoseSign1 {
                 A: SeqAccess<'de>,
             {
                 // This is the untagged version
-                let protected = match seq.next_element()? {
+                let protected: ByteBuf = match seq.next_element()? {
                     Some(v) => v,
                     None => return Err(A::Error::missing_field("protected")),
                 };
+                let protected_header = serde_cbor::from_slice(&protected).map_err(|err| {
+                    A::Error::custom(format!("invalid protected header: {:?}", err))
+                })?;
 
                 let unprotected = match seq.next_element()? {
                     Some(v) => v,
                     None => return Err(A::Error::missing_field("unprotected")),
                 };
+                // a nil payload is detached
                 let payload = match seq.next_element()? {
                     Some(v) => v,
                     None => return Err(A::Error::missing_field("payload")),
                 };
-                let signature = match seq.next_element()? {
-                    Some(v) => v,
+                let signature = match seq.next_element::<SignatureField>()? {
+                    Some(v) => v.into_bytes(),
                     None => return Err(A::Error::missing_field("signature")),
                 };
 
                 Ok(CoseSign1 {
                     protected,
+                    protected_header,
                     unprotected,
                     payload,
                     signature,
@@ -187,6 +304,226 @@ impl<'de> Deserialize<'de> for CoseSign1 {
     }
 }
 
+/// One signer of a [`CoseSign`] (COSE_Signature).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoseSignature {
+    /// protected: empty_or_serialized_map, must carry the signature algorithm
+    pub protected: ByteBuf,
+    /// unprotected: HeaderMap
+    pub unprotected: HeaderMap,
+    /// signature: bstr
+    pub signature: ByteBuf,
+}
+
+impl CoseSignature {
+    /// Returns the COSE algorithm identifier declared in this signer's protected header.
+    pub fn protected_algorithm(&self) -> anyhow::Result<i8> {
+        header_algorithm(&self.protected)
+    }
+}
+
+/// COSE_Sign (tag 98): a payload signed by one or more signers.
+///
+/// Nitro attestation documents are always [`CoseSign1`]; this is for tooling that wraps
+/// the same payloads with several signatures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoseSign {
+    /// protected: empty_or_serialized_map,
+    pub protected: ByteBuf,
+    /// unprotected: HeaderMap
+    pub unprotected: HeaderMap,
+    /// payload: bstr
+    pub payload: ByteBuf,
+    /// signatures: [+ COSE_Signature]
+    pub signatures: Vec<CoseSignature>,
+}
+
+impl CoseSign {
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let cosesign: serde_cbor::tags::Tagged<Self> = serde_cbor::from_slice(bytes)
+            .map_err(|err| anyhow!("deserialization failed: {:?}", err))?;
+
+        match cosesign.tag {
+            None | Some(98) => (),
+            Some(tag) => return Err(anyhow!("tag error: {:?}", tag)),
+        }
+        if !cosesign.value.protected.is_empty() {
+            let _: HeaderMap = serde_cbor::from_slice(&cosesign.value.protected)
+                .map_err(|err| anyhow!("deserialization failed: {:?}", err))?;
+        }
+        if cosesign.value.signatures.is_empty() {
+            return Err(anyhow!("COSE_Sign has no signatures"));
+        }
+        Ok(cosesign.value)
+    }
+
+    /// Serializes back to CBOR, with the COSE_Sign tag when `tagged` is set.
+    pub fn to_bytes(&self, tagged: bool) -> anyhow::Result<Vec<u8>> {
+        let tag = if tagged { Some(98) } else { None };
+        serde_cbor::to_vec(&serde_cbor::tags::Tagged::new(tag, self))
+            .map_err(|err| anyhow!("serialization failed: {:?}", err))
+    }
+
+    /// Returns whether at least one signature verifies under one of `keys`.
+    ///
+    /// Each signature is checked with the algorithm from its own protected header against
+    /// every key of a compatible type. Signatures whose protected header is malformed or
+    /// names an unsupported algorithm are skipped.
+    pub fn verify_any(&self, keys: &[PubKey]) -> anyhow::Result<bool> {
+        for signature in &self.signatures {
+            let Ok(sig_algo) = signature.protected_algorithm().and_then(sig_algo_from_val) else {
+                continue;
+            };
+            let tbs = SigStructure::new_sign(&self.protected, &signature.protected, &self.payload)
+                .as_bytes()?;
+            for key in keys {
+                if sig_algo.check_compatible_with(key.algo).is_err() {
+                    continue;
+                }
+                if let Ok(true) =
+                    verify_signature(key.clone(), sig_algo, &signature.signature, &tbs)
+                {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl Serialize for CoseSignature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(3))?;
+        seq.serialize_element(&self.protected)?;
+        seq.serialize_element(&self.unprotected)?;
+        seq.serialize_element(&self.signature)?;
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for CoseSignature {
+    fn deserialize<D>(deserializer: D) -> Result<CoseSignature, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::{Error, SeqAccess, Visitor};
+        use std::fmt;
+
+        struct CoseSignatureVisitor;
+
+        impl<'de> Visitor<'de> for CoseSignatureVisitor {
+            type Value = CoseSignature;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a COSE_Signature structure")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<CoseSignature, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let protected = match seq.next_element()? {
+                    Some(v) => v,
+                    None => return Err(A::Error::missing_field("protected")),
+                };
+                let unprotected = match seq.next_element()? {
+                    Some(v) => v,
+                    None => return Err(A::Error::missing_field("unprotected")),
+                };
+                let signature = match seq.next_element()? {
+                    Some(v) => v,
+                    None => return Err(A::Error::missing_field("signature")),
+                };
+
+                Ok(CoseSignature {
+                    protected,
+                    unprotected,
+                    signature,
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(CoseSignatureVisitor)
+    }
+}
+
+impl Serialize for CoseSign {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(4))?;
+        seq.serialize_element(&self.protected)?;
+        seq.serialize_element(&self.unprotected)?;
+        seq.serialize_element(&self.payload)?;
+        seq.serialize_element(&self.signatures)?;
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for CoseSign {
+    fn deserialize<D>(deserializer: D) -> Result<CoseSign, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::{Error, SeqAccess, Visitor};
+        use std::fmt;
+
+        struct CoseSignVisitor;
+
+        impl<'de> Visitor<'de> for CoseSignVisitor {
+            type Value = CoseSign;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a possibly tagged CoseSign structure")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<CoseSign, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                // This is the untagged version
+                let protected = match seq.next_element()? {
+                    Some(v) => v,
+                    None => return Err(A::Error::missing_field("protected")),
+                };
+                let unprotected = match seq.next_element()? {
+                    Some(v) => v,
+                    None => return Err(A::Error::missing_field("unprotected")),
+                };
+                let payload = match seq.next_element()? {
+                    Some(v) => v,
+                    None => return Err(A::Error::missing_field("payload")),
+                };
+                let signatures = match seq.next_element()? {
+                    Some(v) => v,
+                    None => return Err(A::Error::missing_field("signatures")),
+                };
+
+                Ok(CoseSign {
+                    protected,
+                    unprotected,
+                    payload,
+                    signatures,
+                })
+            }
+
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<CoseSign, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                // This is the tagged version: we ignore the tag part, and just go into it
+                deserializer.deserialize_seq(CoseSignVisitor)
+            }
+        }
+
+        deserializer.deserialize_any(CoseSignVisitor)
+    }
+}
+
 ///  Implementation of the Sig_structure as defined in
 ///  [RFC8152](https://tools.ietf.org/html/rfc8152#section-4.4).
 ///
@@ -251,15 +588,37 @@ impl SigStructure {
     /// Takes the protected field of the COSE_Sign object and a raw slice of bytes as payload and creates a
     /// SigStructure for one signer from it
     pub fn new_sign1(body_protected: &[u8], payload: &[u8]) -> anyhow::Result<Self> {
+        Self::new_sign1_with_aad(body_protected, &[], payload)
+    }
+
+    /// Same as [`SigStructure::new_sign1`] with the given `external_aad` instead of an empty
+    /// one
+    pub fn new_sign1_with_aad(
+        body_protected: &[u8],
+        external_aad: &[u8],
+        payload: &[u8],
+    ) -> anyhow::Result<Self> {
         Ok(SigStructure(
             String::from("Signature1"),
             ByteBuf::from(body_protected.to_vec()),
             None,
-            ByteBuf::new(),
+            ByteBuf::from(external_aad.to_vec()),
             ByteBuf::from(payload.to_vec()),
         ))
     }
 
+    /// Takes the protected fields of the COSE_Sign object and of one of its signers, and a
+    /// raw slice of bytes as payload, and creates the SigStructure for that signer
+    pub fn new_sign(body_protected: &[u8], sign_protected: &[u8], payload: &[u8]) -> Self {
+        SigStructure(
+            String::from("Signature"),
+            ByteBuf::from(body_protected.to_vec()),
+            Some(ByteBuf::from(sign_protected.to_vec())),
+            ByteBuf::new(),
+            ByteBuf::from(payload.to_vec()),
+        )
+    }
+
     /// Takes the protected field of the COSE_Sign object and a CborValue as payload and creates a
     /// SigStructure for one signer from it
     pub fn new_sign1_cbor_value(
@@ -277,3 +636,249 @@ impl SigStructure {
         Ok(serde_cbor::to_vec(self).map_err(|err| anyhow!("serialization failed: {:?}", err))?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::hex;
+    use serde_cbor::Value as CborValue;
+    use x509_verifier_rust_crypto::{KeyAlgo, KeyAlgoParams, PubKey, SigAlgo};
+
+    use super::{header_algorithm, parse_header, CoseSign, CoseSign1, CoseSignature, HeaderMap};
+
+    // signers of samples/multi_sig.cose
+    const MULTI_SIG_P256_PUBKEY: &str = "0400347dc1145d091fe29c217dfdb03d995c8aa53e0fe1bd72679305ecda63a4008eb5d446fd1776a899ab1013c5b65fca7c3e774710e1ee8646b7d6b74aed4bab";
+    const MULTI_SIG_P384_PUBKEY: &str = "04ecc45de07c80d6c3f7fc2e135cc347db2d854b65bef1ced3972a1f8a29681a69a068743e0fe83e81adac9503911991063e76bd92a9bca8b3afacc2b2daaf69effb8f672f080e69acff26b0f4884eccfa97088c2f47328866aadc351132a5092e";
+
+    // signer of samples/es384_aad.cose, signed with external_aad `application aad`
+    const ES384_AAD_PUBKEY: &str = "04beb9032ffc4e278c74c85e1f797c29a81163e26411a7e505c65e6f9588da31b8277018e4b7c448ed39921ceb7c0f756741160b6ba59a3e4444330b3cf84c7757a5946915944de2c805cc12f116de7131a5be4bd187908e44cc311f501b9f7b34";
+
+    const ES512_PUBKEY: &str = "040015ac5ec23aa5c052eeb483d9848d76ef36cab36b6a969aefa0c24da30d645b20787bdcc7514ca7034a1eb6b4ddc0bbd0f2265d593c97e2ea021b87ac64a057cf3d01001daf221db2792cfea51c50c33fe03690494fe28f2e5184236b4e2e7e8b92698bfcefe83e904188b14d3759c5510312808238ab5ddf354a715f1d9f97187c0208";
+
+    #[test]
+    fn test_verify_es512() {
+        let cose_sign =
+            CoseSign1::from_bytes(&std::fs::read("./samples/es512.cose").unwrap()).unwrap();
+        let key = hex::decode(ES512_PUBKEY).unwrap();
+        let pubkey = PubKey {
+            algo: KeyAlgo::ECDSA(KeyAlgoParams::P521),
+            val: &key,
+        };
+        assert!(cose_sign
+            .verify_signature(SigAlgo::EcdsaSHA512, pubkey.clone())
+            .unwrap());
+        // the protected header declares ES512, so any other algorithm is rejected
+        assert_eq!(cose_sign.protected_algorithm().unwrap(), -36);
+        assert!(!cose_sign
+            .verify_signature(SigAlgo::EcdsaSHA384, pubkey)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_cached_protected_header() {
+        for path in [
+            "./samples/es512.cose",
+            "./samples/es512_nested_signature.cose",
+            "./samples/es512_detached.cose",
+            "./samples/es384_aad.cose",
+            "../../samples/attestation_1.report",
+        ] {
+            let cose_sign = CoseSign1::from_bytes(&std::fs::read(path).unwrap()).unwrap();
+            assert_eq!(
+                cose_sign.protected_header,
+                parse_header(&cose_sign.protected).unwrap(),
+                "{}",
+                path
+            );
+            assert_eq!(
+                cose_sign.protected_algorithm().unwrap(),
+                header_algorithm(&cose_sign.protected).unwrap(),
+                "{}",
+                path
+            );
+        }
+
+        // a protected header that is not a map still fails to parse
+        let flat = CoseSign1::from_bytes(&std::fs::read("./samples/es512.cose").unwrap()).unwrap();
+        let protected = serde_bytes::ByteBuf::from(vec![0x01]);
+        let data =
+            serde_cbor::to_vec(&(protected, &flat.unprotected, &flat.payload, &flat.signature))
+                .unwrap();
+        assert!(CoseSign1::from_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn test_tolerated_variants() {
+        let key = hex::decode(ES512_PUBKEY).unwrap();
+        let pubkey = PubKey {
+            algo: KeyAlgo::ECDSA(KeyAlgoParams::P521),
+            val: &key,
+        };
+        let flat = CoseSign1::from_bytes(&std::fs::read("./samples/es512.cose").unwrap()).unwrap();
+
+        // es512.cose with the signature wrapped in a single-element array
+        let nested = std::fs::read("./samples/es512_nested_signature.cose").unwrap();
+        let nested = CoseSign1::from_bytes(&nested).unwrap();
+        assert_eq!(nested.signature, flat.signature);
+        assert!(nested
+            .verify_signature(SigAlgo::EcdsaSHA512, pubkey.clone())
+            .unwrap());
+
+        // es512.cose tagged, with a nil payload
+        let detached = std::fs::read("./samples/es512_detached.cose").unwrap();
+        let detached = CoseSign1::from_bytes(&detached).unwrap();
+        assert!(detached.payload.is_none());
+        let payload = flat.payload.as_ref().unwrap();
+        assert!(detached
+            .verify_signature_detached(SigAlgo::EcdsaSHA512, pubkey, payload)
+            .unwrap());
+
+        // other signature shapes are still rejected
+        for signature in [
+            CborValue::Array(vec![
+                CborValue::Bytes(flat.signature.to_vec()),
+                CborValue::Bytes(flat.signature.to_vec()),
+            ]),
+            CborValue::Array(vec![]),
+            CborValue::Integer(1),
+        ] {
+            let data =
+                serde_cbor::to_vec(&(&flat.protected, &flat.unprotected, &flat.payload, signature))
+                    .unwrap();
+            assert!(CoseSign1::from_bytes(&data).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_external_aad() {
+        let cose_sign =
+            CoseSign1::from_bytes(&std::fs::read("./samples/es384_aad.cose").unwrap()).unwrap();
+        let key = hex::decode(ES384_AAD_PUBKEY).unwrap();
+        let pubkey = PubKey {
+            algo: KeyAlgo::ECDSA(KeyAlgoParams::P384),
+            val: &key,
+        };
+        assert!(cose_sign
+            .verify_signature_with_aad(SigAlgo::EcdsaSHA384, pubkey.clone(), b"application aad")
+            .unwrap());
+        assert!(!cose_sign
+            .verify_signature_with_aad(SigAlgo::EcdsaSHA384, pubkey.clone(), b"other aad")
+            .unwrap());
+        // the default is an empty aad
+        assert!(!cose_sign
+            .verify_signature(SigAlgo::EcdsaSHA384, pubkey)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_verify_detached_payload() {
+        let attached =
+            CoseSign1::from_bytes(&std::fs::read("./samples/es512.cose").unwrap()).unwrap();
+        let key = hex::decode(ES512_PUBKEY).unwrap();
+        let pubkey = PubKey {
+            algo: KeyAlgo::ECDSA(KeyAlgoParams::P521),
+            val: &key,
+        };
+        // same signature with the payload detached, encoded as nil
+        let detached = serde_cbor::to_vec(&(
+            &attached.protected,
+            &attached.unprotected,
+            CborValue::Null,
+            &attached.signature,
+        ))
+        .unwrap();
+        let detached_bytes = detached;
+        let detached = CoseSign1::from_bytes(&detached_bytes).unwrap();
+        assert!(detached.payload.is_none());
+        // nil round-trips as nil, not as an empty bstr
+        assert_eq!(detached.to_bytes(false).unwrap(), detached_bytes);
+        assert!(detached
+            .verify_signature(SigAlgo::EcdsaSHA512, pubkey.clone())
+            .is_err());
+
+        // an embedded empty payload is not detached
+        let empty = serde_cbor::to_vec(&(
+            &attached.protected,
+            &attached.unprotected,
+            serde_bytes::ByteBuf::new(),
+            &attached.signature,
+        ))
+        .unwrap();
+        let empty = CoseSign1::from_bytes(&empty).unwrap();
+        assert_eq!(empty.payload.as_deref().map(|p| p.len()), Some(0));
+        assert!(empty
+            .verify_signature_detached(SigAlgo::EcdsaSHA512, pubkey.clone(), b"")
+            .is_err());
+
+        let payload = attached.payload.as_ref().unwrap();
+        assert!(detached
+            .verify_signature_detached(SigAlgo::EcdsaSHA512, pubkey.clone(), payload)
+            .unwrap());
+        assert!(!detached
+            .verify_signature_detached(SigAlgo::EcdsaSHA512, pubkey.clone(), b"tampered")
+            .unwrap());
+        assert!(attached
+            .verify_signature_detached(SigAlgo::EcdsaSHA512, pubkey, payload)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cose_sign_round_trip() {
+        let data = std::fs::read("./samples/multi_sig.cose").unwrap();
+        let cose_sign = CoseSign::from_bytes(&data).unwrap();
+        assert_eq!(cose_sign.signatures.len(), 2);
+        assert_eq!(cose_sign.signatures[0].protected_algorithm().unwrap(), -7);
+        assert_eq!(cose_sign.signatures[1].protected_algorithm().unwrap(), -35);
+        assert_eq!(cose_sign.to_bytes(true).unwrap(), data);
+
+        let untagged = cose_sign.to_bytes(false).unwrap();
+        assert_eq!(CoseSign::from_bytes(&untagged).unwrap(), cose_sign);
+
+        // a COSE_Sign1 document is not a COSE_Sign
+        assert!(CoseSign::from_bytes(&std::fs::read("./samples/es512.cose").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_cose_sign_verify_any() {
+        let cose_sign =
+            CoseSign::from_bytes(&std::fs::read("./samples/multi_sig.cose").unwrap()).unwrap();
+        let p256 = hex::decode(MULTI_SIG_P256_PUBKEY).unwrap();
+        let p384 = hex::decode(MULTI_SIG_P384_PUBKEY).unwrap();
+        let es512 = hex::decode(ES512_PUBKEY).unwrap();
+        let p256 = PubKey {
+            algo: KeyAlgo::ECDSA(KeyAlgoParams::P256),
+            val: &p256,
+        };
+        let p384 = PubKey {
+            algo: KeyAlgo::ECDSA(KeyAlgoParams::P384),
+            val: &p384,
+        };
+        let es512 = PubKey {
+            algo: KeyAlgo::ECDSA(KeyAlgoParams::P521),
+            val: &es512,
+        };
+        assert!(cose_sign.verify_any(&[p256.clone()]).unwrap());
+        assert!(cose_sign
+            .verify_any(&[es512.clone(), p384.clone()])
+            .unwrap());
+        assert!(!cose_sign.verify_any(&[es512]).unwrap());
+        assert!(!cose_sign.verify_any(&[]).unwrap());
+
+        // signers with a malformed or unsupported protected header are skipped, not fatal
+        let mut skipped = cose_sign.clone();
+        for protected in [
+            vec![0x01],
+            serde_cbor::to_vec(&HeaderMap::default()).unwrap(),
+        ] {
+            let signature = CoseSignature {
+                protected: serde_bytes::ByteBuf::from(protected),
+                ..skipped.signatures[1].clone()
+            };
+            skipped.signatures.insert(0, signature);
+        }
+        assert!(skipped.verify_any(&[p384.clone()]).unwrap());
+
+        let mut tampered = cose_sign.clone();
+        tampered.payload = b"tampered".to_vec().into();
+        assert!(!tampered.verify_any(&[p256, p384]).unwrap());
+    }
+}
@@ -1,11 +1,20 @@
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap};
 
+use alloy_primitives::{b256, hex, B256};
 use anyhow::{anyhow, Context};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_bytes::{ByteArray, ByteBuf};
-use x509_verifier_rust_crypto::{CertChain, SigAlgo};
+use subtle::ConstantTimeEq;
+use x509_verifier_rust_crypto::{
+    ec_decode_sig, sha256, verify_signature,
+    x509_parser::{
+        prelude::{FromDer, SubjectPublicKeyInfo},
+        time::ASN1Time,
+    },
+    Cert, CertChain, ChainPolicy, KeyAlgo, KeyAlgoParams, PubKey, SigAlgo,
+};
 
-use crate::CoseSign1;
+use crate::{policy, AttestationPolicy, CoseSign1, SignatureAlgorithm};
 
 #[derive(Debug)]
 pub struct AttestationReport {
@@ -14,19 +23,69 @@ pub struct AttestationReport {
 }
 
 impl AttestationReport {
+    /// Parses a report with the default [`ParseLimits`].
+    ///
+    /// The input is untrusted: any malformed report is reported as an `Err`, never as a
+    /// panic.
     pub fn parse(document_data: &[u8]) -> anyhow::Result<Self> {
+        Self::parse_with_limits(document_data, &ParseLimits::default())
+    }
+
+    /// Parses a report, rejecting it if it or any of its variable-size fields is larger
+    /// than `limits` allows.
+    pub fn parse_with_limits(document_data: &[u8], limits: &ParseLimits) -> anyhow::Result<Self> {
+        // no field decoded below can be larger than the report itself
+        check_size("report", document_data.len(), limits.max_report_size)?;
         let cose_sign = CoseSign1::from_bytes(document_data)
             .with_context(|| "AttestationDocument::authenticate parse failed")?;
         // Step 2. Exract the attestation document from the COSE_Sign1 structure
-        let doc: AttestationDocument = serde_cbor::from_slice(&cose_sign.payload)
+        let payload = cose_sign
+            .payload
+            .as_ref()
+            .ok_or_else(|| anyhow!("document parse failed: payload is detached"))?;
+        let doc: AttestationDocument = serde_cbor::from_slice(payload)
             .map_err(|err| anyhow!("document parse failed: {:?}", err))?;
+        doc.validate_digest_algorithm()?;
+        doc.validate_certificate()?;
+        doc.check_limits(limits)?;
 
         Ok(Self { doc, cose_sign })
     }
 
+    /// Parses a report given as hex, with or without a `0x` prefix, e.g. from a JSON-RPC
+    /// response. Fails with an "invalid hex" error before attempting to parse.
+    pub fn parse_hex(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let data = hex::decode(s.strip_prefix("0x").unwrap_or(s))
+            .map_err(|err| anyhow!("invalid hex report: {}", err))?;
+        Self::parse(&data)
+    }
+
+    /// Builds the chain root -> leaf, taking the cabundle to be root first as AWS emits it.
+    ///
+    /// The order is never detected here, nor by [`AttestationReport::authenticate`] and
+    /// [`verify_attestation_report`](crate::verify_attestation_report), which build on this:
+    /// the verifier programs and the contract's cert digests assume root first, so a
+    /// leaf-to-root bundle fails verification. Hosts that accept other producers must
+    /// detect the order with [`AttestationReport::bundle_order`] and build the chain with
+    /// [`AttestationReport::cert_chain_ordered`] themselves.
     pub fn cert_chain(&self) -> anyhow::Result<CertChain> {
+        self.cert_chain_ordered(BundleOrder::RootFirst)
+    }
+
+    /// Builds the chain root -> leaf, taking the cabundle to be in `order`.
+    ///
+    /// Fails on an empty cabundle: the leaf alone would end up as the chain's root.
+    pub fn cert_chain_ordered(&self, order: BundleOrder) -> anyhow::Result<CertChain> {
+        if self.doc.cabundle.is_empty() {
+            return Err(anyhow!("attestation cabundle is empty; cannot build chain"));
+        }
+        let mut cabundle: Vec<&ByteBuf> = self.doc.cabundle.iter().collect();
+        if order == BundleOrder::LeafFirst {
+            cabundle.reverse();
+        }
         let mut cert_chain = CertChain::new();
-        for cert in &self.doc.cabundle {
+        for cert in cabundle {
             cert_chain.add_cert_by_der(cert)?;
         }
         cert_chain.add_cert_by_der(&self.doc.certificate)?;
@@ -34,6 +93,25 @@ impl AttestationReport {
         Ok(cert_chain)
     }
 
+    /// Detects the cabundle order. The bundle is taken as leaf first only when its first
+    /// cert is not self-issued and its last cert is; anything else, including a bundle of
+    /// one cert, is root first as AWS emits it.
+    pub fn bundle_order(&self) -> anyhow::Result<BundleOrder> {
+        let self_issued = |der: &[u8]| -> anyhow::Result<bool> {
+            let cert = Cert::parse_der(der)?;
+            Ok(cert.raw.subject().as_raw() == cert.raw.issuer().as_raw())
+        };
+        let cabundle = &self.doc.cabundle;
+        if cabundle.len() < 2 || self_issued(&cabundle[0])? {
+            return Ok(BundleOrder::RootFirst);
+        }
+        Ok(if self_issued(&cabundle[cabundle.len() - 1])? {
+            BundleOrder::LeafFirst
+        } else {
+            BundleOrder::RootFirst
+        })
+    }
+
     pub fn doc(&self) -> &AttestationDocument {
         &self.doc
     }
@@ -43,17 +121,67 @@ impl AttestationReport {
         &self,
         trusted_certs_len: usize,
         timestamp: u64,
+    ) -> anyhow::Result<CertChain> {
+        self.authenticate_with_root(trusted_certs_len, timestamp, None)
+    }
+
+    /// Same as [`AttestationReport::authenticate`], but when `expected_root` is set the
+    /// chain's root must also have that digest, so the trusted prefix is anchored to a
+    /// known root (e.g. the AWS Nitro root) without consulting a contract.
+    pub fn authenticate_with_root(
+        &self,
+        trusted_certs_len: usize,
+        timestamp: u64,
+        expected_root: Option<B256>,
+    ) -> anyhow::Result<CertChain> {
+        let opts = AuthenticateOptions {
+            expected_root,
+            ..Default::default()
+        };
+        self.authenticate_with(trusted_certs_len, timestamp, &opts)
+    }
+
+    /// [`AttestationReport::authenticate`] with the optional checks in `opts` applied.
+    pub fn authenticate_with(
+        &self,
+        trusted_certs_len: usize,
+        timestamp: u64,
+        opts: &AuthenticateOptions,
     ) -> anyhow::Result<CertChain> {
         let cert_chain = self.cert_chain()?;
-        match cert_chain.verify_chain(trusted_certs_len) {
+        if let Some(expected_root) = opts.expected_root {
+            cert_chain.verify_root_pinned(expected_root)?;
+        }
+        if opts.require_nsm_leaf && !cert_chain.leaf().is_nsm_signing_cert() {
+            return Err(anyhow!("leaf cert is not an NSM signing cert"));
+        }
+        let policy = ChainPolicy {
+            algorithm_allowlist: opts.algorithm_allowlist.clone(),
+            ..Default::default()
+        };
+        match cert_chain.verify_chain_with_policy(trusted_certs_len, &policy) {
             Ok(true) => {}
             Ok(false) => return Err(anyhow!("failed to verify x509 chain")),
             Err(err) => return Err(anyhow!("failed to verify x509 chain: {:?}", err)),
         };
-        cert_chain.check_valid(timestamp)?;
+        if !opts.skip_cert_validity {
+            cert_chain.check_valid(timestamp)?;
+        }
 
         let pubkey = cert_chain.leaf_pubkey();
         let sig_algo = SigAlgo::EcdsaSHA384;
+        let protected_alg = self.cose_sign.protected_algorithm()?;
+        if protected_alg != SignatureAlgorithm::ES384 as i8 {
+            return Err(anyhow!("expected ES384, got {}", protected_alg));
+        }
+        if let Some(allowlist) = &opts.algorithm_allowlist {
+            if !allowlist.contains(&sig_algo) {
+                return Err(anyhow!(
+                    "COSE signature algorithm {:?} is not allowed",
+                    sig_algo
+                ));
+            }
+        }
 
         let result = self.cose_sign.verify_signature(sig_algo, pubkey)?;
         if !result {
@@ -64,9 +192,337 @@ impl AttestationReport {
 
         return Ok(cert_chain);
     }
+
+    /// Checks that the document's `nonce` equals the challenge `expected`, in constant time
+    /// for equal-length inputs. Fails if the document has no nonce.
+    pub fn verify_nonce(&self, expected: &[u8]) -> anyhow::Result<()> {
+        let nonce = self
+            .doc
+            .nonce
+            .as_ref()
+            .ok_or_else(|| anyhow!("attestation document has no nonce"))?;
+        if !bool::from(nonce.as_slice().ct_eq(expected)) {
+            return Err(anyhow!("nonce mismatch"));
+        }
+        Ok(())
+    }
+
+    /// Checks the document's PCRs against known-good values keyed by PCR index. Indices not
+    /// in `expected` are not checked; an expected index missing from the document fails.
+    pub fn check_pcrs(&self, expected: &BTreeMap<u64, [u8; 48]>) -> anyhow::Result<()> {
+        policy::check_pcrs(&self.doc, expected)
+    }
+
+    /// Compares two reports by content rather than by encoding.
+    ///
+    /// Two reports are equal if they carry the same module id, timestamp, PCRs, user data,
+    /// nonce and public key, and their cert chains have the same path digests. The COSE
+    /// envelope (tagging, header encoding, signature bytes) is ignored.
+    pub fn semantically_eq(&self, other: &AttestationReport) -> bool {
+        let (a, b) = (&self.doc, &other.doc);
+        if a.module_id != b.module_id
+            || a.timestamp != b.timestamp
+            || a.pcrs != b.pcrs
+            || a.user_data != b.user_data
+            || a.nonce != b.nonce
+            || a.public_key != b.public_key
+        {
+            return false;
+        }
+        match (self.cert_chain(), other.cert_chain()) {
+            (Ok(a), Ok(b)) => a.digest() == b.digest(),
+            _ => false,
+        }
+    }
+
+    /// Authenticates the report once and evaluates every named policy against it.
+    ///
+    /// The chain is verified with only the root as trusted prefix, and the root must have
+    /// the digest `expected_root` (e.g. [`AWS_NITRO_ROOT_DIGEST`]). If authentication fails,
+    /// every policy reports the authentication error.
+    pub fn evaluate_policies(
+        &self,
+        policies: &[(&str, &AttestationPolicy)],
+        timestamp: u64,
+        expected_root: B256,
+    ) -> Vec<(String, anyhow::Result<()>)> {
+        let authenticated = self
+            .authenticate_with_root(1, timestamp, Some(expected_root))
+            .map(|_| ());
+        policies
+            .iter()
+            .map(|(name, policy)| {
+                let result = match &authenticated {
+                    Ok(()) => policy.evaluate(&self.doc),
+                    Err(err) => Err(anyhow!("authentication failed: {:#}", err)),
+                };
+                (name.to_string(), result)
+            })
+            .collect()
+    }
+
+    /// Runs [`AttestationReport::authenticate_with`] with only the root trusted, followed by
+    /// `policy` if given, and narrates each step in prose. Set `opts.expected_root` (e.g. to
+    /// [`AWS_NITRO_ROOT_DIGEST`]) so that a self-rooted report cannot pass.
+    ///
+    /// On failure the narrative stops at the first failing step and says why. Meant for
+    /// humans; use `authenticate_with` and [`AttestationPolicy::evaluate`] to act on the
+    /// result.
+    pub fn explain_verification(
+        &self,
+        policy: Option<&AttestationPolicy>,
+        timestamp: u64,
+        opts: &AuthenticateOptions,
+    ) -> String {
+        let mut steps = Vec::new();
+        match self.explain_steps(policy, timestamp, opts, &mut steps) {
+            Ok(()) => steps.push("Verification succeeded.".to_string()),
+            Err(err) => steps.push(format!("Verification failed: {:#}.", err)),
+        }
+        steps.join(" ")
+    }
+
+    fn explain_steps(
+        &self,
+        policy: Option<&AttestationPolicy>,
+        timestamp: u64,
+        opts: &AuthenticateOptions,
+        steps: &mut Vec<String>,
+    ) -> anyhow::Result<()> {
+        let alg = self.cose_sign.protected_algorithm()?;
+        let alg_name = match alg {
+            alg if alg == SignatureAlgorithm::ES256 as i8 => "ES256".to_string(),
+            alg if alg == SignatureAlgorithm::ES384 as i8 => "ES384".to_string(),
+            alg if alg == SignatureAlgorithm::ES512 as i8 => "ES512".to_string(),
+            alg => format!("algorithm {}", alg),
+        };
+        steps.push(format!("Parsed COSE_Sign1 ({}).", alg_name));
+
+        let cert_chain = self.cert_chain()?;
+        steps.push(format!(
+            "Built {}-cert chain rooted at {}.",
+            cert_chain.certs.len(),
+            cert_chain.root().raw.subject()
+        ));
+
+        let time = ASN1Time::from_timestamp(timestamp as i64)
+            .map_err(|_| anyhow!("invalid timestamp {}", timestamp))?;
+        self.authenticate_with(1, timestamp, opts)
+            .map_err(|err| anyhow!("report did not authenticate at {}: {:#}", time, err))?;
+        if let Some(expected_root) = opts.expected_root {
+            steps.push(format!("Root matched pinned digest {}.", expected_root));
+        }
+        steps.push(format!(
+            "Verified {} signatures.",
+            cert_chain.certs.len() - 1
+        ));
+        if !opts.skip_cert_validity {
+            steps.push(format!("Chain valid at {}.", time));
+        }
+        steps.push("COSE signature valid.".to_string());
+
+        if let Some(policy) = policy {
+            policy.evaluate(&self.doc)?;
+            steps.push("Document matched policy.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Verifies an application-level `signature` over `message` made by the enclave key
+    /// carried in the document's `public_key`.
+    ///
+    /// The key may be a DER SubjectPublicKeyInfo or an uncompressed SEC1 point. ECDSA
+    /// signatures are accepted either DER-encoded or as raw `r || s`.
+    ///
+    /// This does not authenticate the report itself; call `authenticate` first.
+    pub fn verify_enclave_signature(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        sig_algo: SigAlgo,
+    ) -> anyhow::Result<bool> {
+        let pubkey = self
+            .enclave_public_key()?
+            .ok_or_else(|| anyhow!("attestation document has no public_key"))?;
+        sig_algo.check_compatible_with(pubkey.algo)?;
+
+        let mut sig = Cow::Borrowed(signature);
+        if let KeyAlgo::ECDSA(params) = pubkey.algo {
+            let raw_len = match params {
+                KeyAlgoParams::P256 => 64,
+                KeyAlgoParams::P384 => 96,
+                KeyAlgoParams::P521 => 132,
+            };
+            if signature.len() != raw_len {
+                sig = Cow::Owned(ec_decode_sig(signature, params)?);
+            }
+        }
+        verify_signature(pubkey, sig_algo, &sig, message)
+    }
+
+    /// Checks that `user_data` commits to the report's `public_key` using the default
+    /// [`PublicKeyBinding::Sha256Prefix`] format.
+    pub fn verify_public_key_binding(&self) -> anyhow::Result<()> {
+        self.verify_public_key_binding_with(&PublicKeyBinding::default())
+    }
+
+    /// Checks that `user_data` commits to the report's `public_key` in the given `binding`
+    /// format. Fails if either field is missing, if `user_data` does not have the expected
+    /// structure, or if the committed digest differs from the actual key.
+    pub fn verify_public_key_binding_with(&self, binding: &PublicKeyBinding) -> anyhow::Result<()> {
+        let public_key = self
+            .doc
+            .public_key
+            .as_ref()
+            .ok_or_else(|| anyhow!("attestation document has no public_key"))?;
+        let user_data = self
+            .doc
+            .user_data
+            .as_ref()
+            .ok_or_else(|| anyhow!("attestation document has no user_data"))?;
+        let expected = sha256(public_key);
+
+        let committed = match binding {
+            PublicKeyBinding::Sha256Prefix => user_data
+                .get(..32)
+                .map(B256::from_slice)
+                .ok_or_else(|| anyhow!("user_data is shorter than a sha256 digest"))?,
+            PublicKeyBinding::JsonSha256Field(field) => {
+                let value: serde_json::Value = serde_json::from_slice(user_data)
+                    .map_err(|err| anyhow!("user_data is not JSON: {}", err))?;
+                let digest = value
+                    .get(field)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow!("user_data has no string field {:?}", field))?;
+                digest
+                    .parse::<B256>()
+                    .map_err(|err| anyhow!("invalid digest in user_data.{}: {}", field, err))?
+            }
+        };
+        if committed != expected {
+            return Err(anyhow!(
+                "public_key binding mismatch: user_data commits to {}, public_key hashes to {}",
+                committed,
+                expected
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses the document's `public_key`, e.g. to bind a TLS session to the attested key.
+    ///
+    /// The key may be a DER SubjectPublicKeyInfo or an uncompressed SEC1 point. Returns
+    /// `None` if the document carries no public key, and an error if it is not a supported
+    /// key encoding.
+    pub fn enclave_public_key(&self) -> anyhow::Result<Option<PubKey>> {
+        match &self.doc.public_key {
+            Some(data) => parse_public_key(data).map(Some),
+            None => Ok(None),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+fn parse_public_key(data: &[u8]) -> anyhow::Result<PubKey> {
+    if let Ok(([], spki)) = SubjectPublicKeyInfo::from_der(data) {
+        let algo = KeyAlgo::from_algo(&spki.algorithm)?;
+        // the subject public key BIT STRING is the last element of the SPKI
+        let key_len = spki.subject_public_key.data.len();
+        return Ok(PubKey {
+            algo,
+            val: &data[data.len() - key_len..],
+        });
+    }
+    let algo = match (data.len(), data.first()) {
+        (65, Some(0x04)) => KeyAlgo::ECDSA(KeyAlgoParams::P256),
+        (97, Some(0x04)) => KeyAlgo::ECDSA(KeyAlgoParams::P384),
+        _ => return Err(anyhow!("unsupported enclave public key encoding")),
+    };
+    Ok(PubKey { algo, val: data })
+}
+
+/// Order of the certs in the document's `cabundle`, see [`AttestationReport::bundle_order`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleOrder {
+    /// Root first, down to the leaf's issuer. This is the order AWS documents.
+    RootFirst,
+    /// The leaf's issuer first, up to the root.
+    LeafFirst,
+}
+
+/// How `user_data` commits to the report's `public_key`, see
+/// [`AttestationReport::verify_public_key_binding_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PublicKeyBinding {
+    /// The first 32 bytes of `user_data` are `sha256(public_key)`; any trailing bytes are
+    /// application data.
+    #[default]
+    Sha256Prefix,
+    /// `user_data` is a JSON object whose named field holds `sha256(public_key)` as hex,
+    /// with or without a `0x` prefix.
+    JsonSha256Field(String),
+}
+
+/// sha256 of the AWS Nitro Enclaves root certificate (`samples/aws_root.der`), for pinning
+/// the chain with [`AuthenticateOptions::expected_root`].
+pub const AWS_NITRO_ROOT_DIGEST: B256 =
+    b256!("641a0321a3e244efe456463195d606317ed7cdcc3c1756e09893f3c68f79bb5b");
+
+/// Optional checks for [`AttestationReport::authenticate_with`]. All are off by default.
+#[derive(Debug, Clone, Default)]
+pub struct AuthenticateOptions {
+    /// Require the chain's root to have this digest.
+    pub expected_root: Option<B256>,
+    /// Require the leaf to look like an AWS NSM signing cert, see
+    /// `Cert::is_nsm_signing_cert`. This relies on AWS naming conventions, so only enable
+    /// it in deployments that can react to AWS changing them.
+    pub require_nsm_leaf: bool,
+    /// When set, reject the report if any cert or the COSE signature uses an algorithm
+    /// outside this list.
+    pub algorithm_allowlist: Option<Vec<SigAlgo>>,
+    /// Skip checking that every cert in the chain is valid at `timestamp`, e.g. to verify
+    /// a report whose certs have since expired. Signatures are still checked.
+    pub skip_cert_validity: bool,
+}
+
+/// Default for every [`ParseLimits`] field, 1 MiB.
+const DEFAULT_PARSE_LIMIT: usize = 1 << 20;
+
+/// Size limits for [`AttestationReport::parse_with_limits`], in bytes. Each defaults to
+/// 1 MiB, far above what the NSM produces.
+#[derive(Debug, Clone)]
+pub struct ParseLimits {
+    /// Maximum size of the encoded report. It bounds every allocation made while decoding,
+    /// so it is checked before parsing.
+    pub max_report_size: usize,
+    pub max_public_key: usize,
+    pub max_user_data: usize,
+    pub max_nonce: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_report_size: DEFAULT_PARSE_LIMIT,
+            max_public_key: DEFAULT_PARSE_LIMIT,
+            max_user_data: DEFAULT_PARSE_LIMIT,
+            max_nonce: DEFAULT_PARSE_LIMIT,
+        }
+    }
+}
+
+fn check_size(field: &str, len: usize, max: usize) -> anyhow::Result<()> {
+    if len > max {
+        return Err(anyhow!(
+            "{} is {} bytes, more than the {} byte limit",
+            field,
+            len,
+            max
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AttestationDocument {
     pub module_id: String,
     pub timestamp: u64,
@@ -78,3 +534,481 @@ pub struct AttestationDocument {
     pub user_data: Option<ByteBuf>,
     pub nonce: Option<ByteBuf>,
 }
+
+impl AttestationDocument {
+    fn check_limits(&self, limits: &ParseLimits) -> anyhow::Result<()> {
+        let len = |field: &Option<ByteBuf>| field.as_ref().map_or(0, |data| data.len());
+        check_size("public_key", len(&self.public_key), limits.max_public_key)?;
+        check_size("user_data", len(&self.user_data), limits.max_user_data)?;
+        check_size("nonce", len(&self.nonce), limits.max_nonce)
+    }
+
+    /// Checks that the document declares SHA384 as its digest algorithm, which the PCR
+    /// sizes and the ES384 signature check rely on.
+    pub fn validate_digest_algorithm(&self) -> anyhow::Result<()> {
+        if self.digest != "SHA384" {
+            return Err(anyhow!(
+                "unsupported document digest: expected SHA384, got {}",
+                self.digest
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that `certificate` holds the leaf cert. A document whose producer left it
+    /// empty, e.g. with the leaf moved into the cabundle, would otherwise build a chain
+    /// with the wrong leaf.
+    pub fn validate_certificate(&self) -> anyhow::Result<()> {
+        if self.certificate.is_empty() {
+            return Err(anyhow!("attestation certificate is empty"));
+        }
+        Cert::parse_der(&self.certificate)
+            .map_err(|err| anyhow!("attestation certificate is not a valid cert: {}", err))?;
+        Ok(())
+    }
+
+    /// Returns the value of PCR `index`, if the document reports it.
+    pub fn pcr(&self, index: u64) -> Option<&[u8; 48]> {
+        self.pcrs.get(&index).map(|value| &**value)
+    }
+
+    /// Returns the value of PCR `index` as 0x-prefixed hex.
+    pub fn pcr_hex(&self, index: u64) -> Option<String> {
+        self.pcr(index).map(hex::encode_prefixed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use alloy_primitives::{b256, hex};
+    use x509_verifier_rust_crypto::{KeyAlgo, KeyAlgoParams, SigAlgo};
+
+    use super::{
+        AttestationDocument, AttestationReport, AuthenticateOptions, BundleOrder, ParseLimits,
+        PublicKeyBinding, AWS_NITRO_ROOT_DIGEST, B256,
+    };
+    use crate::AttestationPolicy;
+
+    const MESSAGE: &[u8] = b"hello from the enclave";
+    const SIG_DER: &str = "3066023100a464c22b34f8718336e56fdc2562cab3d98485207adc4ed8bff578e15a96a788cb5a14045df68d3f1854b829fc870270023100e4415ecafca19e08811c9b27eef470ffabeefcf55e1d354da5450ce93748998433b421b4a5591c2fb6f2442e12ffa4ea";
+    const SIG_RAW: &str = "a464c22b34f8718336e56fdc2562cab3d98485207adc4ed8bff578e15a96a788cb5a14045df68d3f1854b829fc870270e4415ecafca19e08811c9b27eef470ffabeefcf55e1d354da5450ce93748998433b421b4a5591c2fb6f2442e12ffa4ea";
+
+    fn read_report(path: &str) -> AttestationReport {
+        AttestationReport::parse(&std::fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_verify_enclave_signature() {
+        // public_key replaced with the SPKI of a known P-384 key
+        let report = read_report("./samples/enclave_key.report");
+        for sig in [SIG_DER, SIG_RAW] {
+            let sig = hex::decode(sig).unwrap();
+            assert!(report
+                .verify_enclave_signature(MESSAGE, &sig, SigAlgo::EcdsaSHA384)
+                .unwrap());
+            assert!(!report
+                .verify_enclave_signature(b"tampered", &sig, SigAlgo::EcdsaSHA384)
+                .unwrap());
+        }
+    }
+
+    #[test]
+    fn test_document_serialize_round_trip() {
+        let report = read_report("../../samples/attestation_2.report");
+        let doc = report.doc();
+
+        let cbor = serde_cbor::to_vec(doc).unwrap();
+        assert_eq!(
+            &serde_cbor::from_slice::<AttestationDocument>(&cbor).unwrap(),
+            doc
+        );
+        let json = serde_json::to_vec(doc).unwrap();
+        assert_eq!(
+            &serde_json::from_slice::<AttestationDocument>(&json).unwrap(),
+            doc
+        );
+
+        assert_eq!(doc.pcr(0).unwrap(), &*doc.pcrs[&0]);
+        assert_eq!(
+            doc.pcr_hex(0).unwrap(),
+            "0xca78fbe0b97bbfe1895dd713639dffcbdd21da5c7e05b8d90fe57a4e122414edc0f677d673df31fee1c16a7b34c16f36"
+        );
+        assert_eq!(doc.pcr(99), None);
+    }
+
+    #[test]
+    fn test_validate_digest_algorithm() {
+        // attestation_1 with the digest field changed to SHA256
+        let data = std::fs::read("./samples/sha256_digest.report").unwrap();
+        let err = AttestationReport::parse(&data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported document digest: expected SHA384, got SHA256"
+        );
+    }
+
+    #[test]
+    fn test_validate_certificate() {
+        // attestation_1 with the certificate field emptied
+        let data = std::fs::read("./samples/empty_certificate.report").unwrap();
+        let err = AttestationReport::parse(&data).unwrap_err();
+        assert_eq!(err.to_string(), "attestation certificate is empty");
+
+        let mut report = read_report("../../samples/attestation_1.report");
+        report.doc.validate_certificate().unwrap();
+        report.doc.certificate = serde_bytes::ByteBuf::from(&report.doc.cabundle[0][..16]);
+        let err = report.doc.validate_certificate().unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("attestation certificate is not a valid cert"));
+    }
+
+    #[test]
+    fn test_verify_nonce() {
+        // attestation_1 was requested with nonce "1234"
+        let report = read_report("../../samples/attestation_1.report");
+        report.verify_nonce(b"1234").unwrap();
+        assert_eq!(
+            report.verify_nonce(b"4321").unwrap_err().to_string(),
+            "nonce mismatch"
+        );
+        assert!(report.verify_nonce(b"12345").is_err());
+
+        // attestation_1 with the nonce removed
+        let report = read_report("./samples/no_nonce.report");
+        assert_eq!(
+            report.verify_nonce(b"1234").unwrap_err().to_string(),
+            "attestation document has no nonce"
+        );
+    }
+
+    #[test]
+    fn test_check_pcrs() {
+        let report = read_report("../../samples/attestation_2.report");
+        let pcr0 = *report.doc().pcr(0).unwrap();
+        let pcr1 = *report.doc().pcr(1).unwrap();
+
+        report.check_pcrs(&BTreeMap::new()).unwrap();
+        report
+            .check_pcrs(&BTreeMap::from([(0, pcr0), (1, pcr1)]))
+            .unwrap();
+
+        let err = report
+            .check_pcrs(&BTreeMap::from([(0, pcr0), (1, pcr0)]))
+            .unwrap_err();
+        assert!(err.to_string().starts_with("PCR1 mismatch"));
+
+        let err = report
+            .check_pcrs(&BTreeMap::from([(99, pcr0)]))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "PCR99 is missing from the document");
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        let data = std::fs::read("../../samples/attestation_1.report").unwrap();
+        let report = AttestationReport::parse_hex(&format!("0x{}", hex::encode(&data))).unwrap();
+        assert!(report.semantically_eq(&AttestationReport::parse(&data).unwrap()));
+        assert!(AttestationReport::parse_hex(&hex::encode(&data)).is_ok());
+
+        let err = AttestationReport::parse_hex("0xzz").unwrap_err();
+        assert!(err.to_string().starts_with("invalid hex report"));
+        let err = AttestationReport::parse_hex("0xdeadbeef").unwrap_err();
+        assert!(!err.to_string().starts_with("invalid hex report"));
+    }
+
+    #[test]
+    fn test_enclave_public_key() {
+        // SPKI of a known P-384 key
+        let report = read_report("./samples/enclave_key.report");
+        let pubkey = report.enclave_public_key().unwrap().unwrap();
+        assert_eq!(pubkey.algo, KeyAlgo::ECDSA(KeyAlgoParams::P384));
+        assert_eq!(pubkey.val.len(), 97);
+        assert_eq!(pubkey.val[0], 0x04);
+
+        // no public_key
+        let report = read_report("../../samples/attestation_2.report");
+        assert!(report.enclave_public_key().unwrap().is_none());
+
+        // public_key is free-form text rather than a key encoding
+        let report = read_report("../../samples/attestation_1.report");
+        assert!(report.enclave_public_key().is_err());
+    }
+
+    #[test]
+    fn test_verify_public_key_binding() {
+        // enclave_key.report with user_data = sha256(public_key) || "app-data"
+        let report = read_report("./samples/pubkey_binding.report");
+        report.verify_public_key_binding().unwrap();
+        assert!(report
+            .verify_public_key_binding_with(&PublicKeyBinding::JsonSha256Field(
+                "pubkey_sha256".into()
+            ))
+            .is_err());
+
+        // enclave_key.report with user_data = {"app": ..., "pubkey_sha256": "0x..."}
+        let report = read_report("./samples/pubkey_binding_json.report");
+        let json = PublicKeyBinding::JsonSha256Field("pubkey_sha256".into());
+        report.verify_public_key_binding_with(&json).unwrap();
+        assert!(report
+            .verify_public_key_binding_with(&PublicKeyBinding::JsonSha256Field("app".into()))
+            .is_err());
+        assert!(report.verify_public_key_binding().is_err());
+
+        // user_data is unrelated to the public key
+        let report = read_report("../../samples/attestation_1.report");
+        let err = report.verify_public_key_binding_with(&json).unwrap_err();
+        assert!(err.to_string().contains("not JSON"));
+        let report = read_report("./samples/enclave_key.report");
+        assert!(report.verify_public_key_binding().is_err());
+    }
+
+    #[test]
+    fn test_evaluate_policies() {
+        let report = read_report("../../samples/attestation_1.report");
+        let timestamp = report.doc().timestamp / 1000;
+
+        let matching = AttestationPolicy {
+            user_data: Some(b"Automata MPC Demo".to_vec()),
+            nonce: Some(b"1234".to_vec()),
+            pcrs: [(3, *report.doc().pcr(3).unwrap())].into(),
+            ..Default::default()
+        };
+        let wrong_nonce = AttestationPolicy {
+            nonce: Some(b"4321".to_vec()),
+            ..Default::default()
+        };
+
+        let policies = [("tenant-a", &matching), ("tenant-b", &wrong_nonce)];
+        let results = report.evaluate_policies(&policies, timestamp, AWS_NITRO_ROOT_DIGEST);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "tenant-a");
+        assert!(results[0].1.is_ok());
+        assert_eq!(results[1].0, "tenant-b");
+        let err = results[1].1.as_ref().unwrap_err();
+        assert!(err.to_string().contains("nonce mismatch"));
+
+        // a chain rooted elsewhere fails every policy, including the matching one
+        let results = report.evaluate_policies(&policies, timestamp, B256::ZERO);
+        for (_, result) in &results {
+            let err = result.as_ref().unwrap_err();
+            assert!(err.to_string().starts_with("authentication failed"));
+        }
+    }
+
+    #[test]
+    fn test_explain_verification() {
+        let report = read_report("../../samples/attestation_1.report");
+        let timestamp = report.doc().timestamp / 1000;
+        let policy = AttestationPolicy {
+            pcrs: [(3, *report.doc().pcr(3).unwrap())].into(),
+            ..Default::default()
+        };
+        let pinned = AuthenticateOptions {
+            expected_root: Some(AWS_NITRO_ROOT_DIGEST),
+            ..Default::default()
+        };
+
+        let narrative = report.explain_verification(Some(&policy), timestamp, &pinned);
+        assert!(narrative.starts_with("Parsed COSE_Sign1 (ES384)."));
+        assert!(narrative.contains("Built 5-cert chain rooted at "));
+        assert!(narrative.contains("Root matched pinned digest "));
+        assert!(narrative.contains("Verified 4 signatures."));
+        assert!(narrative.contains("Document matched policy."));
+        assert!(narrative.ends_with("Verification succeeded."));
+
+        // long after the leaf cert expired
+        let narrative = report.explain_verification(None, 4102444800, &pinned);
+        assert!(narrative.contains("Built 5-cert chain"));
+        assert!(!narrative.contains("COSE signature valid."));
+        assert!(narrative.contains("Verification failed: report did not authenticate at "));
+
+        // a chain that does not end in the pinned root
+        let other_root = AuthenticateOptions {
+            expected_root: Some(B256::ZERO),
+            ..Default::default()
+        };
+        let narrative = report.explain_verification(None, timestamp, &other_root);
+        assert!(!narrative.contains("Verified 4 signatures."));
+        assert!(narrative.contains("Verification failed: "));
+    }
+
+    #[test]
+    fn test_semantically_eq() {
+        let untagged = std::fs::read("../../samples/attestation_1.report").unwrap();
+        // the same COSE_Sign1 wrapped in CBOR tag 18
+        let tagged = [&[0xd2u8][..], &untagged].concat();
+        assert_ne!(untagged, tagged);
+
+        let a = AttestationReport::parse(&untagged).unwrap();
+        let b = AttestationReport::parse(&tagged).unwrap();
+        assert!(a.semantically_eq(&b));
+
+        let other = read_report("../../samples/attestation_2.report");
+        assert!(!a.semantically_eq(&other));
+    }
+
+    #[test]
+    fn test_authenticate_with_root() {
+        // sha256 of samples/aws_root.der
+        let aws_root = b256!("641a0321a3e244efe456463195d606317ed7cdcc3c1756e09893f3c68f79bb5b");
+        assert_eq!(aws_root, AWS_NITRO_ROOT_DIGEST);
+        let report = read_report("../../samples/attestation_1.report");
+        let timestamp = report.doc().timestamp / 1000;
+
+        let cert_chain = report
+            .authenticate_with_root(1, timestamp, Some(aws_root))
+            .unwrap();
+        assert_eq!(cert_chain.root().digest(), aws_root);
+        assert!(report
+            .authenticate_with_root(1, timestamp, Some(B256::ZERO))
+            .is_err());
+    }
+
+    #[test]
+    fn test_reversed_cabundle() {
+        let report = read_report("../../samples/attestation_1.report");
+        assert_eq!(report.bundle_order().unwrap(), BundleOrder::RootFirst);
+        let expected = report.cert_chain().unwrap();
+
+        // attestation_1 with the cabundle reversed, so the COSE signature no longer matches
+        let reversed = read_report("./samples/reversed_cabundle.report");
+        assert_eq!(reversed.bundle_order().unwrap(), BundleOrder::LeafFirst);
+        let cert_chain = reversed.cert_chain_ordered(BundleOrder::LeafFirst).unwrap();
+        assert_eq!(cert_chain.digest(), expected.digest());
+        assert!(cert_chain.verify_chain(1).unwrap());
+
+        // cert_chain takes the bundle as root first, which does not verify here
+        let misordered = reversed.cert_chain().unwrap();
+        assert!(!matches!(misordered.verify_chain(1), Ok(true)));
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let data = std::fs::read("../../samples/attestation_1.report").unwrap();
+        assert!(AttestationReport::parse_with_limits(&data, &ParseLimits::default()).is_ok());
+
+        // attestation_1 carries 17 bytes of user_data
+        let limits = ParseLimits {
+            max_user_data: 16,
+            ..Default::default()
+        };
+        let err = AttestationReport::parse_with_limits(&data, &limits).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "user_data is 17 bytes, more than the 16 byte limit"
+        );
+
+        let limits = ParseLimits {
+            max_report_size: 1024,
+            ..Default::default()
+        };
+        let err = AttestationReport::parse_with_limits(&data, &limits).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "report is {} bytes, more than the 1024 byte limit",
+                data.len()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_never_panics() {
+        // xorshift64, so failures are reproducible
+        let mut state = 0x9e3779b97f4a7c15u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for len in [0, 1, 2, 16, 64, 512, 4096] {
+            let data: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            assert!(AttestationReport::parse(&data).is_err());
+        }
+
+        let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+        for _ in 0..2000 {
+            let mut data = report.clone();
+            match next() % 3 {
+                0 => data.truncate(next() as usize % report.len()),
+                1 => {
+                    let idx = next() as usize % data.len();
+                    data[idx] ^= 1 << (next() % 8);
+                }
+                _ => {
+                    let idx = next() as usize % data.len();
+                    data[idx] = next() as u8;
+                }
+            }
+            // any result will do as long as it is returned
+            if let Ok(parsed) = AttestationReport::parse(&data) {
+                if let Ok(cert_chain) = parsed.cert_chain() {
+                    let _ = cert_chain.verify_chain(1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_cabundle() {
+        let mut report = read_report("../../samples/attestation_1.report");
+        report.doc.cabundle.clear();
+        let timestamp = report.doc().timestamp / 1000;
+
+        let err = report.cert_chain().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "attestation cabundle is empty; cannot build chain"
+        );
+        assert!(report.authenticate(1, timestamp).is_err());
+    }
+
+    #[test]
+    fn test_nsm_signing_cert() {
+        let report = read_report("../../samples/attestation_2.report");
+        let timestamp = report.doc().timestamp / 1000;
+        let cert_chain = report.cert_chain().unwrap();
+        assert!(cert_chain.leaf().is_nsm_signing_cert());
+        // the per-instance intermediate is a CA, not the signing cert
+        assert!(!cert_chain.certs[3].is_nsm_signing_cert());
+        assert!(!cert_chain.root().is_nsm_signing_cert());
+
+        let strict = AuthenticateOptions {
+            require_nsm_leaf: true,
+            ..Default::default()
+        };
+        assert!(report.authenticate_with(1, timestamp, &strict).is_ok());
+    }
+
+    #[test]
+    fn test_protected_algorithm_mismatch() {
+        // attestation_1 with the protected header rewritten to declare ES256
+        let report = read_report("./samples/es256_header.report");
+        let timestamp = report.doc().timestamp / 1000;
+        let err = report.authenticate(1, timestamp).unwrap_err();
+        assert_eq!(err.to_string(), "expected ES384, got -7");
+    }
+
+    #[test]
+    fn test_algorithm_allowlist() {
+        let report = read_report("../../samples/attestation_1.report");
+        let timestamp = report.doc().timestamp / 1000;
+
+        let es384_only = AuthenticateOptions {
+            algorithm_allowlist: Some(vec![SigAlgo::EcdsaSHA384]),
+            ..Default::default()
+        };
+        assert!(report.authenticate_with(1, timestamp, &es384_only).is_ok());
+
+        let es256_only = AuthenticateOptions {
+            algorithm_allowlist: Some(vec![SigAlgo::EcdsaSHA256]),
+            ..Default::default()
+        };
+        assert!(report.authenticate_with(1, timestamp, &es256_only).is_err());
+    }
+}
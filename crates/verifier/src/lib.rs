@@ -4,7 +4,15 @@ pub use doc::*;
 mod cose;
 pub use cose::*;
 
+mod policy;
+pub use policy::*;
+
 mod verifier;
 pub use verifier::*;
 
 pub mod stub;
+
+#[cfg(feature = "test-utils")]
+mod builder;
+#[cfg(feature = "test-utils")]
+pub use builder::*;
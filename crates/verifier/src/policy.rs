@@ -0,0 +1,148 @@
+use std::{collections::BTreeMap, path::Path};
+
+use alloy_primitives::hex;
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+
+use crate::AttestationDocument;
+
+/// Expected values for the fields of an already-authenticated attestation document.
+///
+/// Every field is optional; `None` (or an empty `pcrs` map) means the field is not checked.
+#[derive(Debug, Clone, Default)]
+pub struct AttestationPolicy {
+    /// Expected PCR values keyed by PCR index.
+    pub pcrs: BTreeMap<u64, [u8; 48]>,
+    pub module_id: Option<String>,
+    pub user_data: Option<Vec<u8>>,
+    pub nonce: Option<Vec<u8>>,
+    pub public_key: Option<Vec<u8>>,
+}
+
+impl AttestationPolicy {
+    /// Checks the document against the policy, reporting the first mismatching field.
+    pub fn evaluate(&self, doc: &AttestationDocument) -> anyhow::Result<()> {
+        check_pcrs(doc, &self.pcrs)?;
+        if let Some(module_id) = &self.module_id {
+            if &doc.module_id != module_id {
+                return Err(anyhow!(
+                    "module_id mismatch: expected {}, got {}",
+                    module_id,
+                    doc.module_id
+                ));
+            }
+        }
+        check_option_field("user_data", &self.user_data, &doc.user_data)?;
+        check_option_field("nonce", &self.nonce, &doc.nonce)?;
+        check_option_field("public_key", &self.public_key, &doc.public_key)?;
+        Ok(())
+    }
+}
+
+fn check_option_field(
+    name: &str,
+    expected: &Option<Vec<u8>>,
+    actual: &Option<serde_bytes::ByteBuf>,
+) -> anyhow::Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    match actual {
+        Some(actual) if actual.as_slice() == expected.as_slice() => Ok(()),
+        Some(_) => Err(anyhow!("{} mismatch", name)),
+        None => Err(anyhow!("{} is missing from the document", name)),
+    }
+}
+
+/// Expected PCR measurements of an enclave image.
+#[derive(Debug, Clone, Default)]
+pub struct PcrPolicy {
+    pub pcrs: BTreeMap<u64, [u8; 48]>,
+}
+
+/// The measurements printed by `nitro-cli build-enclave` / `nitro-cli describe-eif`.
+#[derive(Deserialize)]
+struct EifManifest {
+    #[serde(rename = "Measurements")]
+    measurements: BTreeMap<String, String>,
+}
+
+impl PcrPolicy {
+    /// PCRs measured from the image: 0 (image), 1 (kernel + bootstrap), 2 (application)
+    /// and 8 (signing cert, only present for signed images).
+    const EIF_PCRS: [u64; 4] = [0, 1, 2, 8];
+
+    /// Builds a policy from the measurements JSON of a locally built enclave image, as
+    /// printed by `nitro-cli build-enclave` or `nitro-cli describe-eif`.
+    pub fn from_eif(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        let manifest: EifManifest = serde_json::from_slice(&data)
+            .with_context(|| format!("parse EIF measurements {}", path.display()))?;
+
+        let mut pcrs = BTreeMap::new();
+        for index in Self::EIF_PCRS {
+            let Some(value) = manifest.measurements.get(&format!("PCR{}", index)) else {
+                continue;
+            };
+            let value: [u8; 48] = hex::decode(value)
+                .ok()
+                .and_then(|n| n.try_into().ok())
+                .ok_or_else(|| anyhow!("PCR{} is not a 48 byte hex string", index))?;
+            pcrs.insert(index, value);
+        }
+        if !pcrs.contains_key(&0) {
+            return Err(anyhow!("EIF measurements do not contain PCR0"));
+        }
+        Ok(Self { pcrs })
+    }
+
+    /// Checks that every PCR of the policy matches the document.
+    pub fn check_pcrs(&self, doc: &AttestationDocument) -> anyhow::Result<()> {
+        check_pcrs(doc, &self.pcrs)
+    }
+}
+
+/// Checks that every PCR in `expected` is present in `doc` with the same value. PCRs not
+/// in `expected` are ignored.
+pub(crate) fn check_pcrs(
+    doc: &AttestationDocument,
+    expected: &BTreeMap<u64, [u8; 48]>,
+) -> anyhow::Result<()> {
+    for (index, expected) in expected {
+        let actual = doc
+            .pcrs
+            .get(index)
+            .ok_or_else(|| anyhow!("PCR{} is missing from the document", index))?;
+        if actual.as_slice() != expected {
+            return Err(anyhow!(
+                "PCR{} mismatch: expected {}, got {}",
+                index,
+                hex::encode(expected),
+                hex::encode(actual.as_slice())
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PcrPolicy;
+    use crate::AttestationReport;
+
+    #[test]
+    fn test_pcr_policy_from_eif() {
+        // measurements of the enclave that produced attestation_2
+        let policy = PcrPolicy::from_eif("./samples/eif_measurements.json").unwrap();
+        assert_eq!(policy.pcrs.keys().copied().collect::<Vec<_>>(), [0, 1, 2]);
+
+        let data = std::fs::read("../../samples/attestation_2.report").unwrap();
+        let report = AttestationReport::parse(&data).unwrap();
+        policy.check_pcrs(report.doc()).unwrap();
+
+        let data = std::fs::read("../../samples/attestation_1.report").unwrap();
+        let report = AttestationReport::parse(&data).unwrap();
+        assert!(policy.check_pcrs(report.doc()).is_err());
+    }
+}
@@ -1,20 +1,62 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use alloy_primitives::Bytes;
 use serde_bytes::ByteBuf;
 
 use crate::{
     stub::{Pcr, VerificationResult, VerifierInput, VerifierJournal},
-    AttestationReport,
+    AttestationReport, AuthenticateOptions,
 };
 
+/// Copies an optional document field into `Bytes` with a single allocation; `None` maps to
+/// empty bytes.
 fn get_option_bytes(val: &Option<ByteBuf>) -> Bytes {
-    val.as_ref().map(|n| n.to_vec()).unwrap_or_default().into()
+    val.as_ref()
+        .map_or_else(Bytes::new, |n| Bytes::copy_from_slice(n))
 }
 
+/// Verifies the report in `input` as the guest programs do. Certs are checked against the
+/// document's own timestamp unless `skipCertValidity` is set; the journal's `notBefore` and
+/// `notAfter` still carry the leaf's validity either way, and the journal records the flag
+/// so the verifier contract can reject such proofs.
+///
+/// With a nonzero `verifyAtTimestamp`, certs are checked at that time instead. The journal
+/// records it, and the verifier contract holds it to the same `maxTimeDiff` window as the
+/// report timestamp.
 pub fn verify_attestation_report(input: &VerifierInput) -> anyhow::Result<VerifierJournal> {
     let report = AttestationReport::parse(&input.attestationReport)?;
+    verify_parsed_report(
+        &report,
+        input.trustedCertsPrefixLen,
+        input.verifyAtTimestamp,
+        input.skipCertValidity,
+    )
+}
 
+/// Authenticates `report` with certs checked at `verify_at` (seconds), or at the report's
+/// own timestamp if it is zero, and builds its journal.
+fn verify_parsed_report(
+    report: &AttestationReport,
+    trusted_certs_prefix_len: u8,
+    verify_at: u64,
+    skip_cert_validity: bool,
+) -> anyhow::Result<VerifierJournal> {
     let doc = report.doc();
-    let cert_chain = report.authenticate(input.trustedCertsPrefixLen as usize, doc.timestamp / 1000)?;
+    let timestamp = match verify_at {
+        0 => doc.timestamp / 1000,
+        verify_at => verify_at,
+    };
+    let opts = AuthenticateOptions {
+        skip_cert_validity,
+        ..Default::default()
+    };
+    let cert_chain =
+        report.authenticate_with(trusted_certs_prefix_len as usize, timestamp, &opts)?;
+    let (not_before, not_after) = cert_chain.leaf().validity();
 
     let user_data = get_option_bytes(&doc.user_data);
     let nonce = get_option_bytes(&doc.nonce);
@@ -32,14 +74,353 @@ pub fn verify_attestation_report(input: &VerifierInput) -> anyhow::Result<Verifi
     let output = VerifierJournal {
         result: VerificationResult::Success,
         certs: cert_chain.digest().to_vec(),
-        trustedCertsPrefixLen: input.trustedCertsPrefixLen,
-        userData: user_data.into(),
-        nonce: nonce.into(),
-        publicKey: public_key.into(),
+        trustedCertsPrefixLen: trusted_certs_prefix_len,
+        userData: user_data,
+        nonce,
+        publicKey: public_key,
         pcrs,
         moduleId: doc.module_id.clone(),
         timestamp: doc.timestamp,
+        notBefore: not_before.timestamp() as u64,
+        notAfter: not_after.timestamp() as u64,
+        skipCertValidity: skip_cert_validity,
+        verifyAtTimestamp: verify_at,
     };
 
     Ok(output)
 }
+
+/// Errors from [`Verifier::verify`] that callers may want to handle specifically; they can
+/// be recovered from the returned `anyhow::Error` with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifierError {
+    /// The report is older than the configured `max_age_secs`.
+    TooOld {
+        /// Report timestamp in seconds.
+        timestamp: u64,
+        now: u64,
+        max_age_secs: u64,
+    },
+    /// The report is dated further ahead of the clock than `max_future_skew_secs`.
+    FromFuture {
+        /// Report timestamp in seconds.
+        timestamp: u64,
+        now: u64,
+        max_future_skew_secs: u64,
+    },
+}
+
+impl fmt::Display for VerifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooOld {
+                timestamp,
+                now,
+                max_age_secs,
+            } => write!(
+                f,
+                "attestation report is too old: generated at {}, now {}, max age {}s",
+                timestamp, now, max_age_secs
+            ),
+            Self::FromFuture {
+                timestamp,
+                now,
+                max_future_skew_secs,
+            } => write!(
+                f,
+                "attestation report is dated in the future: generated at {}, now {}, max clock skew {}s",
+                timestamp, now, max_future_skew_secs
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifierError {}
+
+/// Default for [`Verifier::max_future_skew_secs`].
+pub const DEFAULT_MAX_FUTURE_SKEW_SECS: u64 = 300;
+
+/// Offline verification context, for checking reports without a verifier contract.
+///
+/// Wraps [`verify_attestation_report`] with the checks the contract would otherwise apply,
+/// such as report freshness.
+pub struct Verifier {
+    pub trusted_certs_prefix_len: u8,
+    /// Reject reports whose timestamp is more than this many seconds before the clock.
+    pub max_age_secs: Option<u64>,
+    /// Reject reports whose timestamp is more than this many seconds after the clock.
+    pub max_future_skew_secs: u64,
+    /// Known-good PCR values the report must match, see [`AttestationReport::check_pcrs`].
+    pub expected_pcrs: Option<BTreeMap<u64, [u8; 48]>>,
+    /// Skip cert validity checks, as `skipCertValidity` does for the verifier program. The
+    /// journal records the flag.
+    pub skip_cert_validity: bool,
+    clock: Box<dyn Fn() -> u64 + Send + Sync>,
+}
+
+impl Verifier {
+    /// Creates a verifier trusting the first `trusted_certs_prefix_len` certs of each chain,
+    /// with no age limit, [`DEFAULT_MAX_FUTURE_SKEW_SECS`] of clock skew and the system clock.
+    pub fn new(trusted_certs_prefix_len: u8) -> Self {
+        Self {
+            trusted_certs_prefix_len,
+            max_age_secs: None,
+            max_future_skew_secs: DEFAULT_MAX_FUTURE_SKEW_SECS,
+            expected_pcrs: None,
+            skip_cert_validity: false,
+            clock: Box::new(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default()
+            }),
+        }
+    }
+
+    pub fn with_max_age(mut self, max_age_secs: u64) -> Self {
+        self.max_age_secs = Some(max_age_secs);
+        self
+    }
+
+    pub fn with_max_future_skew(mut self, max_future_skew_secs: u64) -> Self {
+        self.max_future_skew_secs = max_future_skew_secs;
+        self
+    }
+
+    pub fn with_expected_pcrs(mut self, expected_pcrs: BTreeMap<u64, [u8; 48]>) -> Self {
+        self.expected_pcrs = Some(expected_pcrs);
+        self
+    }
+
+    pub fn with_skip_cert_validity(mut self, skip_cert_validity: bool) -> Self {
+        self.skip_cert_validity = skip_cert_validity;
+        self
+    }
+
+    /// Replaces the clock used for freshness checks; it returns the current unix time in
+    /// seconds.
+    pub fn with_clock(mut self, clock: impl Fn() -> u64 + Send + Sync + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Verifies `report` and returns its journal. On top of [`verify_attestation_report`],
+    /// fails with [`VerifierError::TooOld`] when the report exceeds `max_age_secs`, with
+    /// [`VerifierError::FromFuture`] when it is dated more than `max_future_skew_secs` after
+    /// the clock, and checks `expected_pcrs` when set.
+    pub fn verify(&self, report: &[u8]) -> anyhow::Result<VerifierJournal> {
+        let report = AttestationReport::parse(report)?;
+        let journal = verify_parsed_report(
+            &report,
+            self.trusted_certs_prefix_len,
+            0,
+            self.skip_cert_validity,
+        )?;
+
+        let timestamp = journal.timestamp / 1000;
+        let now = (self.clock)();
+        if timestamp > now.saturating_add(self.max_future_skew_secs) {
+            return Err(VerifierError::FromFuture {
+                timestamp,
+                now,
+                max_future_skew_secs: self.max_future_skew_secs,
+            }
+            .into());
+        }
+        if let Some(max_age_secs) = self.max_age_secs {
+            if now.saturating_sub(timestamp) > max_age_secs {
+                return Err(VerifierError::TooOld {
+                    timestamp,
+                    now,
+                    max_age_secs,
+                }
+                .into());
+            }
+        }
+        if let Some(expected_pcrs) = &self.expected_pcrs {
+            report.check_pcrs(expected_pcrs)?;
+        }
+        Ok(journal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_verifier_rust_crypto::CertError;
+
+    use super::*;
+
+    #[test]
+    fn test_get_option_bytes() {
+        // large payloads are benchmarked in benches/cose.rs
+        let user_data = Some(ByteBuf::from(vec![0xab; 64 * 1024]));
+        let bytes = get_option_bytes(&user_data);
+        assert_eq!(bytes.as_ref(), user_data.unwrap().as_slice());
+
+        assert!(get_option_bytes(&None).is_empty());
+    }
+
+    #[test]
+    fn test_verifier_max_age() {
+        let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+        // attestation_1 was generated at 1723799509167 ms
+        let timestamp = 1723799509;
+        let max_age = 3600;
+
+        let verifier = Verifier::new(1)
+            .with_max_age(max_age)
+            .with_clock(move || timestamp + max_age);
+        let journal = verifier.verify(&report).unwrap();
+        assert_eq!(journal.timestamp / 1000, timestamp);
+
+        let verifier = Verifier::new(1)
+            .with_max_age(max_age)
+            .with_clock(move || timestamp + max_age + 1);
+        let err = verifier.verify(&report).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<VerifierError>(),
+            Some(&VerifierError::TooOld {
+                timestamp,
+                now: timestamp + max_age + 1,
+                max_age_secs: max_age,
+            })
+        );
+
+        // no age limit
+        let verifier = Verifier::new(1).with_clock(move || timestamp + max_age + 1);
+        assert!(verifier.verify(&report).is_ok());
+    }
+
+    #[test]
+    fn test_verifier_future_report() {
+        let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+        // attestation_1 was generated at 1723799509167 ms
+        let timestamp = 1723799509;
+        let skew = DEFAULT_MAX_FUTURE_SKEW_SECS;
+
+        let verifier = Verifier::new(1)
+            .with_max_age(3600)
+            .with_clock(move || timestamp - skew);
+        assert!(verifier.verify(&report).is_ok());
+
+        for verifier in [Verifier::new(1), Verifier::new(1).with_max_age(3600)] {
+            let err = verifier
+                .with_clock(move || timestamp - skew - 1)
+                .verify(&report)
+                .unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<VerifierError>(),
+                Some(&VerifierError::FromFuture {
+                    timestamp,
+                    now: timestamp - skew - 1,
+                    max_future_skew_secs: skew,
+                })
+            );
+        }
+
+        let verifier = Verifier::new(1)
+            .with_max_future_skew(0)
+            .with_clock(move || timestamp - 1);
+        assert!(verifier.verify(&report).is_err());
+    }
+
+    #[test]
+    fn test_verifier_expected_pcrs() {
+        let report = std::fs::read("../../samples/attestation_2.report").unwrap();
+        let doc = AttestationReport::parse(&report).unwrap().doc().clone();
+        let pcr0 = *doc.pcr(0).unwrap();
+
+        let verifier = Verifier::new(1).with_expected_pcrs(BTreeMap::from([(0, pcr0)]));
+        assert!(verifier.verify(&report).is_ok());
+
+        let verifier = Verifier::new(1).with_expected_pcrs(BTreeMap::from([(0, [0u8; 48])]));
+        assert!(verifier.verify(&report).is_err());
+    }
+
+    #[test]
+    fn test_journal_leaf_validity() {
+        let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+        let journal = verify_attestation_report(&VerifierInput {
+            trustedCertsPrefixLen: 1,
+            attestationReport: report.into(),
+            skipCertValidity: false,
+            verifyAtTimestamp: 0,
+        })
+        .unwrap();
+        // the leaf is issued for about three hours around the attestation
+        assert_eq!(journal.notBefore, 1723799506);
+        assert_eq!(journal.notAfter, 1723810309);
+        assert!(journal.notBefore <= journal.timestamp / 1000);
+        assert!(!journal.skipCertValidity);
+
+        let decoded = VerifierJournal::decode(&journal.encode()).unwrap();
+        assert_eq!(decoded.notBefore, journal.notBefore);
+        assert_eq!(decoded.notAfter, journal.notAfter);
+    }
+
+    #[test]
+    fn test_skip_cert_validity() {
+        // signed by samples/test_leaf.der, which expired in 2124, with a timestamp in 2125
+        let report = std::fs::read("./samples/expired_cert.report").unwrap();
+        let mut input = VerifierInput {
+            trustedCertsPrefixLen: 1,
+            attestationReport: report.into(),
+            skipCertValidity: false,
+            verifyAtTimestamp: 0,
+        };
+        let err = verify_attestation_report(&input).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CertError>(),
+            Some(CertError::Expired { .. })
+        ));
+
+        input.skipCertValidity = true;
+        let journal = verify_attestation_report(&input).unwrap();
+        assert!(journal.notAfter < journal.timestamp / 1000);
+        assert!(journal.skipCertValidity);
+
+        // still round-trips through the guest input encoding
+        let decoded = VerifierInput::decode(&input.encode()).unwrap();
+        assert!(decoded.skipCertValidity);
+
+        // the offline verifier commits the same journal
+        let timestamp = journal.timestamp / 1000;
+        let verifier = Verifier::new(1).with_clock(move || timestamp);
+        assert!(verifier.verify(&input.attestationReport).is_err());
+        let verifier = verifier.with_skip_cert_validity(true);
+        let offline = verifier.verify(&input.attestationReport).unwrap();
+        assert_eq!(offline.encode(), journal.encode());
+    }
+
+    #[test]
+    fn test_verify_at_timestamp() {
+        let report = std::fs::read("../../samples/attestation_1.report").unwrap();
+        // attestation_1 was generated at 1723799509, its leaf is valid until 1723810309
+        let timestamp = 1723799509;
+        let verify_at = |verify_at: u64| {
+            verify_attestation_report(&VerifierInput {
+                trustedCertsPrefixLen: 1,
+                attestationReport: report.clone().into(),
+                skipCertValidity: false,
+                verifyAtTimestamp: verify_at,
+            })
+        };
+
+        // recorded in the journal for the contract to check against the block time
+        let journal = verify_at(timestamp + 600).unwrap();
+        assert_eq!(journal.timestamp / 1000, timestamp);
+        assert_eq!(journal.verifyAtTimestamp, timestamp + 600);
+        let decoded = VerifierJournal::decode(&journal.encode()).unwrap();
+        assert_eq!(decoded.verifyAtTimestamp, timestamp + 600);
+
+        // certs are checked at verifyAtTimestamp, not at the report timestamp
+        let err = verify_at(1723810309 + 1).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CertError>(),
+            Some(CertError::Expired { .. })
+        ));
+
+        // zero keeps the report timestamp
+        assert_eq!(verify_at(0).unwrap().verifyAtTimestamp, 0);
+    }
+}
@@ -0,0 +1,65 @@
+//! Signature verification benchmarks over the sample chains.
+//!
+//! Run with `cargo bench -p x509-verifier-rust-crypto`, optionally with `--features sp1` or
+//! `--features risc0_unstable` to compare the precompile crates on the native target.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use x509_verifier_rust_crypto::{pem_to_der, CertChain};
+
+/// Sample chains as DER, leaf first.
+fn sample_chains() -> Vec<(&'static str, Vec<Vec<u8>>)> {
+    let json = |name: &str| -> Vec<Vec<u8>> {
+        let buf = std::fs::read(format!("./samples/{}.json", name)).unwrap();
+        let certs: Vec<String> = serde_json::from_slice(&buf).unwrap();
+        certs
+            .iter()
+            .map(|n| hex::decode(n.trim_start_matches("0x")).unwrap())
+            .collect()
+    };
+    let pem = |name: &str| -> Vec<Vec<u8>> {
+        let buf = std::fs::read(format!("./samples/{}.pem", name)).unwrap();
+        pem_to_der(&buf).unwrap()
+    };
+    let mut vcek = pem("vcek_milan_cert_chain");
+    vcek.insert(0, std::fs::read("./samples/vcek.der").unwrap());
+    vec![
+        ("apple_ios_der_ec", json("apple_ios_der_ec")),
+        ("gcp_tdx_tpm_cert", json("gcp_tdx_tpm_cert")),
+        ("android_attestation", pem("android-attestation")),
+        ("rsa_pss_sha256", pem("rsa_pss_sha256_chain")),
+        ("vcek_milan", vcek),
+    ]
+}
+
+/// Whole-chain verification, including the root's self-signature. Throughput is reported
+/// per signature.
+fn bench_verify_chain(c: &mut Criterion) {
+    let chains = sample_chains();
+    let mut group = c.benchmark_group("verify_chain");
+    for (name, der) in &chains {
+        let chain = CertChain::parse_rev(der).unwrap();
+        assert!(chain.verify_chain(0).unwrap(), "{} does not verify", name);
+        group.throughput(Throughput::Elements(chain.certs.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &chain, |b, chain| {
+            b.iter(|| chain.verify_chain(0).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Each signature in the sample chains on its own, labelled with its algorithm.
+fn bench_verify_signature(c: &mut Criterion) {
+    let chains = sample_chains();
+    let mut group = c.benchmark_group("verify_signature");
+    for (name, der) in &chains {
+        let chain = CertChain::parse_rev(der).unwrap();
+        for (i, (subject, issuer)) in chain.links().enumerate() {
+            let id = format!("{}/{}/{:?}", name, i, subject.sig_algo().unwrap());
+            group.bench_function(id, |b| b.iter(|| subject.verify(issuer).unwrap()));
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify_chain, bench_verify_signature);
+criterion_main!(benches);
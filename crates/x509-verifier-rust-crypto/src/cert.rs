@@ -1,14 +1,22 @@
-use std::borrow::Cow;
+use alloc::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 
-use crate::ec_decode_sig;
+use crate::ec_decode_sig_with;
+use crate::verify_ocsp_response;
 use crate::verify_signature;
+use crate::CertError;
 use crate::KeyAlgo;
+use crate::KeyAlgoParams;
+use crate::OcspStatus;
 use crate::PubKey;
 use crate::SigAlgo;
 
 use alloy_primitives::B256;
-use anyhow::anyhow;
-use anyhow::Context;
 use sha2::Sha256;
 use x509_parser::prelude::*;
 
@@ -20,15 +28,16 @@ pub struct Cert<'a> {
 }
 
 impl<'a> Cert<'a> {
-    pub fn parse_der(bytes: &'a [u8]) -> anyhow::Result<Self> {
+    pub fn parse_der(bytes: &'a [u8]) -> Result<Self, CertError> {
         let (remain, raw) = X509Certificate::from_der(bytes)
-            .map_err(|err| anyhow!("parse cert failed: {:?}", err))?;
+            .map_err(|err| CertError::ParseFailed(format!("{:?}", err)))?;
         if remain.len() != 0 {
-            return Err(anyhow!("parse cert not consume all bytes"));
+            return Err(CertError::ParseFailed("not consume all bytes".into()));
         }
         let pubkey_algo = {
             let info = raw.public_key();
-            KeyAlgo::from_algo(&info.algorithm)?
+            KeyAlgo::from_algo(&info.algorithm)
+                .map_err(|err| CertError::UnsupportedAlgorithm(format!("{:#}", err)))?
         };
         Ok(Self {
             raw,
@@ -42,34 +51,145 @@ impl<'a> Cert<'a> {
         (validity.not_before, validity.not_after)
     }
 
-    pub fn check_valid(&self, time: ASN1Time) -> anyhow::Result<()> {
+    pub fn check_valid(&self, time: ASN1Time) -> Result<(), CertError> {
         let validity = &self.raw.validity;
-        if !validity.is_valid_at(time) {
-            Err(anyhow!(
-                "certificate is not valid at time: {}({}), range: {}({}) - {}({})",
-                time,
-                time.timestamp(),
-                validity.not_before,
-                validity.not_before.timestamp(),
-                validity.not_after,
-                validity.not_after.timestamp(),
-            ))
+        if time < validity.not_before {
+            Err(CertError::NotYetValid {
+                index: None,
+                time: time.timestamp(),
+                not_before: validity.not_before.timestamp(),
+            })
+        } else if time > validity.not_after {
+            Err(CertError::Expired {
+                index: None,
+                time: time.timestamp(),
+                not_after: validity.not_after.timestamp(),
+            })
         } else {
             Ok(())
         }
     }
 
+    /// Returns the distance in seconds from `timestamp` to the nearest validity boundary:
+    /// the slack (positive) when inside the window, the overshoot (negative) when outside.
+    pub fn validity_margin(&self, timestamp: i64) -> i64 {
+        let (not_before, not_after) = self.validity();
+        (timestamp - not_before.timestamp()).min(not_after.timestamp() - timestamp)
+    }
+
+    /// Returns whether the cert expires within `window_secs` of `now` (or has already expired).
+    pub fn expires_within(&self, now: u64, window_secs: u64) -> bool {
+        let (_, not_after) = self.validity();
+        not_after.timestamp() - (now as i64) <= window_secs as i64
+    }
+
     pub fn digest(&self) -> B256 {
         sha256(self.bytes)
     }
 
+    /// Returns `(is_ca, path_len)` parsed from the BasicConstraints extension.
+    ///
+    /// A missing or malformed extension is treated as a non-CA certificate.
+    pub fn basic_constraints(&self) -> (bool, Option<u32>) {
+        match self.raw.basic_constraints() {
+            Ok(Some(ext)) => (ext.value.ca, ext.value.path_len_constraint),
+            _ => (false, None),
+        }
+    }
+
+    /// Returns the KeyUsage extension, if present.
+    pub fn key_usage(&self) -> Option<&KeyUsage> {
+        match self.raw.key_usage() {
+            Ok(Some(ext)) => Some(ext.value),
+            _ => None,
+        }
+    }
+
+    /// Returns the ExtendedKeyUsage extension, if present.
+    pub fn extended_key_usage(&self) -> Option<&ExtendedKeyUsage<'a>> {
+        match self.raw.extended_key_usage() {
+            Ok(Some(ext)) => Some(ext.value),
+            _ => None,
+        }
+    }
+
+    /// Returns the key identifier of the SubjectKeyIdentifier extension, if present.
+    pub fn subject_key_id(&self) -> Option<&[u8]> {
+        self.raw
+            .extensions()
+            .iter()
+            .find_map(|ext| match ext.parsed_extension() {
+                ParsedExtension::SubjectKeyIdentifier(KeyIdentifier(id)) => Some(*id),
+                _ => None,
+            })
+    }
+
+    /// Returns the key identifier of the AuthorityKeyIdentifier extension, if present. An
+    /// AuthorityKeyIdentifier naming the issuer only by name and serial yields `None`.
+    pub fn authority_key_id(&self) -> Option<&[u8]> {
+        self.raw
+            .extensions()
+            .iter()
+            .find_map(|ext| match ext.parsed_extension() {
+                ParsedExtension::AuthorityKeyIdentifier(aki) => {
+                    aki.key_identifier.as_ref().map(|id| id.0)
+                }
+                _ => None,
+            })
+    }
+
+    /// Returns the DNS names and URIs from the SubjectAlternativeName extension, in the
+    /// order they appear. Other name types are skipped; a missing extension yields an
+    /// empty list.
+    pub fn subject_alt_names(&self) -> anyhow::Result<Vec<String>> {
+        let ext = self
+            .raw
+            .subject_alternative_name()
+            .map_err(|err| anyhow::anyhow!("invalid SubjectAlternativeName: {}", err))?;
+        let Some(ext) = ext else {
+            return Ok(Vec::new());
+        };
+        Ok(ext
+            .value
+            .general_names
+            .iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(name) | GeneralName::URI(name) => Some(name.to_string()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Returns whether the cert has the shape of an AWS Nitro NSM signing cert: a non-CA
+    /// P-384 key with digitalSignature usage, subject `O=Amazon, OU=AWS` with an enclave
+    /// (`-enc`) common name, issued by a per-instance `*.nitro-enclaves` cert.
+    ///
+    /// These are observed characteristics rather than a documented AWS profile.
+    pub fn is_nsm_signing_cert(&self) -> bool {
+        fn first<'b, 'c: 'b>(
+            mut attrs: impl Iterator<Item = &'b AttributeTypeAndValue<'c>>,
+        ) -> Option<&'b str> {
+            attrs.next().and_then(|attr| attr.as_str().ok())
+        }
+        let subject = self.raw.subject();
+        let issuer = self.raw.issuer();
+
+        !self.basic_constraints().0
+            && self.pubkey_algo == KeyAlgo::ECDSA(KeyAlgoParams::P384)
+            && self.key_usage().is_some_and(|ku| ku.digital_signature())
+            && first(subject.iter_organization()) == Some("Amazon")
+            && first(subject.iter_organizational_unit()) == Some("AWS")
+            && first(subject.iter_common_name()).is_some_and(|cn| cn.contains("-enc"))
+            && first(issuer.iter_common_name()).is_some_and(|cn| cn.ends_with(".nitro-enclaves"))
+    }
+
     pub fn pubkey_algo(&self) -> anyhow::Result<KeyAlgo> {
         let info = self.raw.public_key();
         Ok(KeyAlgo::from_algo(&info.algorithm)?)
     }
 
     pub fn sig_algo(&self) -> anyhow::Result<SigAlgo> {
-        SigAlgo::from_oid(self.raw.signature_algorithm.oid())
+        SigAlgo::from_algo(&self.raw.signature_algorithm)
     }
 
     pub fn pubkey(&self) -> PubKey {
@@ -91,18 +211,59 @@ impl<'a> Cert<'a> {
         &self.bytes
     }
 
-    pub fn verify(&self, issuer: Option<&Self>) -> anyhow::Result<bool> {
+    /// Verifies the signature of this cert against `issuer` (or itself if `None`).
+    ///
+    /// Signature errors carry no chain index; [`CertChain::verify_chain`] fills in the
+    /// cert's position in the chain.
+    pub fn verify(&self, issuer: Option<&Self>) -> Result<bool, CertError> {
+        self.verify_with(issuer, false)
+    }
+
+    /// Like [`Cert::verify`], but when `require_low_s` is set an ECDSA signature that is
+    /// not in low-S form is reported as [`CertError::SignatureInvalid`].
+    pub fn verify_with(
+        &self,
+        issuer: Option<&Self>,
+        require_low_s: bool,
+    ) -> Result<bool, CertError> {
         let issuer_key = issuer.unwrap_or(self).pubkey();
-        let sig_algo = self.sig_algo()?;
+        let sig_algo = self
+            .sig_algo()
+            .map_err(|err| CertError::UnsupportedAlgorithm(format!("{:#}", err)))?;
 
-        sig_algo.check_compatible_with(issuer_key.algo)?;
+        sig_algo
+            .check_compatible_with(issuer_key.algo)
+            .map_err(|err| CertError::AlgorithmMismatch(format!("{:#}", err)))?;
 
+        let sig_invalid = |err: anyhow::Error| CertError::SignatureInvalid {
+            index: None,
+            reason: format!("{:#}", err),
+        };
         let mut sig = Cow::Borrowed(self.signature());
+        // only ECDSA signatures are DER-wrapped; RSA and Ed25519 signatures are used as-is
         if let KeyAlgo::ECDSA(params) = issuer_key.algo {
-            sig = Cow::Owned(ec_decode_sig(&sig, params)?);
+            sig = Cow::Owned(ec_decode_sig_with(&sig, params, require_low_s).map_err(sig_invalid)?);
+        }
+        verify_signature(issuer_key, sig_algo, &sig, self.tbs_certificate()).map_err(sig_invalid)
+    }
+
+    /// Verifies an X.509-style signature over `data`, DER-wrapped for ECDSA, made by this
+    /// cert's key. Used for signed structures other than certs, such as OCSP responses and CRLs.
+    pub(crate) fn verify_signed_data(
+        &self,
+        sig_algo: SigAlgo,
+        signature: &[u8],
+        data: &[u8],
+    ) -> anyhow::Result<bool> {
+        let pubkey = self.pubkey();
+        sig_algo.check_compatible_with(pubkey.algo)?;
+        match pubkey.algo {
+            KeyAlgo::ECDSA(params) => {
+                let signature = ec_decode_sig_with(signature, params, false)?;
+                verify_signature(pubkey, sig_algo, &signature, data)
+            }
+            _ => verify_signature(pubkey, sig_algo, signature, data),
         }
-        let result = verify_signature(issuer_key, sig_algo, &sig, self.tbs_certificate())?;
-        Ok(result)
     }
 }
 
@@ -112,6 +273,75 @@ pub fn sha256(bytes: &[u8]) -> B256 {
     digest.into()
 }
 
+/// Computes the path digests of a DER cert chain ordered root -> leaf, as returned by
+/// [`CertChain::digest`], without parsing the certs.
+///
+/// Index 0 is the raw sha256 digest of the root; every following entry is
+/// `sha256(parent_path_digest || sha256(cert))`, so each digest commits to the whole path
+/// from the root.
+pub fn chain_path_digest(certs: &[impl AsRef<[u8]>]) -> Vec<B256> {
+    let mut digests: Vec<B256> = Vec::with_capacity(certs.len());
+    for cert in certs {
+        digests.push(path_digest_step(digests.last(), sha256(cert.as_ref())));
+    }
+    digests
+}
+
+/// Decodes every `CERTIFICATE` block of a PEM bundle into DER, in file order.
+pub fn pem_to_der(pem: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut der_chain = Vec::new();
+    for pem in Pem::iter_from_buffer(pem) {
+        let pem = pem.map_err(|err| anyhow::anyhow!("decode PEM failed: {:?}", err))?;
+        if pem.label != "CERTIFICATE" {
+            return Err(anyhow::anyhow!("unexpected PEM block: {}", pem.label));
+        }
+        der_chain.push(pem.contents);
+    }
+    if der_chain.is_empty() {
+        return Err(anyhow::anyhow!("no PEM certificate found"));
+    }
+    Ok(der_chain)
+}
+
+fn path_digest_step(parent_digest: Option<&B256>, cert_digest: B256) -> B256 {
+    match parent_digest {
+        Some(parent_digest) => sha256(&[parent_digest.as_slice(), cert_digest.as_slice()].concat()),
+        None => cert_digest,
+    }
+}
+
+/// Optional checks applied by [`CertChain::verify_chain_with_policy`] on top of
+/// signature and BasicConstraints verification.
+#[derive(Debug, Clone, Default)]
+pub struct ChainPolicy {
+    /// Require every issuer cert to carry a KeyUsage extension asserting `keyCertSign`.
+    pub require_key_cert_sign: bool,
+    /// Require the leaf cert to carry a KeyUsage extension asserting `digitalSignature`.
+    pub require_leaf_digital_signature: bool,
+    /// When set, reject any cert signed with an algorithm outside this list, even if the
+    /// signature is valid.
+    pub algorithm_allowlist: Option<Vec<SigAlgo>>,
+    /// Reject ECDSA signatures whose `s` is above `n / 2`. Off by default, since X.509 lets
+    /// an issuer emit either of the two equivalent signatures.
+    pub require_low_s: bool,
+    /// Require each cert's AuthorityKeyIdentifier to match its issuer's
+    /// SubjectKeyIdentifier. Pairs where either extension is absent are not checked.
+    pub require_key_id_match: bool,
+    /// Reject chains containing the same cert more than once, see
+    /// [`CertChain::find_duplicates`].
+    pub reject_duplicates: bool,
+    /// Check the leaf's revocation status against a stapled OCSP response.
+    pub stapled_ocsp: Option<StapledOcsp>,
+}
+
+/// A DER `OCSPResponse` for the leaf cert, see [`verify_ocsp_response`].
+#[derive(Debug, Clone)]
+pub struct StapledOcsp {
+    pub response: Vec<u8>,
+    /// Unix time in seconds at which the response must be current.
+    pub now: u64,
+}
+
 pub struct CertChain<'a> {
     // cert order: root -> leaf
     pub certs: Vec<Cert<'a>>,
@@ -153,11 +383,10 @@ impl<'a> CertChain<'a> {
     }
 
     pub fn add_cert_by_der<'b: 'a>(&mut self, buf: &'b [u8]) -> anyhow::Result<()> {
-        let cert = Cert::parse_der(buf)?;
-        self.path_digest.push(match self.path_digest.last() {
-            Some(parent_digest) => sha256(&[parent_digest, &cert.digest()].concat()),
-            None => cert.digest(),
-        });
+        // `?` would need `CertError: std::error::Error`, which only exists with `std`
+        let cert = Cert::parse_der(buf).map_err(|err| anyhow::anyhow!(err))?;
+        self.path_digest
+            .push(path_digest_step(self.path_digest.last(), cert.digest()));
         self.certs.push(cert);
         Ok(())
     }
@@ -178,40 +407,353 @@ impl<'a> CertChain<'a> {
         &self.path_digest
     }
 
-    pub fn check_valid(&self, timestamp: u64) -> anyhow::Result<()> {
+    /// Pairs every cert, root first, with the cert that issued it. The root is self-signed
+    /// and paired with `None`, the same pairing [`CertChain::verify_chain`] verifies.
+    pub fn links(&self) -> impl Iterator<Item = (&Cert<'a>, Option<&Cert<'a>>)> {
+        self.certs
+            .iter()
+            .enumerate()
+            .map(|(i, cert)| (cert, i.checked_sub(1).map(|parent| &self.certs[parent])))
+    }
+
+    /// Parses a PEM bundle in the order OpenSSL writes it, leaf first and root last.
+    ///
+    /// Certs borrow their DER, so the decoded bytes are kept in `der`, which has to outlive
+    /// the chain.
+    pub fn from_pem(pem_bytes: &[u8], der: &'a mut Vec<Vec<u8>>) -> anyhow::Result<Self> {
+        *der = pem_to_der(pem_bytes)?;
+        let der: &'a Vec<Vec<u8>> = der;
+        Self::parse_rev(der)
+    }
+
+    /// Encodes the chain as a PEM bundle, leaf first, so that [`CertChain::from_pem`] reads
+    /// it back in the same root -> leaf order.
+    pub fn to_pem(&self) -> String {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let mut pem = String::new();
+        for cert in self.certs.iter().rev() {
+            let encoded = STANDARD.encode(cert.bytes());
+            pem.push_str("-----BEGIN CERTIFICATE-----\n");
+            for start in (0..encoded.len()).step_by(64) {
+                pem.push_str(&encoded[start..encoded.len().min(start + 64)]);
+                pem.push('\n');
+            }
+            pem.push_str("-----END CERTIFICATE-----\n");
+        }
+        pem
+    }
+
+    pub fn check_valid(&self, timestamp: u64) -> Result<(), CertError> {
         let time = ASN1Time::from_timestamp(timestamp as i64)
-            .map_err(|_| anyhow!("invalid timestamp: {}", timestamp))?;
+            .map_err(|_| CertError::InvalidTimestamp(timestamp))?;
         if self.certs.is_empty() {
-            return Err(anyhow!("cert chain is empty"));
+            return Err(CertError::EmptyChain);
         }
         for (idx, cert) in self.certs.iter().enumerate() {
-            cert.check_valid(time).with_context(|| {
-                format!("cert not valid at chain [{}/{}]", idx + 1, self.certs.len())
+            cert.check_valid(time).map_err(|err| match err {
+                CertError::NotYetValid {
+                    time, not_before, ..
+                } => CertError::NotYetValid {
+                    index: Some(idx),
+                    time,
+                    not_before,
+                },
+                CertError::Expired {
+                    time, not_after, ..
+                } => CertError::Expired {
+                    index: Some(idx),
+                    time,
+                    not_after,
+                },
+                err => err,
             })?;
         }
         Ok(())
     }
 
-    pub fn verify_chain(&self, trusted_certs_len: usize) -> anyhow::Result<bool> {
+    /// Checks that every non-leaf cert is a CA and that its pathLenConstraint
+    /// covers the intermediates below it. The leaf is allowed to be a non-CA.
+    pub fn check_basic_constraints(&self) -> Result<(), CertError> {
+        let issuers_len = self.certs.len().saturating_sub(1);
+        for (idx, cert) in self.certs[..issuers_len].iter().enumerate() {
+            let (is_ca, path_len) = cert.basic_constraints();
+            if !is_ca {
+                return Err(CertError::NotCa { index: idx });
+            }
+            let intermediates_below = issuers_len - idx - 1;
+            if let Some(path_len) = path_len {
+                if intermediates_below > path_len as usize {
+                    return Err(CertError::PathLenExceeded {
+                        index: idx,
+                        allowed: path_len,
+                        actual: intermediates_below,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the KeyUsage requirements of `policy` against the chain.
+    pub fn check_key_usage(&self, policy: &ChainPolicy) -> Result<(), CertError> {
+        let issuers_len = self.certs.len().saturating_sub(1);
+        if policy.require_key_cert_sign {
+            for (idx, cert) in self.certs[..issuers_len].iter().enumerate() {
+                if !cert.key_usage().is_some_and(|ku| ku.key_cert_sign()) {
+                    return Err(CertError::MissingKeyUsage {
+                        index: idx,
+                        usage: "keyCertSign",
+                    });
+                }
+            }
+        }
+        if policy.require_leaf_digital_signature
+            && !self.certs.is_empty()
+            && !self
+                .leaf()
+                .key_usage()
+                .is_some_and(|ku| ku.digital_signature())
+        {
+            return Err(CertError::MissingKeyUsage {
+                index: issuers_len,
+                usage: "digitalSignature",
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that every cert in the chain, trusted ones included, is signed with an
+    /// algorithm from `policy.algorithm_allowlist`. A no-op when no allowlist is set.
+    pub fn check_algorithms(&self, policy: &ChainPolicy) -> Result<(), CertError> {
+        let Some(allowlist) = &policy.algorithm_allowlist else {
+            return Ok(());
+        };
+        for (idx, cert) in self.certs.iter().enumerate() {
+            let algo = cert
+                .sig_algo()
+                .map_err(|err| CertError::UnsupportedAlgorithm(format!("{:#}", err)))?;
+            if !allowlist.contains(&algo) {
+                return Err(CertError::AlgorithmNotAllowed { index: idx, algo });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that each cert's AuthorityKeyIdentifier matches the SubjectKeyIdentifier of
+    /// its issuer when the policy asks for it. The check is skipped for a pair where either
+    /// extension is missing, since not every CA sets them, and for the self-signed root.
+    pub fn check_key_ids(&self, policy: &ChainPolicy) -> Result<(), CertError> {
+        if !policy.require_key_id_match {
+            return Ok(());
+        }
+        for (idx, (cert, issuer)) in self.links().enumerate() {
+            let Some(issuer) = issuer else {
+                continue;
+            };
+            if let (Some(aki), Some(ski)) = (cert.authority_key_id(), issuer.subject_key_id()) {
+                if aki != ski {
+                    return Err(CertError::KeyIdMismatch { index: idx });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the `(first, duplicate)` index pairs of byte-identical certs in the chain,
+    /// e.g. a root included both in the bundle and as a trusted anchor. Such chains still
+    /// verify, since a self-signed root also signs its own copy, but shift every index and
+    /// path digest below the duplicate.
+    pub fn find_duplicates(&self) -> Vec<(usize, usize)> {
+        let mut duplicates = Vec::new();
+        for (i, cert) in self.certs.iter().enumerate() {
+            for (j, other) in self.certs.iter().enumerate().skip(i + 1) {
+                if cert.bytes == other.bytes {
+                    duplicates.push((i, j));
+                }
+            }
+        }
+        duplicates
+    }
+
+    /// Fails on the first duplicate cert when the policy rejects them.
+    pub fn check_duplicates(&self, policy: &ChainPolicy) -> Result<(), CertError> {
+        if !policy.reject_duplicates {
+            return Ok(());
+        }
+        match self.find_duplicates().first() {
+            Some(&(first, index)) => Err(CertError::DuplicateCert { index, first }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the leaf against the policy's stapled OCSP response, if any. Only `Good` is
+    /// accepted; an `Unknown` status fails like an unverifiable response.
+    pub fn check_stapled_ocsp(&self, policy: &ChainPolicy) -> Result<(), CertError> {
+        let Some(stapled) = &policy.stapled_ocsp else {
+            return Ok(());
+        };
+        let (leaf, issuer) = self.links().last().ok_or(CertError::EmptyChain)?;
+        let issuer = issuer.ok_or_else(|| {
+            CertError::OcspFailed("the leaf has no issuer in the chain".to_string())
+        })?;
+        let status = verify_ocsp_response(leaf, issuer, &stapled.response, stapled.now)
+            .map_err(|err| CertError::OcspFailed(format!("{:#}", err)))?;
+        match status {
+            OcspStatus::Good => Ok(()),
+            OcspStatus::Revoked { .. } => Err(CertError::Revoked {
+                index: self.certs.len() - 1,
+            }),
+            OcspStatus::Unknown => Err(CertError::OcspFailed(
+                "the responder does not know the leaf".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the smallest [`Cert::validity_margin`] across the chain, i.e. how many
+    /// seconds `timestamp` can move before (positive) or has moved past (negative) the
+    /// tightest validity boundary. An empty chain returns `i64::MAX`.
+    pub fn validity_margin(&self, timestamp: u64) -> i64 {
+        self.certs
+            .iter()
+            .map(|cert| cert.validity_margin(timestamp as i64))
+            .min()
+            .unwrap_or(i64::MAX)
+    }
+
+    /// Returns the smallest `not_after - now` across the chain in seconds, negative if a cert
+    /// has already expired. Returns `None` for an empty chain.
+    pub fn min_remaining_validity(&self, now: u64) -> Option<i64> {
+        self.certs
+            .iter()
+            .map(|cert| cert.validity().1.timestamp() - now as i64)
+            .min()
+    }
+
+    /// Checks that `certs[0]` is a self-issued cert (subject == issuer) whose signature
+    /// verifies against its own public key.
+    ///
+    /// `verify_chain` skips the root whenever `trusted_certs_len >= 1`; combine this with a
+    /// digest comparison to pin a known root.
+    pub fn verify_root_self_signed(&self) -> Result<bool, CertError> {
+        if self.certs.is_empty() {
+            return Err(CertError::EmptyChain);
+        }
+        let root = self.root();
+        if root.raw.subject() != root.raw.issuer() {
+            return Ok(false);
+        }
+        root.verify(None)
+    }
+
+    /// Checks that the chain's root is the cert with digest `expected_root_digest`.
+    pub fn verify_root_pinned(&self, expected_root_digest: B256) -> Result<(), CertError> {
+        if self.certs.is_empty() {
+            return Err(CertError::EmptyChain);
+        }
+        let actual = self.root().digest();
+        if actual != expected_root_digest {
+            return Err(CertError::RootMismatch {
+                expected: expected_root_digest,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn verify_chain(&self, trusted_certs_len: usize) -> Result<bool, CertError> {
+        self.verify_chain_with_policy(trusted_certs_len, &ChainPolicy::default())
+    }
+
+    pub fn verify_chain_with_policy(
+        &self,
+        trusted_certs_len: usize,
+        policy: &ChainPolicy,
+    ) -> Result<bool, CertError> {
+        self.verify_chain_cached(trusted_certs_len, policy, &mut BTreeMap::new())
+    }
+
+    /// Verifies the chain, trusting every cert whose path digest (see [`CertChain::digest`])
+    /// is in `trusted`, as the verifier contract does with its cert cache.
+    ///
+    /// A path digest commits to the cert and all of its issuers, so the deepest trusted cert
+    /// marks the end of the trusted prefix wherever it sits in the chain; only the certs
+    /// after it are verified. If no cert is trusted the whole chain is verified, including
+    /// the root's self-signature, as with `verify_chain(0)`.
+    pub fn verify_chain_with_trusted_set(
+        &self,
+        trusted: &BTreeSet<B256>,
+    ) -> Result<bool, CertError> {
+        let trusted_certs_len = self
+            .path_digest
+            .iter()
+            .rposition(|digest| trusted.contains(digest))
+            .map_or(0, |idx| idx + 1);
+        self.verify_chain(trusted_certs_len)
+    }
+
+    /// Verifies many chains, checking each distinct signature only once.
+    ///
+    /// Chains from the same enclave share their root and intermediates. Signature results
+    /// are cached by path digest, which commits to both the cert and every issuer above it,
+    /// so a cached result is only reused for the exact same (cert, issuer) pair. A
+    /// `BTreeMap` keeps the cache usable without std. Results are in input order.
+    pub fn verify_many(
+        chains: &[CertChain<'a>],
+        trusted_certs_len: usize,
+    ) -> Vec<Result<bool, CertError>> {
+        let mut verified = BTreeMap::new();
+        chains
+            .iter()
+            .map(|chain| {
+                chain.verify_chain_cached(trusted_certs_len, &ChainPolicy::default(), &mut verified)
+            })
+            .collect()
+    }
+
+    fn verify_chain_cached(
+        &self,
+        trusted_certs_len: usize,
+        policy: &ChainPolicy,
+        verified: &mut BTreeMap<B256, bool>,
+    ) -> Result<bool, CertError> {
         if trusted_certs_len > self.certs.len() {
-            return Err(anyhow!(
-                "trusted certs length is greater than cert chain length"
-            ));
-        }
-        for i in trusted_certs_len..self.certs.len() {
-            let subject = &self.certs[i];
-            let issuer = if i == 0 {
-                None
-            } else {
-                Some(&self.certs[i - 1])
+            return Err(CertError::TrustedLenTooLarge {
+                trusted_len: trusted_certs_len,
+                chain_len: self.certs.len(),
+            });
+        }
+        self.check_duplicates(policy)?;
+        self.check_basic_constraints()?;
+        self.check_key_usage(policy)?;
+        self.check_algorithms(policy)?;
+        self.check_key_ids(policy)?;
+        for (i, (subject, issuer)) in self.links().enumerate().skip(trusted_certs_len) {
+            let result = match verified.get(&self.path_digest[i]) {
+                Some(result) => *result,
+                None => {
+                    let result = subject.verify_with(issuer, policy.require_low_s).map_err(
+                        |err| match err {
+                            CertError::SignatureInvalid { reason, .. } => {
+                                CertError::SignatureInvalid {
+                                    index: Some(i),
+                                    reason,
+                                }
+                            }
+                            err => err,
+                        },
+                    )?;
+                    verified.insert(self.path_digest[i], result);
+                    result
+                }
             };
-            if !subject
-                .verify(issuer)
-                .with_context(|| format!("verify cert sig failed at {}", i))?
-            {
+            if !result {
                 return Ok(false);
             }
         }
+        // the issuer's signature over the leaf is verified, so its OCSP responses can be
+        // trusted
+        self.check_stapled_ocsp(policy)?;
         Ok(true)
     }
 }
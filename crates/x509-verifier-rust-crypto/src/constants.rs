@@ -6,23 +6,38 @@ use x509_parser::der_parser::{oid, Oid};
 pub const OID_KEY_ALGO_EC: Oid = oid!(1.2.840.10045.2.1);
 pub const OID_KEY_ALGO_PKCS1_V1_5: Oid = oid!(1.2.840.113549.1.1.1);
 
+pub const OID_EC_PRIME_FIELD: Oid = oid!(1.2.840.10045.1.1);
+
 pub const EC_KEY_ALGO_OID: &str = "1.2.840.10045.2.1";
 
 pub const OLD_KEY_ALGO_PARAM_P256: Oid = oid!(1.2.840.10045.3.1.7);
 pub const OLD_KEY_ALGO_PARAM_P384: Oid = oid!(1.3.132.0.34);
+pub const OLD_KEY_ALGO_PARAM_P521: Oid = oid!(1.3.132.0.35);
 pub const EC_KEY_P256_PARAM_OID: &str = "1.2.840.10045.3.1.7";
 pub const EC_KEY_P384_PARAM_OID: &str = "1.3.132.0.34";
+pub const EC_KEY_P521_PARAM_OID: &str = "1.3.132.0.35";
+// Ed25519 uses the same OID for the key and the signature algorithm (RFC 8410)
+pub const OID_ALGO_ED25519: Oid = oid!(1.3.101.112);
+pub const ED25519_OID: &str = "1.3.101.112";
 pub const RSA_PKCS1_V1_5_KEY_OID: &str = "1.2.840.113549.1.1.1";
 
 // Signature Algo OIDs
 pub const OID_SIG_ALGO_ECDSA_SHA256: Oid = oid!(1.2.840.10045.4.3.2);
 pub const OID_SIG_ALGO_ECDSA_SHA384: Oid = oid!(1.2.840.10045.4.3.3);
+pub const OID_SIG_ALGO_ECDSA_SHA512: Oid = oid!(1.2.840.10045.4.3.4);
 pub const OID_SIG_ALGO_RSASSA_PSS: Oid = oid!(1.2.840.113549.1.1.10);
 pub const OID_SIG_ALGO_RSA_SHA256: Oid = oid!(1.2.840.113549.1.1.11);
 pub const ECDSA_SHA256_OID: &str = "1.2.840.10045.4.3.2";
 pub const ECDSA_SHA384_OID: &str = "1.2.840.10045.4.3.3";
+pub const ECDSA_SHA512_OID: &str = "1.2.840.10045.4.3.4";
 pub const RSASSA_PSS_OID: &str = "1.2.840.113549.1.1.10";
 pub const SHA256_WITH_RSA_ENCRYPTION_OID: &str = "1.2.840.113549.1.1.11";
 
 // Hash Algo OIDs
-pub const SHA384_HASH_OID: &str = "2.16.840.1.101.3.4.2.2";
\ No newline at end of file
+pub const OID_HASH_ALGO_SHA256: Oid = oid!(2.16.840.1.101.3.4.2.1);
+pub const OID_HASH_ALGO_SHA384: Oid = oid!(2.16.840.1.101.3.4.2.2);
+pub const OID_HASH_ALGO_SHA512: Oid = oid!(2.16.840.1.101.3.4.2.3);
+pub const SHA384_HASH_OID: &str = "2.16.840.1.101.3.4.2.2";
+
+// OCSP
+pub const OID_OCSP_BASIC: Oid = oid!(1.3.6.1.5.5.7.48.1.1);
\ No newline at end of file
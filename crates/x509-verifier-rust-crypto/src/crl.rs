@@ -0,0 +1,64 @@
+//! Revocation checks against certificate revocation lists (RFC 5280, section 5).
+
+use anyhow::{anyhow, bail};
+use x509_parser::revocation_list::CertificateRevocationList;
+
+use crate::{Cert, CertChain, CertError, SigAlgo};
+
+impl<'a> CertChain<'a> {
+    /// Fails if any cert in the chain is listed in its issuer's CRL.
+    ///
+    /// A CRL applies to the certs whose issuer name matches the CRL's issuer. Every
+    /// applicable CRL must be signed by that issuer and be current at `now` (unix seconds):
+    /// `thisUpdate` not in the future and `nextUpdate`, if present, not in the past. Certs
+    /// whose issuer has no CRL in `crls` are not checked, nor is the root.
+    pub fn check_revocation(
+        &self,
+        crls: &[CertificateRevocationList],
+        now: u64,
+    ) -> anyhow::Result<()> {
+        for (index, (cert, issuer)) in self.links().enumerate() {
+            let Some(issuer) = issuer else {
+                continue;
+            };
+            let issued_by = |crl: &&CertificateRevocationList| {
+                crl.issuer().as_raw() == issuer.raw.subject().as_raw()
+            };
+            for crl in crls.iter().filter(issued_by) {
+                check_crl(crl, issuer, now)?;
+                let serial = &cert.raw.tbs_certificate.serial;
+                if crl
+                    .iter_revoked_certificates()
+                    .any(|revoked| &revoked.user_certificate == serial)
+                {
+                    return Err(anyhow!(CertError::Revoked { index }));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that `crl` is signed by `issuer` and current at `now`.
+fn check_crl(crl: &CertificateRevocationList, issuer: &Cert, now: u64) -> anyhow::Result<()> {
+    let sig_algo = SigAlgo::from_algo(&crl.signature_algorithm)?;
+    let signed = issuer.verify_signed_data(
+        sig_algo,
+        crl.signature_value.as_ref(),
+        crl.tbs_cert_list.as_ref(),
+    )?;
+    if !signed {
+        bail!("CRL of {} has an invalid signature", crl.issuer());
+    }
+
+    let now = now as i64;
+    if crl.last_update().timestamp() > now {
+        bail!("CRL of {} is not yet valid", crl.issuer());
+    }
+    if let Some(next_update) = crl.next_update() {
+        if next_update.timestamp() < now {
+            bail!("CRL of {} is stale", crl.issuer());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,191 @@
+use alloc::string::String;
+use core::fmt;
+
+use alloy_primitives::B256;
+
+use crate::SigAlgo;
+
+/// Failure reasons reported by [`Cert`](crate::Cert) and [`CertChain`](crate::CertChain)
+/// verification.
+///
+/// With the `std` feature `CertError` implements `std::error::Error`, so it converts into
+/// `anyhow::Error` with `?`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertError {
+    /// The DER encoding of a certificate could not be parsed.
+    ParseFailed(String),
+    /// The certificate or issuer uses an algorithm this crate does not support.
+    UnsupportedAlgorithm(String),
+    /// The signature algorithm is not compatible with the issuer's key algorithm.
+    AlgorithmMismatch(String),
+    /// The signature of a cert could not be checked. `index` is the cert's chain position,
+    /// or `None` when a single [`Cert`](crate::Cert) was verified outside a chain.
+    SignatureInvalid {
+        index: Option<usize>,
+        reason: String,
+    },
+    /// `time` is before the cert's notBefore. `index` is as in
+    /// [`CertError::SignatureInvalid`].
+    NotYetValid {
+        index: Option<usize>,
+        time: i64,
+        not_before: i64,
+    },
+    /// `time` is after the cert's notAfter. `index` is as in
+    /// [`CertError::SignatureInvalid`].
+    Expired {
+        index: Option<usize>,
+        time: i64,
+        not_after: i64,
+    },
+    /// The timestamp cannot be represented as an ASN.1 time.
+    InvalidTimestamp(u64),
+    EmptyChain,
+    /// The chain's root does not match the pinned root digest.
+    RootMismatch {
+        expected: B256,
+        actual: B256,
+    },
+    TrustedLenTooLarge {
+        trusted_len: usize,
+        chain_len: usize,
+    },
+    /// An issuer cert is not marked as a CA in its BasicConstraints.
+    NotCa {
+        index: usize,
+    },
+    /// An issuer cert has more intermediates below it than its pathLenConstraint allows.
+    PathLenExceeded {
+        index: usize,
+        allowed: u32,
+        actual: usize,
+    },
+    /// A cert lacks a KeyUsage bit required by the [`ChainPolicy`](crate::ChainPolicy).
+    MissingKeyUsage {
+        index: usize,
+        usage: &'static str,
+    },
+    /// A cert is signed with an algorithm outside the [`ChainPolicy`](crate::ChainPolicy)
+    /// allowlist.
+    AlgorithmNotAllowed {
+        index: usize,
+        algo: SigAlgo,
+    },
+    /// A cert's AuthorityKeyIdentifier does not match its issuer's SubjectKeyIdentifier,
+    /// see [`ChainPolicy::require_key_id_match`](crate::ChainPolicy::require_key_id_match).
+    KeyIdMismatch {
+        index: usize,
+    },
+    /// The cert at `index` is byte-identical to the one at `first`, see
+    /// [`ChainPolicy::reject_duplicates`](crate::ChainPolicy::reject_duplicates).
+    DuplicateCert {
+        index: usize,
+        first: usize,
+    },
+    /// The stapled OCSP response reports the cert at `index` as revoked.
+    Revoked {
+        index: usize,
+    },
+    /// The stapled OCSP response could not be verified, or does not vouch for the leaf.
+    OcspFailed(String),
+}
+
+impl fmt::Display for CertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParseFailed(reason) => write!(f, "parse cert failed: {}", reason),
+            Self::UnsupportedAlgorithm(reason) => write!(f, "unsupported algorithm: {}", reason),
+            Self::AlgorithmMismatch(reason) => write!(f, "algorithm mismatch: {}", reason),
+            Self::SignatureInvalid { index, reason } => {
+                write!(f, "verify sig of {} failed: {}", CertAt(*index), reason)
+            }
+            Self::NotYetValid {
+                index,
+                time,
+                not_before,
+            } => write!(
+                f,
+                "{} is not yet valid at {}: {}s before not_before({})",
+                CertAt(*index),
+                time,
+                not_before - time,
+                not_before
+            ),
+            Self::Expired {
+                index,
+                time,
+                not_after,
+            } => write!(
+                f,
+                "{} is expired at {}: {}s after not_after({})",
+                CertAt(*index),
+                time,
+                time - not_after,
+                not_after
+            ),
+            Self::InvalidTimestamp(timestamp) => write!(f, "invalid timestamp: {}", timestamp),
+            Self::EmptyChain => write!(f, "cert chain is empty"),
+            Self::RootMismatch { expected, actual } => write!(
+                f,
+                "root cert digest mismatch: expected {}, got {}",
+                expected, actual
+            ),
+            Self::TrustedLenTooLarge {
+                trusted_len,
+                chain_len,
+            } => write!(
+                f,
+                "trusted certs length {} is greater than cert chain length {}",
+                trusted_len, chain_len
+            ),
+            Self::NotCa { index } => write!(f, "cert at chain [{}] is not a CA", index),
+            Self::PathLenExceeded {
+                index,
+                allowed,
+                actual,
+            } => write!(
+                f,
+                "cert at chain [{}] exceeds pathLenConstraint: allowed {}, got {}",
+                index, allowed, actual
+            ),
+            Self::MissingKeyUsage { index, usage } => {
+                write!(
+                    f,
+                    "cert at chain [{}] is missing {} key usage",
+                    index, usage
+                )
+            }
+            Self::AlgorithmNotAllowed { index, algo } => write!(
+                f,
+                "cert at chain [{}] is signed with {:?}, which is not allowed",
+                index, algo
+            ),
+            Self::KeyIdMismatch { index } => write!(
+                f,
+                "cert at chain [{}] authority key id does not match its issuer",
+                index
+            ),
+            Self::DuplicateCert { index, first } => write!(
+                f,
+                "cert at chain [{}] is a duplicate of the cert at [{}]",
+                index, first
+            ),
+            Self::Revoked { index } => write!(f, "cert at chain [{}] is revoked", index),
+            Self::OcspFailed(reason) => write!(f, "OCSP check failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CertError {}
+
+/// Names a cert by its chain position, if known, in error messages.
+struct CertAt(Option<usize>);
+
+impl fmt::Display for CertAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(index) => write!(f, "cert at chain [{}]", index),
+            None => write!(f, "certificate"),
+        }
+    }
+}
@@ -1,4 +1,12 @@
+extern crate alloc;
+
 // Override the crates by the precompiles
+//
+// Without the `sp1`/`risc0` features the plain RustCrypto crates are used. The swapped
+// crates back these `verify_signature` paths: P-256 ECDSA (p256), ECDSA over SHA-256 and
+// every cert digest (sha2), and RSA PKCS#1 v1.5 / RSASSA-PSS (rsa, sha2). P-384, P-521 and
+// Ed25519 always use the plain crates. Under `sp1` the tests cross-check the swapped paths
+// against the plain crates, which stay linked as `*_native`.
 
 #[cfg(feature = "sp1")]
 pub extern crate sha2_sp1 as sha2;
@@ -21,19 +29,36 @@ pub extern crate rsa_risc0 as rsa;
 #[cfg(all(not(feature = "sp1"), not(feature = "risc0_unstable")))]
 pub use rsa;
 
+#[cfg(all(test, feature = "sp1"))]
+extern crate p256 as p256_native;
+#[cfg(all(test, feature = "sp1"))]
+extern crate rsa as rsa_native;
+#[cfg(all(test, feature = "sp1"))]
+extern crate sha2 as sha2_native;
+
 mod cert;
 pub mod constants;
 pub use cert::*;
+mod error;
+pub use error::*;
 mod sign;
 pub use sign::*;
+mod crl;
+pub use crl::*;
+mod ocsp;
+pub use ocsp::*;
 
 // re-exports
 pub use x509_parser;
 
 #[cfg(test)]
 mod tests {
-    use crate::CertChain;
-    use x509_parser::prelude::*;
+    use std::collections::BTreeSet;
+
+    use crate::{
+        chain_path_digest, verify_ocsp_response, CertChain, CertError, ChainPolicy, HashAlgo,
+        KeyAlgo, KeyAlgoParams, OcspStatus, SigAlgo, StapledOcsp,
+    };
 
     #[test]
     fn test_short_sig() {
@@ -121,18 +146,756 @@ mod tests {
         assert!(cert_chain.verify_chain(0).unwrap(), "verification failed");
     }
 
+    #[test]
+    fn test_vcek_chain_basic_constraints() {
+        let ca_pem_chain_data = std::fs::read("./samples/vcek_milan_cert_chain.pem").unwrap();
+        let vcek_der = std::fs::read("./samples/vcek.der").unwrap();
+        let mut der_chain = pem_to_der(&ca_pem_chain_data);
+        der_chain.insert(0, vcek_der);
+
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        assert_eq!(cert_chain.certs[0].basic_constraints(), (true, None));
+        assert_eq!(cert_chain.certs[1].basic_constraints(), (true, Some(0)));
+        assert_eq!(cert_chain.certs[2].basic_constraints(), (false, None));
+        assert!(cert_chain.check_basic_constraints().is_ok());
+    }
+
+    #[test]
+    fn test_links() {
+        let ca_pem_chain_data = std::fs::read("./samples/vcek_milan_cert_chain.pem").unwrap();
+        let vcek_der = std::fs::read("./samples/vcek.der").unwrap();
+        let mut der_chain = pem_to_der(&ca_pem_chain_data);
+        der_chain.insert(0, vcek_der);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+
+        let links: Vec<_> = cert_chain
+            .links()
+            .map(|(subject, issuer)| (subject.digest(), issuer.map(|issuer| issuer.digest())))
+            .collect();
+        let certs = &cert_chain.certs;
+        assert_eq!(
+            links,
+            vec![
+                (certs[0].digest(), None),
+                (certs[1].digest(), Some(certs[0].digest())),
+                (certs[2].digest(), Some(certs[1].digest())),
+            ]
+        );
+        for (subject, issuer) in cert_chain.links().skip(1) {
+            assert_eq!(subject.raw.issuer(), issuer.unwrap().raw.subject());
+        }
+    }
+
+    #[test]
+    fn test_key_id_linkage() {
+        let policy = ChainPolicy {
+            require_key_id_match: true,
+            ..Default::default()
+        };
+
+        // SEV-Milan's AKI names the ARK-Milan root's SKI
+        let pem_chain_data = std::fs::read("./samples/vcek_milan_cert_chain.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        let (root, intermediate) = (&cert_chain.certs[0], &cert_chain.certs[1]);
+        assert!(root.subject_key_id().is_some());
+        assert_eq!(intermediate.authority_key_id(), root.subject_key_id());
+        assert!(cert_chain.verify_chain_with_policy(0, &policy).unwrap());
+
+        // no key identifiers at all, so there is nothing to check
+        let pem_chain_data = std::fs::read("./samples/rsa_pss_sha256_chain.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        assert!(cert_chain
+            .certs
+            .iter()
+            .all(|cert| cert.subject_key_id().is_none() && cert.authority_key_id().is_none()));
+        assert!(cert_chain.verify_chain_with_policy(0, &policy).unwrap());
+
+        // the leaf is validly signed by the root but its AKI names another key
+        let pem_chain_data = std::fs::read("./samples/aki_mismatch.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        assert!(cert_chain.verify_chain(0).unwrap());
+        assert_eq!(
+            cert_chain.verify_chain_with_policy(0, &policy),
+            Err(CertError::KeyIdMismatch { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let pem_chain_data = std::fs::read("./samples/vcek_milan_cert_chain.pem").unwrap();
+        let vcek_der = std::fs::read("./samples/vcek.der").unwrap();
+        let mut der_chain = pem_to_der(&pem_chain_data);
+        der_chain.insert(0, vcek_der);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        assert!(cert_chain.find_duplicates().is_empty());
+
+        // ARK-Milan listed twice, as if the bundle and the trust anchor both carried it
+        let root = der_chain.last().unwrap().clone();
+        der_chain.push(root);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        assert_eq!(cert_chain.find_duplicates(), vec![(0, 1)]);
+        // the root signs its own copy, so the chain still verifies by default
+        assert!(cert_chain.verify_chain(0).unwrap());
+
+        let policy = ChainPolicy {
+            reject_duplicates: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            cert_chain.verify_chain_with_policy(0, &policy),
+            Err(CertError::DuplicateCert { index: 1, first: 0 })
+        );
+    }
+
+    #[test]
+    fn test_ocsp() {
+        // thisUpdate and nextUpdate of both responses
+        const THIS_UPDATE: u64 = 1792047842;
+        const NEXT_UPDATE: u64 = 1794639842;
+        let now = THIS_UPDATE + 3600;
+
+        let good_pem = std::fs::read("./samples/ocsp_good_chain.pem").unwrap();
+        let good_der = pem_to_der(&good_pem);
+        let good_chain = CertChain::parse_rev(&good_der).unwrap();
+        let revoked_pem = std::fs::read("./samples/ocsp_revoked_chain.pem").unwrap();
+        let revoked_der = pem_to_der(&revoked_pem);
+        let revoked_chain = CertChain::parse_rev(&revoked_der).unwrap();
+        let root = good_chain.root();
+
+        // signed by the issuer itself
+        let good = std::fs::read("./samples/ocsp_good.der").unwrap();
+        let status = verify_ocsp_response(good_chain.leaf(), root, &good, now).unwrap();
+        assert_eq!(status, OcspStatus::Good);
+        assert!(verify_ocsp_response(good_chain.leaf(), root, &good, THIS_UPDATE - 1).is_err());
+        assert!(verify_ocsp_response(good_chain.leaf(), root, &good, NEXT_UPDATE + 1).is_err());
+        // the response is about another cert
+        assert!(verify_ocsp_response(revoked_chain.leaf(), root, &good, now).is_err());
+
+        // signed by a delegated responder embedded in the response
+        let revoked = std::fs::read("./samples/ocsp_revoked.der").unwrap();
+        assert_eq!(
+            verify_ocsp_response(revoked_chain.leaf(), root, &revoked, now).unwrap(),
+            OcspStatus::Revoked {
+                revocation_time: 1790812800
+            }
+        );
+
+        // tampered producedAt
+        let mut tampered = good.clone();
+        let pos = tampered
+            .windows(15)
+            .position(|w| w == b"20261015070402Z")
+            .unwrap();
+        tampered[pos + 13] = b'3';
+        let err = verify_ocsp_response(good_chain.leaf(), root, &tampered, now).unwrap_err();
+        assert!(err.to_string().contains("not signed"));
+
+        // ocsp_good.der with thisUpdate set to February 31st and signed again
+        let invalid_date = std::fs::read("./samples/ocsp_invalid_date.der").unwrap();
+        let err = verify_ocsp_response(good_chain.leaf(), root, &invalid_date, now).unwrap_err();
+        assert!(err.to_string().contains("thisUpdate"), "{}", err);
+
+        let stapled = |response: &[u8]| ChainPolicy {
+            stapled_ocsp: Some(StapledOcsp {
+                response: response.to_vec(),
+                now,
+            }),
+            ..Default::default()
+        };
+        assert!(good_chain
+            .verify_chain_with_policy(0, &stapled(&good))
+            .unwrap());
+        assert_eq!(
+            revoked_chain.verify_chain_with_policy(0, &stapled(&revoked)),
+            Err(CertError::Revoked { index: 1 })
+        );
+        assert!(matches!(
+            revoked_chain.verify_chain_with_policy(0, &stapled(&good)),
+            Err(CertError::OcspFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_crl() {
+        use x509_parser::{prelude::FromDer, revocation_list::CertificateRevocationList};
+
+        // lastUpdate and nextUpdate of both CRLs
+        const LAST_UPDATE: u64 = 1792048043;
+        const NEXT_UPDATE: u64 = 1794640043;
+        let now = LAST_UPDATE + 3600;
+
+        let good_pem = std::fs::read("./samples/ocsp_good_chain.pem").unwrap();
+        let good_der = pem_to_der(&good_pem);
+        let good_chain = CertChain::parse_rev(&good_der).unwrap();
+        let revoked_pem = std::fs::read("./samples/ocsp_revoked_chain.pem").unwrap();
+        let revoked_der = pem_to_der(&revoked_pem);
+        let revoked_chain = CertChain::parse_rev(&revoked_der).unwrap();
+
+        // lists the leaf of the revoked chain
+        let revoking = std::fs::read("./samples/crl_revoked_leaf.der").unwrap();
+        let crls = [CertificateRevocationList::from_der(&revoking).unwrap().1];
+        good_chain.check_revocation(&crls, now).unwrap();
+        let err = revoked_chain.check_revocation(&crls, now).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CertError>(),
+            Some(&CertError::Revoked { index: 1 })
+        );
+        assert!(good_chain.check_revocation(&crls, LAST_UPDATE - 1).is_err());
+        assert!(good_chain.check_revocation(&crls, NEXT_UPDATE + 1).is_err());
+
+        let empty = std::fs::read("./samples/crl_empty.der").unwrap();
+        let crls = [CertificateRevocationList::from_der(&empty).unwrap().1];
+        good_chain.check_revocation(&crls, now).unwrap();
+        revoked_chain.check_revocation(&crls, now).unwrap();
+
+        // no CRL for the issuer
+        revoked_chain.check_revocation(&[], now).unwrap();
+
+        // tampered lastUpdate
+        let mut tampered = empty.clone();
+        let pos = tampered
+            .windows(13)
+            .position(|w| w == b"261015070723Z")
+            .unwrap();
+        tampered[pos + 11] = b'4';
+        let crls = [CertificateRevocationList::from_der(&tampered).unwrap().1];
+        let err = good_chain.check_revocation(&crls, now).unwrap_err();
+        assert!(err.to_string().contains("invalid signature"));
+    }
+
+    #[test]
+    fn test_pem_round_trip() {
+        let pem = std::fs::read("./samples/vcek_milan_cert_chain.pem").unwrap();
+        let mut der = Vec::new();
+        let cert_chain = CertChain::from_pem(&pem, &mut der).unwrap();
+        // the bundle lists SEV-Milan before its ARK-Milan root
+        assert_eq!(cert_chain.certs.len(), 2);
+        assert_eq!(
+            cert_chain.root().raw.subject().to_string(),
+            cert_chain.root().raw.issuer().to_string()
+        );
+        assert!(cert_chain.verify_chain(0).unwrap());
+
+        let encoded = cert_chain.to_pem();
+        assert_eq!(encoded.as_bytes(), &pem[..]);
+
+        let mut der2 = Vec::new();
+        let decoded = CertChain::from_pem(encoded.as_bytes(), &mut der2).unwrap();
+        assert_eq!(decoded.digest(), cert_chain.digest());
+
+        let mut der3 = Vec::new();
+        assert!(CertChain::from_pem(b"not a pem", &mut der3).is_err());
+    }
+
+    #[test]
+    fn test_path_len_violation() {
+        // root(pathLen=0) -> intermediate -> leaf
+        let pem_chain_data = std::fs::read("./samples/path_len_violation.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        assert_eq!(cert_chain.root().basic_constraints(), (true, Some(0)));
+        let expected = CertError::PathLenExceeded {
+            index: 0,
+            allowed: 0,
+            actual: 1,
+        };
+        assert_eq!(cert_chain.verify_chain(0), Err(expected.clone()));
+        assert_eq!(cert_chain.verify_chain(1), Err(expected));
+        assert_eq!(
+            cert_chain.verify_chain(4),
+            Err(CertError::TrustedLenTooLarge {
+                trusted_len: 4,
+                chain_len: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_key_usage_policy() {
+        let strict = ChainPolicy {
+            require_key_cert_sign: true,
+            require_leaf_digital_signature: true,
+            ..Default::default()
+        };
+
+        let certs = read_cert_chain_json("gcp_tdx_tpm_cert");
+        let cert_chain = CertChain::parse_rev(&certs).unwrap();
+        assert!(cert_chain.root().key_usage().unwrap().key_cert_sign());
+        assert!(cert_chain.root().extended_key_usage().is_some());
+        assert!(cert_chain.verify_chain_with_policy(0, &strict).unwrap());
+
+        // intermediate carries digitalSignature but not keyCertSign
+        let pem_chain_data = std::fs::read("./samples/no_key_cert_sign.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        assert!(cert_chain.verify_chain(0).unwrap());
+        assert_eq!(
+            cert_chain.verify_chain_with_policy(0, &strict),
+            Err(CertError::MissingKeyUsage {
+                index: 1,
+                usage: "keyCertSign"
+            })
+        );
+    }
+
+    #[test]
+    fn test_algorithm_allowlist() {
+        let es384_only = ChainPolicy {
+            algorithm_allowlist: Some(vec![SigAlgo::EcdsaSHA384]),
+            ..Default::default()
+        };
+
+        let pem_chain_data = std::fs::read("./samples/explicit_p384.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse(&der_chain).unwrap();
+        assert!(cert_chain.verify_chain_with_policy(0, &es384_only).unwrap());
+
+        // every cert is signed with ES256; the trusted root is checked too
+        let pem_chain_data = std::fs::read("./samples/shared_intermediate.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse(&der_chain[..3]).unwrap();
+        assert!(cert_chain.verify_chain(1).unwrap());
+        assert_eq!(
+            cert_chain.verify_chain_with_policy(1, &es384_only),
+            Err(CertError::AlgorithmNotAllowed {
+                index: 0,
+                algo: SigAlgo::EcdsaSHA256
+            })
+        );
+    }
+
+    #[test]
+    fn test_rsa_pss_sha256_chain() {
+        let pem_chain_data = std::fs::read("./samples/rsa_pss_sha256_chain.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse_rev(&der_chain).unwrap();
+        assert_eq!(
+            cert_chain.leaf().sig_algo().unwrap(),
+            SigAlgo::RsaPss {
+                hash: HashAlgo::SHA256,
+                salt_len: 32
+            }
+        );
+        assert!(cert_chain.verify_chain(0).unwrap(), "verification failed");
+
+        // the SEV-SNP chains use SHA-384 with a 48 byte salt
+        let certs = read_cert_chain_json("gcp_snp_vek_cert");
+        let cert_chain = CertChain::parse_rev(&certs).unwrap();
+        assert_eq!(
+            cert_chain.leaf().sig_algo().unwrap(),
+            SigAlgo::RsaPss {
+                hash: HashAlgo::SHA384,
+                salt_len: 48
+            }
+        );
+    }
+
+    #[test]
+    fn test_validity_margin() {
+        let certs = read_cert_chain_json("gcp_tdx_tpm_cert");
+        let cert_chain = CertChain::parse_rev(&certs).unwrap();
+        // the leaf has the tightest window
+        let (not_before, not_after) = cert_chain.leaf().validity();
+        let not_before = not_before.timestamp() as u64;
+        let not_after = not_after.timestamp() as u64;
+
+        assert_eq!(cert_chain.validity_margin(not_before + 10), 10);
+        assert!(cert_chain.check_valid(not_before + 10).is_ok());
+        assert_eq!(cert_chain.validity_margin(not_after - 10), 10);
+        assert!(cert_chain.check_valid(not_after - 10).is_ok());
+
+        assert_eq!(cert_chain.validity_margin(not_before - 42), -42);
+        let err = cert_chain.check_valid(not_before - 42).unwrap_err();
+        let leaf = cert_chain.digest().len() - 1;
+        assert!(matches!(err, CertError::NotYetValid { index: Some(i), .. } if i == leaf));
+        assert!(err.to_string().contains("42s before not_before"));
+
+        assert_eq!(cert_chain.validity_margin(not_after + 42), -42);
+        let err = cert_chain.check_valid(not_after + 42).unwrap_err();
+        assert!(matches!(err, CertError::Expired { index: Some(i), .. } if i == leaf));
+        assert!(err.to_string().contains("42s after not_after"));
+        assert!(err
+            .to_string()
+            .starts_with(&format!("cert at chain [{}] is expired", leaf)));
+    }
+
+    #[test]
+    fn test_min_remaining_validity() {
+        let certs = read_cert_chain_json("gcp_tdx_tpm_cert");
+        let cert_chain = CertChain::parse_rev(&certs).unwrap();
+        // leaf notAfter: 2055-04-29T04:19:37Z
+        let not_after = 2692585177;
+        let now = 1750000000;
+
+        assert_eq!(
+            cert_chain.min_remaining_validity(now),
+            Some(not_after - now as i64)
+        );
+        assert_eq!(
+            cert_chain.min_remaining_validity(not_after as u64 + 5),
+            Some(-5)
+        );
+        assert_eq!(CertChain::new().min_remaining_validity(now), None);
+
+        let leaf = cert_chain.leaf();
+        assert!(!leaf.expires_within(now, 3600));
+        assert!(leaf.expires_within(not_after as u64 - 3600, 3600));
+        assert!(leaf.expires_within(not_after as u64 + 1, 0));
+        // the root outlives the leaf
+        assert!(!cert_chain.root().expires_within(not_after as u64, 3600));
+    }
+
+    #[test]
+    fn test_explicit_p384_curve_params() {
+        // self-signed, with the SPKI carrying explicit secp384r1 domain parameters
+        let pem_chain_data = std::fs::read("./samples/explicit_p384.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse(&der_chain).unwrap();
+        assert_eq!(
+            cert_chain.root().pubkey_algo().unwrap(),
+            KeyAlgo::ECDSA(KeyAlgoParams::P384)
+        );
+        assert!(cert_chain.verify_chain(0).unwrap(), "verification failed");
+    }
+
+    #[test]
+    fn test_ed25519_self_signed() {
+        let pem_chain_data = std::fs::read("./samples/ed25519_self_signed.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse(&der_chain).unwrap();
+        let root = cert_chain.root();
+        assert_eq!(root.pubkey_algo().unwrap(), KeyAlgo::Ed25519);
+        assert_eq!(root.sig_algo().unwrap(), SigAlgo::Ed25519);
+        assert!(cert_chain.verify_chain(0).unwrap(), "verification failed");
+    }
+
+    #[test]
+    fn test_verify_root_self_signed() {
+        let aws_root = std::fs::read("../../samples/aws_root.der").unwrap();
+        let cert_chain = CertChain::parse([&aws_root]).unwrap();
+        assert!(cert_chain.verify_root_self_signed().unwrap());
+
+        // drop the root so that chain starts at an intermediate
+        let pem_chain_data = std::fs::read("./samples/vcek_milan_cert_chain.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse(&der_chain[..1]).unwrap();
+        assert!(!cert_chain.verify_root_self_signed().unwrap());
+
+        assert_eq!(
+            CertChain::new().verify_root_self_signed(),
+            Err(CertError::EmptyChain)
+        );
+    }
+
+    #[test]
+    fn test_chain_path_digest() {
+        let mut certs = read_cert_chain_json("gcp_tdx_tpm_cert");
+        let cert_chain = CertChain::parse_rev(&certs).unwrap();
+        certs.reverse();
+        let digests = chain_path_digest(&certs);
+        assert_eq!(digests, cert_chain.digest());
+        assert_eq!(digests[0], cert_chain.root().digest());
+    }
+
+    #[test]
+    fn test_verify_many_shared_intermediate() {
+        // root, intermediate, two leaves issued by the intermediate and a forged leaf
+        let pem_chain_data = std::fs::read("./samples/shared_intermediate.pem").unwrap();
+        let der = pem_to_der(&pem_chain_data);
+        let chains = der[2..]
+            .iter()
+            .map(|leaf| CertChain::parse([&der[0], &der[1], leaf]).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(chains[0].digest()[1], chains[1].digest()[1]);
+
+        let results = CertChain::verify_many(&chains, 1);
+        assert_eq!(results, vec![Ok(true), Ok(true), Ok(false)]);
+        for (chain, result) in chains.iter().zip(&results) {
+            assert_eq!(&chain.verify_chain(1), result);
+        }
+    }
+
+    #[test]
+    fn test_subject_alt_names() {
+        let pem_chain_data = std::fs::read("./samples/subject_alt_name.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse(&der_chain).unwrap();
+        // the IP address entry is skipped
+        assert_eq!(
+            cert_chain.leaf().subject_alt_names().unwrap(),
+            vec![
+                "enclave.example.com",
+                "urn:aws:nitro:i-0123456789abcdef0-enc0123456789abcdef",
+                "*.enclave.example.com",
+            ]
+        );
+
+        let pem_chain_data = std::fs::read("./samples/vcek_milan_cert_chain.pem").unwrap();
+        let der_chain = pem_to_der(&pem_chain_data);
+        let cert_chain = CertChain::parse(&der_chain).unwrap();
+        assert!(cert_chain.leaf().subject_alt_names().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_verify_chain_with_trusted_set() {
+        // an unrelated root in front of the shared intermediate and one of its leaves
+        let root = pem_to_der(&std::fs::read("./samples/explicit_p384.pem").unwrap());
+        let der = pem_to_der(&std::fs::read("./samples/shared_intermediate.pem").unwrap());
+        let cert_chain = CertChain::parse([&root[0], &der[1], &der[2]]).unwrap();
+        assert!(!cert_chain.verify_chain(1).unwrap());
+
+        // trusting the intermediate skips the root -> intermediate edge
+        let trusted = BTreeSet::from([cert_chain.digest()[1]]);
+        assert!(cert_chain.verify_chain_with_trusted_set(&trusted).unwrap());
+
+        // trusting only the root still verifies the intermediate
+        let trusted = BTreeSet::from([cert_chain.digest()[0]]);
+        assert!(!cert_chain.verify_chain_with_trusted_set(&trusted).unwrap());
+        assert!(!cert_chain
+            .verify_chain_with_trusted_set(&BTreeSet::new())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_parse_der_malformed_never_panics() {
+        use crate::{ec_decode_sig, Cert};
+
+        // xorshift64, so failures are reproducible
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for len in [0, 1, 2, 16, 64, 512] {
+            let data: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            assert!(Cert::parse_der(&data).is_err());
+            assert!(ec_decode_sig(&data, KeyAlgoParams::P384).is_err());
+        }
+
+        let der = std::fs::read("../../samples/aws_root.der").unwrap();
+        for _ in 0..2000 {
+            let mut data = der.clone();
+            if next() % 2 == 0 {
+                data.truncate(next() as usize % der.len());
+            } else {
+                let idx = next() as usize % data.len();
+                data[idx] = next() as u8;
+            }
+            // any result will do as long as it is returned
+            if let Ok(cert) = Cert::parse_der(&data) {
+                let _ = cert.verify(None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_low_s() {
+        use crate::{ec_decode_sig, ec_decode_sig_with, ec_is_low_s, verify_signature, Cert};
+
+        fn sub_be(a: &[u8], b: &[u8]) -> Vec<u8> {
+            let mut out = vec![0u8; a.len()];
+            let mut borrow = 0i16;
+            for i in (0..a.len()).rev() {
+                let mut diff = a[i] as i16 - b[i] as i16 - borrow;
+                borrow = (diff < 0) as i16;
+                if diff < 0 {
+                    diff += 256;
+                }
+                out[i] = diff as u8;
+            }
+            out
+        }
+        fn der_int(bytes: &[u8]) -> Vec<u8> {
+            let start = bytes
+                .iter()
+                .position(|b| *b != 0)
+                .unwrap_or(bytes.len() - 1);
+            let mut int = bytes[start..].to_vec();
+            if int[0] & 0x80 != 0 {
+                int.insert(0, 0);
+            }
+            [vec![0x02, int.len() as u8], int].concat()
+        }
+        fn der_sig(raw: &[u8]) -> Vec<u8> {
+            let (r, s) = raw.split_at(raw.len() / 2);
+            let body = [der_int(r), der_int(s)].concat();
+            [vec![0x30, body.len() as u8], body].concat()
+        }
+
+        // the AWS Nitro root is self-signed with ECDSA P-384 / SHA-384
+        let der = std::fs::read("../../samples/aws_root.der").unwrap();
+        let root = Cert::parse_der(&der).unwrap();
+        let params = KeyAlgoParams::P384;
+        let n = alloy_primitives::hex!("ffffffffffffffffffffffffffffffffffffffffffffffffc7634d81f4372ddf581a0db248b0a77aecec196accc52973");
+
+        let raw = ec_decode_sig(root.signature(), params).unwrap();
+        let (r, s) = raw.split_at(raw.len() / 2);
+        let flipped = [r, &sub_be(&n, s)].concat();
+        let (low, high) = if ec_is_low_s(&raw, params) {
+            (raw.clone(), flipped)
+        } else {
+            (flipped, raw.clone())
+        };
+        assert!(ec_is_low_s(&low, params));
+        assert!(!ec_is_low_s(&high, params));
+
+        // both forms are valid signatures over the same message
+        for sig in [&low, &high] {
+            let valid = verify_signature(
+                root.pubkey(),
+                root.sig_algo().unwrap(),
+                sig,
+                root.tbs_certificate(),
+            )
+            .unwrap();
+            assert!(valid);
+        }
+
+        // high-S is accepted by default and rejected only on request
+        let (low_der, high_der) = (der_sig(&low), der_sig(&high));
+        assert_eq!(ec_decode_sig(&high_der, params).unwrap(), high);
+        assert_eq!(ec_decode_sig_with(&low_der, params, true).unwrap(), low);
+        assert!(ec_decode_sig_with(&high_der, params, false).is_ok());
+        assert!(ec_decode_sig_with(&high_der, params, true).is_err());
+
+        assert!(root.verify(None).unwrap());
+        let strict = root.verify_with(None, true);
+        if ec_is_low_s(&raw, params) {
+            assert!(strict.unwrap());
+        } else {
+            assert!(matches!(
+                strict,
+                Err(CertError::SignatureInvalid { index: None, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_verify_signature_signature() {
+        // callers across the workspace (and the zkVM guests) rely on this exact signature
+        let _: fn(crate::PubKey, SigAlgo, &[u8], &[u8]) -> anyhow::Result<bool> =
+            crate::verify_signature;
+    }
+
+    /// Verifies with the plain RustCrypto crates, or returns `None` for the algorithms whose
+    /// crates are never swapped for a precompile.
+    #[cfg(feature = "sp1")]
+    fn verify_plain(
+        pubkey: &crate::PubKey,
+        sig_algo: SigAlgo,
+        sig: &[u8],
+        msg: &[u8],
+    ) -> Option<bool> {
+        use crate::{
+            rsa_native::{pkcs1::DecodeRsaPublicKey, pkcs1v15, pss, RsaPublicKey},
+            sha2_native::{Digest, Sha256, Sha384, Sha512},
+        };
+
+        Some(match (pubkey.algo, sig_algo) {
+            (KeyAlgo::ECDSA(KeyAlgoParams::P256), SigAlgo::EcdsaSHA256) => {
+                use crate::p256_native::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+                let key = VerifyingKey::from_sec1_bytes(pubkey.val).ok()?;
+                let sig = Signature::from_slice(sig).ok()?;
+                key.verify(msg, &sig).is_ok()
+            }
+            (KeyAlgo::ECDSA(KeyAlgoParams::P384), SigAlgo::EcdsaSHA256) => {
+                use p384::ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+                let key = VerifyingKey::from_sec1_bytes(pubkey.val).ok()?;
+                let sig = Signature::from_slice(sig).ok()?;
+                key.verify_prehash(&Sha256::digest(msg), &sig).is_ok()
+            }
+            (KeyAlgo::RSA, SigAlgo::RsaSHA256) => {
+                use crate::rsa_native::signature::Verifier;
+                let key = RsaPublicKey::from_pkcs1_der(pubkey.val).ok()?;
+                let sig = pkcs1v15::Signature::try_from(sig).ok()?;
+                pkcs1v15::VerifyingKey::<Sha256>::new(key)
+                    .verify(msg, &sig)
+                    .is_ok()
+            }
+            (KeyAlgo::RSA, SigAlgo::RsaPss { hash, salt_len }) => {
+                use crate::rsa_native::signature::Verifier;
+                let key = RsaPublicKey::from_pkcs1_der(pubkey.val).ok()?;
+                let sig = pss::Signature::try_from(sig).ok()?;
+                let salt_len = salt_len as usize;
+                match hash {
+                    HashAlgo::SHA256 => {
+                        pss::VerifyingKey::<Sha256>::new_with_salt_len(key, salt_len)
+                            .verify(msg, &sig)
+                            .is_ok()
+                    }
+                    HashAlgo::SHA384 => {
+                        pss::VerifyingKey::<Sha384>::new_with_salt_len(key, salt_len)
+                            .verify(msg, &sig)
+                            .is_ok()
+                    }
+                    HashAlgo::SHA512 => {
+                        pss::VerifyingKey::<Sha512>::new_with_salt_len(key, salt_len)
+                            .verify(msg, &sig)
+                            .is_ok()
+                    }
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// Checks that the `sp1` crate selection agrees with the plain crates, accepting and
+    /// rejecting the same signatures. Outside the zkVM the patched crates run in software,
+    /// so on the host this compares software against software and only catches divergence
+    /// in how the crates are swapped in. The precompiles themselves are cross-checked by
+    /// executing the SP1 guest in the prover's `test_guest_matches_native_verifier`.
+    #[test]
+    #[cfg(feature = "sp1")]
+    fn test_precompiles_match_plain_crates() {
+        use crate::{ec_decode_sig, sha2_native::Digest, verify_signature};
+
+        let chains = [
+            read_cert_chain_json("apple_ios_der_ec"),
+            read_cert_chain_json("azure_snp_vek_cert"),
+            read_cert_chain_json("gcp_snp_vek_cert"),
+            pem_to_der(&std::fs::read("./samples/android-attestation.pem").unwrap()),
+            pem_to_der(&std::fs::read("./samples/rsa_pss_sha256_chain.pem").unwrap()),
+        ];
+        let mut checked = 0;
+        for der_chain in &chains {
+            let cert_chain = CertChain::parse_rev(der_chain).unwrap();
+            for (idx, cert) in cert_chain.certs.iter().enumerate() {
+                let digest: [u8; 32] = crate::sha2_native::Sha256::digest(cert.bytes()).into();
+                assert_eq!(cert.digest(), alloy_primitives::B256::from(digest));
+
+                // the root is self-signed
+                let pubkey = cert_chain.certs[idx.saturating_sub(1)].pubkey();
+                let sig_algo = cert.sig_algo().unwrap();
+                let sig = match pubkey.algo {
+                    KeyAlgo::ECDSA(params) => ec_decode_sig(cert.signature(), params).unwrap(),
+                    _ => cert.signature().to_vec(),
+                };
+                let mut tampered = cert.tbs_certificate().to_vec();
+                *tampered.last_mut().unwrap() ^= 1;
+                for msg in [cert.tbs_certificate(), &tampered[..]] {
+                    let Some(plain) = verify_plain(&pubkey, sig_algo, &sig, msg) else {
+                        continue;
+                    };
+                    let swapped = verify_signature(pubkey.clone(), sig_algo, &sig, msg).unwrap();
+                    assert_eq!(swapped, plain, "{:?} with {:?}", sig_algo, pubkey.algo);
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 0);
+    }
+
     // Helper function
 
     // PEM chain to DER-encoded bytes conversion
     // Provide PEM data directly to this function call
     fn pem_to_der(pem_chain: &[u8]) -> Vec<Vec<u8>> {
-        let mut der_chain: Vec<Vec<u8>> = Vec::new();
-
-        for pem in Pem::iter_from_buffer(pem_chain) {
-            let current_pem_content = pem.unwrap().contents;
-            der_chain.push(current_pem_content);
-        }
-
-        der_chain
+        crate::pem_to_der(pem_chain).unwrap()
     }
 }
@@ -0,0 +1,265 @@
+//! Verification of stapled OCSP responses (RFC 6960).
+//!
+//! x509-parser has no OCSP types, so responses are decoded with the asn1-rs parsers it
+//! re-exports, keeping the raw `tbsResponseData` bytes the signature covers.
+
+use alloc::vec::Vec;
+
+use anyhow::{anyhow, bail};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use x509_parser::{
+    der_parser::asn1_rs::{Any, BitString, Class, Enumerated, Oid, Tag},
+    prelude::FromDer,
+    time::ASN1Time,
+    x509::AlgorithmIdentifier,
+};
+
+use crate::constants::OID_OCSP_BASIC;
+use crate::{Cert, HashAlgo, SigAlgo};
+
+/// Revocation status of a cert, as reported by an OCSP responder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OcspStatus {
+    Good,
+    /// Revoked at `revocation_time`, in unix seconds.
+    Revoked {
+        revocation_time: i64,
+    },
+    /// The responder does not know the cert.
+    Unknown,
+}
+
+/// Checks the status of `cert` in the DER `OCSPResponse` `response_der`.
+///
+/// The response must be signed by `issuer` itself, or by a responder cert embedded in the
+/// response that `issuer` signed for `id-kp-OCSPSigning` and that is valid at `now` (unix
+/// seconds). The single response for `cert` must be current at `now`: `thisUpdate` not in
+/// the future and `nextUpdate`, if present, not in the past.
+///
+/// Only SHA-256, SHA-384 and SHA-512 CertIDs are supported.
+pub fn verify_ocsp_response(
+    cert: &Cert,
+    issuer: &Cert,
+    response_der: &[u8],
+    now: u64,
+) -> anyhow::Result<OcspStatus> {
+    let (response, _) = expect(response_der, Tag::Sequence, "OCSPResponse")?;
+    let (status, rest) = parse::<Enumerated>(response.data, "responseStatus")?;
+    if status.0 != 0 {
+        bail!("OCSP response status is not successful: {}", status.0);
+    }
+    let (bytes, _) = expect_context(rest, 0, "responseBytes")?;
+    let (bytes, _) = expect(bytes.data, Tag::Sequence, "responseBytes")?;
+    let (response_type, rest) = parse::<Oid>(bytes.data, "responseType")?;
+    if response_type != OID_OCSP_BASIC {
+        bail!("unsupported OCSP response type, expected id-pkix-ocsp-basic");
+    }
+    let (basic, _) = expect(rest, Tag::OctetString, "response")?;
+    let (basic, _) = expect(basic.data, Tag::Sequence, "BasicOCSPResponse")?;
+    let (tbs, rest) = expect(basic.data, Tag::Sequence, "tbsResponseData")?;
+    let tbs_raw = raw(basic.data, rest);
+    let (rest, sig_algo) = AlgorithmIdentifier::from_der(rest)
+        .map_err(|err| anyhow!("parse OCSP signature algorithm failed: {:?}", err))?;
+    let (signature, rest) = parse::<BitString>(rest, "signature")?;
+    let mut certs = Vec::new();
+    if !rest.is_empty() {
+        let (wrapper, _) = expect_context(rest, 0, "certs")?;
+        let (seq, _) = expect(wrapper.data, Tag::Sequence, "certs")?;
+        let mut rest = seq.data;
+        while !rest.is_empty() {
+            let (_, remain) = expect(rest, Tag::Sequence, "certs")?;
+            certs.push(raw(rest, remain));
+            rest = remain;
+        }
+    }
+
+    let sig_algo = SigAlgo::from_algo(&sig_algo)?;
+    if signature.unused_bits != 0 {
+        bail!("malformed OCSP signature");
+    }
+    let signed_by = |signer: &Cert| {
+        signer
+            .verify_signed_data(sig_algo, &signature.data, tbs_raw)
+            .unwrap_or(false)
+    };
+    if !signed_by(issuer) {
+        let delegated = certs.iter().any(|der| match Cert::parse_der(der) {
+            Ok(responder) => {
+                is_delegated_responder(&responder, issuer, now) && signed_by(&responder)
+            }
+            Err(_) => false,
+        });
+        if !delegated {
+            bail!("OCSP response is not signed by the issuer or a responder it delegated to");
+        }
+    }
+
+    let status = find_single_response(tbs.data, cert, issuer, now)?;
+    Ok(status)
+}
+
+/// Finds the SingleResponse for `cert` in `ResponseData` and checks that it is current.
+fn find_single_response(
+    response_data: &[u8],
+    cert: &Cert,
+    issuer: &Cert,
+    now: u64,
+) -> anyhow::Result<OcspStatus> {
+    let mut rest = response_data;
+    if let Ok((version, remain)) = expect_context(rest, 0, "version") {
+        // only v1 is defined
+        let (version, _) = parse::<u32>(version.data, "version")?;
+        if version != 0 {
+            bail!("unsupported OCSP response version");
+        }
+        rest = remain;
+    }
+    let (responder_id, rest) = parse::<Any>(rest, "responderID")?;
+    if responder_id.class() != Class::ContextSpecific
+        || !matches!(responder_id.tag(), Tag(1) | Tag(2))
+    {
+        bail!("malformed OCSP responderID");
+    }
+    let (_, rest) = generalized_time(rest, "producedAt")?;
+    let (responses, _) = expect(rest, Tag::Sequence, "responses")?;
+
+    let mut rest = responses.data;
+    while !rest.is_empty() {
+        let (single, remain) = expect(rest, Tag::Sequence, "SingleResponse")?;
+        rest = remain;
+        let (cert_id, fields) = expect(single.data, Tag::Sequence, "certID")?;
+        if !cert_id_matches(cert_id.data, cert, issuer)? {
+            continue;
+        }
+
+        // CertStatus choices are IMPLICIT tagged
+        let (status, fields) = parse::<Any>(fields, "certStatus")?;
+        if status.class() != Class::ContextSpecific {
+            bail!("malformed OCSP certStatus");
+        }
+        let status = match status.tag() {
+            Tag(0) => OcspStatus::Good,
+            Tag(1) => {
+                let (time, _) = generalized_time(status.data, "revocationTime")?;
+                OcspStatus::Revoked {
+                    revocation_time: time.timestamp(),
+                }
+            }
+            Tag(2) => OcspStatus::Unknown,
+            Tag(tag) => bail!("unknown OCSP certStatus tag {}", tag),
+        };
+
+        let now = now as i64;
+        let (this_update, fields) = generalized_time(fields, "thisUpdate")?;
+        let this_update = this_update.timestamp();
+        if this_update > now {
+            bail!(
+                "OCSP response is not yet valid: thisUpdate {} is after {}",
+                this_update,
+                now
+            );
+        }
+        if let Ok((next_update, _)) = expect_context(fields, 0, "nextUpdate") {
+            let (next_update, _) = generalized_time(next_update.data, "nextUpdate")?;
+            let next_update = next_update.timestamp();
+            if next_update < now {
+                bail!(
+                    "OCSP response is stale: nextUpdate {} is before {}",
+                    next_update,
+                    now
+                );
+            }
+        }
+        return Ok(status);
+    }
+    Err(anyhow!("OCSP response has no status for the cert"))
+}
+
+/// Compares a CertID with `cert`: its serial, and the hashes of its issuer's name and key.
+fn cert_id_matches(cert_id: &[u8], cert: &Cert, issuer: &Cert) -> anyhow::Result<bool> {
+    let (rest, hash_algo) = AlgorithmIdentifier::from_der(cert_id)
+        .map_err(|err| anyhow!("parse OCSP CertID hash algorithm failed: {:?}", err))?;
+    let (name_hash, rest) = expect(rest, Tag::OctetString, "CertID issuerNameHash")?;
+    let (key_hash, rest) = expect(rest, Tag::OctetString, "CertID issuerKeyHash")?;
+    let (serial, _) = expect(rest, Tag::Integer, "CertID serialNumber")?;
+
+    if serial.data != cert.raw.tbs_certificate.raw_serial() {
+        return Ok(false);
+    }
+    let hash_algo = HashAlgo::from_oid(hash_algo.oid())?;
+    Ok(
+        name_hash.data == hash(hash_algo, issuer.raw.subject().as_raw())
+            && key_hash.data == hash(hash_algo, issuer.pubkey().val),
+    )
+}
+
+/// A delegated responder is issued by `issuer` for OCSP signing and valid at `now`.
+fn is_delegated_responder(responder: &Cert, issuer: &Cert, now: u64) -> bool {
+    let for_ocsp = responder
+        .extended_key_usage()
+        .is_some_and(|eku| eku.ocsp_signing);
+    let valid =
+        ASN1Time::from_timestamp(now as i64).is_ok_and(|time| responder.check_valid(time).is_ok());
+    for_ocsp && valid && matches!(responder.verify(Some(issuer)), Ok(true))
+}
+
+fn hash(algo: HashAlgo, data: &[u8]) -> Vec<u8> {
+    match algo {
+        HashAlgo::SHA256 => Sha256::digest(data).to_vec(),
+        HashAlgo::SHA384 => Sha384::digest(data).to_vec(),
+        HashAlgo::SHA512 => Sha512::digest(data).to_vec(),
+    }
+}
+
+/// Parses one DER element as `T` from the front of `input`; `field` names it in errors.
+fn parse<'a, T: FromDer<'a>>(input: &'a [u8], field: &str) -> anyhow::Result<(T, &'a [u8])> {
+    let (rest, value) =
+        T::from_der(input).map_err(|err| anyhow!("malformed OCSP {}: {:?}", field, err))?;
+    Ok((value, rest))
+}
+
+/// Parses one DER element of universal type `tag` from the front of `input`.
+fn expect<'a>(input: &'a [u8], tag: Tag, field: &str) -> anyhow::Result<(Any<'a>, &'a [u8])> {
+    let (any, rest) = parse::<Any>(input, field)?;
+    if any.class() != Class::Universal || any.tag() != tag {
+        bail!(
+            "malformed OCSP {}: expected {:?}, got {:?}",
+            field,
+            tag,
+            any.tag()
+        );
+    }
+    Ok((any, rest))
+}
+
+/// Parses one DER element with the context-specific tag `[tag]` from the front of `input`.
+fn expect_context<'a>(
+    input: &'a [u8],
+    tag: u32,
+    field: &str,
+) -> anyhow::Result<(Any<'a>, &'a [u8])> {
+    let (any, rest) = parse::<Any>(input, field)?;
+    if any.class() != Class::ContextSpecific || any.tag() != Tag(tag) {
+        bail!(
+            "malformed OCSP {}: expected [{}], got {:?}",
+            field,
+            tag,
+            any.tag()
+        );
+    }
+    Ok((any, rest))
+}
+
+/// Parses a GeneralizedTime from the front of `input`. Dates that do not exist, such as
+/// February 31, are rejected.
+fn generalized_time<'a>(input: &'a [u8], field: &str) -> anyhow::Result<(ASN1Time, &'a [u8])> {
+    let (_, rest) = expect(input, Tag::GeneralizedTime, field)?;
+    let (_, time) =
+        ASN1Time::from_der(input).map_err(|_| anyhow!("malformed OCSP {}: invalid date", field))?;
+    Ok((time, rest))
+}
+
+/// The whole encoding of the element at the front of `input` that `rest` follows.
+fn raw<'a>(input: &'a [u8], rest: &[u8]) -> &'a [u8] {
+    &input[..input.len() - rest.len()]
+}
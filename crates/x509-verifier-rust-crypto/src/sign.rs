@@ -1,5 +1,12 @@
 use crate::constants::*;
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use alloy_primitives::hex;
 use anyhow::anyhow;
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey as Ed25519VerifyingKey};
 use oid::ObjectIdentifier;
 use p256::ecdsa::{
     signature::Verifier as ECDSASha256Verifier, Signature as P256Signature,
@@ -9,16 +16,18 @@ use p384::ecdsa::{
     signature::hazmat::PrehashVerifier, Signature as P384Signature,
     VerifyingKey as P384VerifyingKey,
 };
+use p521::ecdsa::{Signature as P521Signature, VerifyingKey as P521VerifyingKey};
 use rsa::{
     pkcs1::DecodeRsaPublicKey,
     pkcs1v15::{Signature as PKCS1v15Signature, VerifyingKey as PKCS1v15VerifyingKey},
     pss::{Signature as PSSSignature, VerifyingKey as PSSVerifyingKey},
     RsaPublicKey,
 };
-use sha2::{Digest, Sha256, Sha384};
+use sha2::{Digest, Sha256, Sha384, Sha512};
 use x509_parser::der_parser::Oid;
 use x509_parser::{
-    der_parser::{ber::BerObjectContent, der::parse_der},
+    der_parser::{asn1_rs::Tag, ber::BerObjectContent, der::parse_der},
+    signature_algorithm::SignatureAlgorithm,
     x509::AlgorithmIdentifier,
 };
 
@@ -32,12 +41,14 @@ pub struct PubKey<'a> {
 pub enum KeyAlgoParams {
     P256,
     P384,
+    P521,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum KeyAlgo {
     ECDSA(KeyAlgoParams),
     RSA,
+    Ed25519,
 }
 
 impl KeyAlgo {
@@ -46,6 +57,9 @@ impl KeyAlgo {
             let Some(key_params) = &algo.parameters else {
                 return Err(anyhow!("ECDSA public key parameters are missing"));
             };
+            if key_params.tag() == Tag::Sequence {
+                return Ok(Self::ECDSA(ec_params_from_explicit(key_params.data)?));
+            }
             let param_oid = ObjectIdentifier::try_from(key_params.data).map_err(|err| {
                 anyhow!("Failed to parse ECDSA public key parameters OID: {:?}", err)
             })?;
@@ -54,6 +68,8 @@ impl KeyAlgo {
                 KeyAlgoParams::P256
             } else if param_oid == EC_KEY_P384_PARAM_OID.to_string() {
                 KeyAlgoParams::P384
+            } else if param_oid == EC_KEY_P521_PARAM_OID.to_string() {
+                KeyAlgoParams::P521
             } else {
                 return Err(anyhow!(
                     "Unsupported ECDSA key parameter OID: {}",
@@ -63,42 +79,216 @@ impl KeyAlgo {
             Ok(Self::ECDSA(key_params))
         } else if algo.oid() == &OID_KEY_ALGO_PKCS1_V1_5 {
             Ok(KeyAlgo::RSA)
+        } else if algo.oid() == &OID_ALGO_ED25519 {
+            Ok(KeyAlgo::Ed25519)
         } else {
             Err(anyhow!("Invalid algo: {:?}", algo))
         }
     }
 }
 
+/// Domain parameters of a named prime curve, used to recognize explicitly encoded curves.
+struct CurveParams {
+    p: &'static [u8],
+    a: &'static [u8],
+    b: &'static [u8],
+    /// Uncompressed generator point.
+    g: &'static [u8],
+    n: &'static [u8],
+}
+
+const NIST_CURVES: [(KeyAlgoParams, CurveParams); 3] = [
+    (
+        KeyAlgoParams::P256,
+        CurveParams {
+            p: &hex!("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff"),
+            a: &hex!("ffffffff00000001000000000000000000000000fffffffffffffffffffffffc"),
+            b: &hex!("5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b"),
+            g: &hex!("046b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c2964fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"),
+            n: &hex!("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551"),
+        },
+    ),
+    (
+        KeyAlgoParams::P384,
+        CurveParams {
+            p: &hex!("fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000ffffffff"),
+            a: &hex!("fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000fffffffc"),
+            b: &hex!("b3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875ac656398d8a2ed19d2a85c8edd3ec2aef"),
+            g: &hex!("04aa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a385502f25dbf55296c3a545e3872760ab73617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c00a60b1ce1d7e819d7a431d7c90ea0e5f"),
+            n: &hex!("ffffffffffffffffffffffffffffffffffffffffffffffffc7634d81f4372ddf581a0db248b0a77aecec196accc52973"),
+        },
+    ),
+    (
+        KeyAlgoParams::P521,
+        CurveParams {
+            p: &hex!("01ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"),
+            a: &hex!("01fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffc"),
+            b: &hex!("0051953eb9618e1c9a1f929a21a0b68540eea2da725b99b315f3b8b489918ef109e156193951ec7e937b1652c0bd3bb1bf073573df883d2c34f1ef451fd46b503f00"),
+            g: &hex!("0400c6858e06b70404e9cd9e3ecb662395b4429c648139053fb521f828af606b4d3dbaa14b5e77efe75928fe1dc127a2ffa8de3348b3c1856a429bf97e7e31c2e5bd66011839296a789a3bc0045c8a5fb42c7d1bd998f54449579b446817afbd17273e662c97ee72995ef42640c550b9013fad0761353c7086a272c24088be94769fd16650"),
+            n: &hex!("01fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffa51868783bf2f966b7fcc0148f709a5d03bb5c9b8899c47aebb6fb71e91386409"),
+        },
+    ),
+];
+
+impl CurveParams {
+    fn matches(&self, p: &[u8], a: &[u8], b: &[u8], g: &[u8], n: &[u8]) -> bool {
+        fn trim(n: &[u8]) -> &[u8] {
+            let start = n.iter().position(|b| *b != 0).unwrap_or(n.len());
+            &n[start..]
+        }
+        let base_matches = match g.first() {
+            Some(0x04) => g == self.g,
+            // compressed point: x coordinate plus the parity of y
+            Some(prefix @ (0x02 | 0x03)) => {
+                let x_len = (self.g.len() - 1) / 2;
+                g.len() == x_len + 1
+                    && g[1..] == self.g[1..=x_len]
+                    && (prefix & 1) == (self.g[self.g.len() - 1] & 1)
+            }
+            _ => false,
+        };
+        base_matches
+            && trim(p) == trim(self.p)
+            && trim(a) == trim(self.a)
+            && trim(b) == trim(self.b)
+            && trim(n) == trim(self.n)
+    }
+}
+
+/// Maps explicit ECParameters (RFC 3279, section 2.3.5) to the named NIST curve they
+/// describe. `data` is the content of the ECParameters SEQUENCE.
+fn ec_params_from_explicit(data: &[u8]) -> anyhow::Result<KeyAlgoParams> {
+    let malformed = || anyhow!("malformed explicit ECDSA public key parameters");
+    let mut items = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (remain, item) = parse_der(rest).map_err(|_| malformed())?;
+        items.push(item);
+        rest = remain;
+    }
+    let [_version, field_id, curve, base, order, ..] = items.as_slice() else {
+        return Err(malformed());
+    };
+    let field_id = field_id.as_sequence().map_err(|_| malformed())?;
+    let field_type = field_id
+        .first()
+        .and_then(|n| n.as_oid().ok())
+        .ok_or_else(malformed)?;
+    if field_type != &OID_EC_PRIME_FIELD {
+        return Err(anyhow!(
+            "Unsupported ECDSA field type: {}",
+            field_type.to_id_string()
+        ));
+    }
+    let prime = field_id
+        .get(1)
+        .and_then(|n| n.as_slice().ok())
+        .ok_or_else(malformed)?;
+    let curve = curve.as_sequence().map_err(|_| malformed())?;
+    let a = curve
+        .first()
+        .and_then(|n| n.as_slice().ok())
+        .ok_or_else(malformed)?;
+    let b = curve
+        .get(1)
+        .and_then(|n| n.as_slice().ok())
+        .ok_or_else(malformed)?;
+    let base = base.as_slice().map_err(|_| malformed())?;
+    let order = order.as_slice().map_err(|_| malformed())?;
+
+    NIST_CURVES
+        .iter()
+        .find(|(_, curve)| curve.matches(prime, a, b, base, order))
+        .map(|(params, _)| *params)
+        .ok_or_else(|| anyhow!("Unsupported explicit ECDSA curve parameters"))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HashAlgo {
+    SHA256,
+    SHA384,
+    SHA512,
+}
+
+impl HashAlgo {
+    pub fn from_oid(oid: &Oid) -> anyhow::Result<Self> {
+        if oid == &OID_HASH_ALGO_SHA256 {
+            Ok(HashAlgo::SHA256)
+        } else if oid == &OID_HASH_ALGO_SHA384 {
+            Ok(HashAlgo::SHA384)
+        } else if oid == &OID_HASH_ALGO_SHA512 {
+            Ok(HashAlgo::SHA512)
+        } else {
+            Err(anyhow!("unsupported hash oid: {:?}", oid.to_id_string()))
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SigAlgo {
     EcdsaSHA256,
     EcdsaSHA384,
-    RsaSSAPSS,
+    EcdsaSHA512,
+    RsaPss { hash: HashAlgo, salt_len: u32 },
     RsaSHA256,
+    Ed25519,
 }
 
 impl SigAlgo {
+    /// Maps a signature algorithm OID. RSASSA-PSS carries its hash and salt length in
+    /// the algorithm parameters, so it must be parsed with [`SigAlgo::from_algo`].
     pub fn from_oid(oid: &Oid) -> anyhow::Result<Self> {
         if oid == &OID_SIG_ALGO_ECDSA_SHA256 {
             Ok(SigAlgo::EcdsaSHA256)
         } else if oid == &OID_SIG_ALGO_ECDSA_SHA384 {
             Ok(SigAlgo::EcdsaSHA384)
+        } else if oid == &OID_SIG_ALGO_ECDSA_SHA512 {
+            Ok(SigAlgo::EcdsaSHA512)
         } else if oid == &OID_SIG_ALGO_RSASSA_PSS {
-            Ok(SigAlgo::RsaSSAPSS)
+            Err(anyhow!("RSASSA-PSS requires algorithm parameters"))
         } else if oid == &OID_SIG_ALGO_RSA_SHA256 {
             Ok(SigAlgo::RsaSHA256)
+        } else if oid == &OID_ALGO_ED25519 {
+            Ok(SigAlgo::Ed25519)
         } else {
             Err(anyhow!("invalid sig oid: {:?}", oid.to_id_string()))
         }
     }
 
+    pub fn from_algo(algo: &AlgorithmIdentifier) -> anyhow::Result<Self> {
+        if algo.oid() != &OID_SIG_ALGO_RSASSA_PSS {
+            return Self::from_oid(algo.oid());
+        }
+        let params = match SignatureAlgorithm::try_from(algo) {
+            Ok(SignatureAlgorithm::RSASSA_PSS(params)) => params,
+            Ok(other) => return Err(anyhow!("unexpected sig algo: {:?}", other)),
+            Err(err) => return Err(anyhow!("parse RSASSA-PSS params failed: {:?}", err)),
+        };
+        let hash = HashAlgo::from_oid(params.hash_algorithm_oid())?;
+        let mgf = params
+            .mask_gen_algorithm()
+            .map_err(|err| anyhow!("parse RSASSA-PSS mask gen algo failed: {:?}", err))?;
+        if HashAlgo::from_oid(&mgf.hash)? != hash {
+            return Err(anyhow!(
+                "RSASSA-PSS MGF1 hash does not match signature hash: {:?}",
+                hash
+            ));
+        }
+        Ok(SigAlgo::RsaPss {
+            hash,
+            salt_len: params.salt_length(),
+        })
+    }
+
     pub fn check_compatible_with(self, key_algo: KeyAlgo) -> anyhow::Result<()> {
         match (self, key_algo) {
             (SigAlgo::EcdsaSHA256, KeyAlgo::ECDSA(KeyAlgoParams::P256)) => Ok(()),
             (SigAlgo::EcdsaSHA256, KeyAlgo::ECDSA(KeyAlgoParams::P384)) => Ok(()),
             (SigAlgo::EcdsaSHA384, KeyAlgo::ECDSA(KeyAlgoParams::P384)) => Ok(()),
+            (SigAlgo::EcdsaSHA512, KeyAlgo::ECDSA(KeyAlgoParams::P521)) => Ok(()),
             (SigAlgo::RsaSHA256, KeyAlgo::RSA) => Ok(()),
-            (SigAlgo::RsaSSAPSS, KeyAlgo::RSA) => Ok(()),
+            (SigAlgo::RsaPss { .. }, KeyAlgo::RSA) => Ok(()),
+            (SigAlgo::Ed25519, KeyAlgo::Ed25519) => Ok(()),
             _ => Err(anyhow!(
                 "Incompatible key and signature algorithm, issuer_pubkey: {:?}, subject_sig: {:?}",
                 key_algo,
@@ -108,6 +298,12 @@ impl SigAlgo {
     }
 }
 
+/// Decodes a DER `ECDSA-Sig-Value` into the raw `r || s` form, each half padded to the
+/// curve's field size.
+///
+/// Both `(r, s)` and the equivalent `(r, n - s)` are accepted: X.509 does not require the
+/// low-S form, so issuers are free to emit either. Use [`ec_decode_sig_with`] to reject
+/// high-S signatures.
 pub fn ec_decode_sig(sig: &[u8], params: KeyAlgoParams) -> anyhow::Result<Vec<u8>> {
     let (_, decoded) = parse_der(sig).map_err(|err| anyhow!("decode der failed: {:?}", err))?;
     let mut ret: Vec<u8> = Vec::new();
@@ -115,13 +311,23 @@ pub fn ec_decode_sig(sig: &[u8], params: KeyAlgoParams) -> anyhow::Result<Vec<u8
     let expected_len = match params {
         KeyAlgoParams::P256 => 32usize,
         KeyAlgoParams::P384 => 48usize,
+        KeyAlgoParams::P521 => 66usize,
     };
 
     match decoded.content {
         BerObjectContent::Sequence(sig_obj) => {
             // ECDSA
+            if sig_obj.len() != 2 {
+                return Err(anyhow!(
+                    "decode ec sig failed: expected r and s, got {} elements",
+                    sig_obj.len()
+                ));
+            }
             for v in sig_obj.iter() {
-                let mut sig_slice = v.as_biguint().unwrap().to_bytes_be();
+                let mut sig_slice = v
+                    .as_biguint()
+                    .map_err(|err| anyhow!("decode ec sig failed: {:?}", err))?
+                    .to_bytes_be();
                 sig_slice = pad_zero_to_length(sig_slice, expected_len);
                 if sig_slice.len() != expected_len {
                     return Err(anyhow!(
@@ -139,6 +345,49 @@ pub fn ec_decode_sig(sig: &[u8], params: KeyAlgoParams) -> anyhow::Result<Vec<u8
     Ok(ret)
 }
 
+/// Like [`ec_decode_sig`], but when `require_low_s` is set also rejects signatures whose `s`
+/// is above `n / 2` (see [`ec_is_low_s`]).
+pub fn ec_decode_sig_with(
+    sig: &[u8],
+    params: KeyAlgoParams,
+    require_low_s: bool,
+) -> anyhow::Result<Vec<u8>> {
+    let decoded = ec_decode_sig(sig, params)?;
+    if require_low_s && !ec_is_low_s(&decoded, params) {
+        return Err(anyhow!("ECDSA signature is not in low-S form"));
+    }
+    Ok(decoded)
+}
+
+/// Returns whether the raw `r || s` signature has `s <= n / 2`, i.e. is the canonical one of
+/// the two valid signatures `(r, s)` and `(r, n - s)`.
+pub fn ec_is_low_s(sig: &[u8], params: KeyAlgoParams) -> bool {
+    let (_, curve) = NIST_CURVES
+        .iter()
+        .find(|(curve, _)| *curve == params)
+        .expect("every KeyAlgoParams has curve parameters");
+    // n / 2, big-endian, same width as n
+    let mut half_n = curve.n.to_vec();
+    let mut carry = 0;
+    for byte in half_n.iter_mut() {
+        let low_bit = *byte & 1;
+        *byte = (*byte >> 1) | (carry << 7);
+        carry = low_bit;
+    }
+    // `s` is padded to the width of `n`, so comparing the bytes compares the numbers
+    let s = &sig[sig.len() / 2..];
+    s.len() == half_n.len() && s <= half_n.as_slice()
+}
+
+/// Verifies `sig` over `msg` with `pubkey`. ECDSA signatures are the raw `r || s` returned
+/// by [`ec_decode_sig`]; RSA and Ed25519 signatures are used as-is.
+///
+/// Nothing here compares signature material byte by byte: recomputing the ECDSA `r`, the
+/// PKCS#1 v1.5 encoded digest and the PSS salt and hash checks all happen inside the
+/// RustCrypto verifiers. The comparisons in this module (algorithm OIDs, curve domain
+/// parameters, signature lengths) only look at public certificate fields, so they are not
+/// required to be constant-time. The one secret-adjacent comparison in the verification
+/// path, the attestation nonce, lives in the verifier crate and uses `subtle`.
 pub fn verify_signature(
     pubkey: PubKey,
     sig_algo: SigAlgo,
@@ -155,8 +404,8 @@ pub fn verify_signature(
             verifying_key.verify(msg, &signature).is_ok()
         }
         (KeyAlgo::ECDSA(KeyAlgoParams::P384), SigAlgo::EcdsaSHA256) => {
-            let verifying_key: P384VerifyingKey =
-                P384VerifyingKey::from_sec1_bytes(pubkey.val).unwrap();
+            let verifying_key = P384VerifyingKey::from_sec1_bytes(pubkey.val)
+                .map_err(|err| anyhow!("parse verifying key failed: {}", err))?;
             let signature = P384Signature::from_slice(&sig)
                 .map_err(|err| anyhow!("parse p384 signature failed: {:?}", err))?;
             let digest = Sha256::digest(msg);
@@ -169,18 +418,50 @@ pub fn verify_signature(
                 .map_err(|err| anyhow!("parse p384 signature failed: {:?}", err))?;
             verifying_key.verify(msg, &signature).is_ok()
         }
+        (KeyAlgo::ECDSA(KeyAlgoParams::P521), SigAlgo::EcdsaSHA512) => {
+            let verifying_key = P521VerifyingKey::from_sec1_bytes(pubkey.val)
+                .map_err(|err| anyhow!("parse verifying key failed: {}", err))?;
+            let signature = P521Signature::from_slice(&sig)
+                .map_err(|err| anyhow!("parse p521 signature failed: {:?}", err))?;
+            verifying_key.verify(msg, &signature).is_ok()
+        }
         (KeyAlgo::RSA, SigAlgo::RsaSHA256) => {
             let pub_key = RsaPublicKey::from_pkcs1_der(pubkey.val)
                 .map_err(|err| anyhow!("parse verifying key failed: {}", err))?;
             let verifying_key = <PKCS1v15VerifyingKey<Sha256>>::new(pub_key);
-            let signature = PKCS1v15Signature::try_from(sig).unwrap();
+            let signature = PKCS1v15Signature::try_from(sig)
+                .map_err(|err| anyhow!("parse pkcs1v15 signature failed: {}", err))?;
             verifying_key.verify(msg, &signature).is_ok()
         }
-        (KeyAlgo::RSA, SigAlgo::RsaSSAPSS) => {
-            let pub_key = RsaPublicKey::from_pkcs1_der(pubkey.val).unwrap();
-            let verifying_key: PSSVerifyingKey<Sha384> = PSSVerifyingKey::new(pub_key);
-            let signature = PSSSignature::try_from(sig).unwrap();
-            verifying_key.verify(msg, &signature).is_ok()
+        (KeyAlgo::RSA, SigAlgo::RsaPss { hash, salt_len }) => {
+            let pub_key = RsaPublicKey::from_pkcs1_der(pubkey.val)
+                .map_err(|err| anyhow!("parse verifying key failed: {}", err))?;
+            let signature = PSSSignature::try_from(sig)
+                .map_err(|err| anyhow!("parse pss signature failed: {}", err))?;
+            let salt_len = salt_len as usize;
+            match hash {
+                HashAlgo::SHA256 => PSSVerifyingKey::<Sha256>::new_with_salt_len(pub_key, salt_len)
+                    .verify(msg, &signature)
+                    .is_ok(),
+                HashAlgo::SHA384 => PSSVerifyingKey::<Sha384>::new_with_salt_len(pub_key, salt_len)
+                    .verify(msg, &signature)
+                    .is_ok(),
+                HashAlgo::SHA512 => PSSVerifyingKey::<Sha512>::new_with_salt_len(pub_key, salt_len)
+                    .verify(msg, &signature)
+                    .is_ok(),
+            }
+        }
+        (KeyAlgo::Ed25519, SigAlgo::Ed25519) => {
+            let key_bytes = pubkey
+                .val
+                .try_into()
+                .map_err(|_| anyhow!("invalid ed25519 public key length: {}", pubkey.val.len()))?;
+            let verifying_key = Ed25519VerifyingKey::from_bytes(key_bytes)
+                .map_err(|err| anyhow!("parse verifying key failed: {}", err))?;
+            // EdDSA signatures are the raw 64-byte R || S, no DER wrapping
+            let signature = Ed25519Signature::from_slice(sig)
+                .map_err(|err| anyhow!("parse ed25519 signature failed: {}", err))?;
+            verifying_key.verify_strict(msg, &signature).is_ok()
         }
         _ => {
             return Err(anyhow!(
@@ -42,8 +42,8 @@ function _summary() {
 #echo "Sepolia: $SEPOLIA_RPC_URL"
 
 #cargo build
-#_cli upload --sp1 --out samples/sp1_program_id.json
-#_cli upload --risc0 --out samples/risc0_program_id.json
+#_cli upload --backend sp1 --out samples/sp1_program_id.json
+#_cli upload --backend risc0 --out samples/risc0_program_id.json
 
 #RPC_URL=$HOLESKY_RPC_URL _script 'deployAll(string,string,string)' ../samples/aws_root.der ../samples/sp1_program_id.json ../samples/risc0_program_id.json
 #RPC_URL=$SEPOLIA_RPC_URL _script 'deployAll(string,string,string)' ../samples/aws_root.der ../samples/sp1_program_id.json ../samples/risc0_program_id.json